@@ -24,7 +24,8 @@ fn main() -> Result<(), std::io::Error> {
         Material::glass(2.5),
     ));
 
-    let renderer = Renderer::new(scene, camera);
-    renderer.render().save("box.png")?;
+    let renderer = Renderer::with_settings(scene, camera, TracerCli::parse().settings());
+    let film = renderer.render();
+    renderer.save(&film, "box.png")?;
     Ok(())
 }
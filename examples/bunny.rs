@@ -25,7 +25,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .translate(0.0, -0.65, -1.5),
     );
 
-    let renderer = Renderer::new(scene, camera);
-    renderer.render().save("bunny.png")?;
+    let mut renderer = Renderer::with_settings(scene, camera, TracerCli::parse().settings());
+    renderer.set_denoiser(Denoiser::VarianceGuided(5));
+    let film = renderer.render();
+    renderer.save(&film, "bunny.png")?;
+    renderer.save_denoised(&film, "bunny_denoised.png")?;
     Ok(())
 }
@@ -66,8 +66,9 @@ fn main() -> Result<(), std::io::Error> {
         )
     );
 
-    let renderer = Renderer::new(scene, camera);
-    renderer.render().save("circle.png")?;
+    let renderer = Renderer::with_settings(scene, camera, TracerCli::parse().settings());
+    let film = renderer.render();
+    renderer.save(&film, "circle.png")?;
 
     Ok(())
 }
@@ -30,8 +30,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Material::Light(Texture::Solid(Color::WHITE)),
     ));
 
-    let renderer = Renderer::new(scene, camera);
-    renderer.render().save("conference.png")?;
+    let renderer = Renderer::with_settings(scene, camera, TracerCli::parse().settings());
+    let film = renderer.render();
+    renderer.save(&film, "conference.png")?;
 
     Ok(())
 }
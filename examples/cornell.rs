@@ -18,7 +18,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let scene = parser::scene_from_url(SCENE_URL, SCENE_NAME)?;
 
-    let renderer = Renderer::new(scene, camera);
-    renderer.render().save("cornell.png")?;
+    let renderer = Renderer::with_settings(scene, camera, TracerCli::parse().settings());
+    let film = renderer.render();
+    renderer.save(&film, "cornell.png")?;
     Ok(())
 }
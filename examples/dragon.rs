@@ -28,7 +28,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .translate(0.0, -0.35, -1.4)
     );
 
-    let renderer = Renderer::new(scene, camera);
-    renderer.render().save("dragon.png")?;
+    let renderer = Renderer::with_settings(scene, camera, TracerCli::parse().settings());
+    let film = renderer.render();
+    renderer.save(&film, "dragon.png")?;
     Ok(())
 }
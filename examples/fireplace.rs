@@ -24,8 +24,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Material::Light(Texture::Solid(Color::WHITE)),
     ));
 
-    let renderer = Renderer::new(scene, camera);
-    renderer.render().save("fireplace.png")?;
+    let renderer = Renderer::with_settings(scene, camera, TracerCli::parse().settings());
+    let film = renderer.render();
+    renderer.save(&film, "fireplace.png")?;
 
     Ok(())
 }
@@ -27,7 +27,8 @@ fn main() -> Result<(), png::EncodingError> {
         .translate(0.0, -0.7, -1.5),
     );
 
-    let mut renderer = Renderer::new(scene, camera);
+    let mut renderer = Renderer::with_settings(scene, camera, TracerCli::parse().settings());
     renderer.set_samples(36);
-    renderer.render().save("hello.png")
+    let film = renderer.render();
+    renderer.save(&film, "hello.png")
 }
@@ -179,8 +179,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 	)
     };
 
-    let renderer = Renderer::new(scene, camera);
-    renderer.render().save("nefe.png")?;
+    let renderer = Renderer::with_settings(scene, camera, TracerCli::parse().settings());
+    let film = renderer.render();
+    renderer.save(&film, "nefe.png")?;
 
     Ok(())
 }
@@ -27,7 +27,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .translate(-0.3, -0.5, -1.3),
     );
 
-    let renderer = Renderer::new(scene, camera);
-    renderer.render().save("teapot.png")?;
+    let renderer = Renderer::with_settings(scene, camera, TracerCli::parse().settings());
+    let film = renderer.render();
+    renderer.save(&film, "teapot.png")?;
     Ok(())
 }
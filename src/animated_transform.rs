@@ -0,0 +1,102 @@
+use crate::{Float, Transform, Vec3, error::{self, Error}};
+use glam::DQuat;
+
+#[cfg(test)]
+mod animated_transform_tests;
+
+/// Transformation interpolated between keyframes. Each keyframe gets
+/// decomposed to a scale, rotation and translation. Scales and
+/// translations get interpolated linearly and rotations with slerp.
+/// Before the first and after the last keyframe the transformation
+/// is held constant.
+#[derive(Clone, Default)]
+pub struct AnimatedTransform {
+    /// Keyframes sorted by time
+    keyframes: Vec<Keyframe>,
+}
+
+#[derive(Clone)]
+struct Keyframe {
+    time: Float,
+    scale: Vec3,
+    rotation: DQuat,
+    translation: Vec3,
+}
+
+impl Keyframe {
+    fn new(time: Float, transform: Transform) -> Self {
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+        Self { time, scale, rotation, translation }
+    }
+
+    fn transform(&self) -> Transform {
+        Transform::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+impl AnimatedTransform {
+    /// Adds a keyframe with `transform` at `time`. Replaces an existing
+    /// keyframe at the same time. Fails if `time` is not finite or
+    /// `transform` is not invertible.
+    pub fn add(&mut self, time: Float, transform: Transform) -> Result<(), Error> {
+        error::check(time.is_finite(), || format!(
+            "keyframe time must be finite, got {}", time
+        ))?;
+        let det = transform.matrix3.determinant();
+        error::check(det != 0.0 && det.is_finite() && transform.translation.is_finite(), || {
+            "keyframe transformation must be invertible".to_string()
+        })?;
+
+        let keyframe = Keyframe::new(time, transform);
+        let idx = self.keyframes.partition_point(|k| k.time < time);
+        match self.keyframes.get_mut(idx) {
+            Some(k) if k.time == time => *k = keyframe,
+            _ => self.keyframes.insert(idx, keyframe),
+        }
+        Ok(())
+    }
+
+    /// Does the transformation have no keyframes?
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Is every keyframe a rotation followed by a translation?
+    pub fn is_rigid(&self) -> bool {
+        self.keyframes.iter().all(|k| (k.scale - Vec3::ONE).abs().max_element() < 1e-6)
+    }
+
+    /// Transformation at `time`. Identity if there are no keyframes.
+    pub fn at(&self, time: Float) -> Transform {
+        let idx = self.keyframes.partition_point(|k| k.time <= time);
+        if idx == 0 {
+            return self.keyframes.first().map_or(Transform::IDENTITY, Keyframe::transform);
+        }
+        if idx == self.keyframes.len() {
+            return self.keyframes[idx - 1].transform();
+        }
+
+        let k0 = &self.keyframes[idx - 1];
+        let k1 = &self.keyframes[idx];
+        let s = (time - k0.time) / (k1.time - k0.time);
+        Transform::from_scale_rotation_translation(
+            k0.scale.lerp(k1.scale, s),
+            k0.rotation.slerp(k1.rotation, s),
+            k0.translation.lerp(k1.translation, s),
+        )
+    }
+
+    /// Times at which the transformation needs to be evaluated such that
+    /// consecutive rotations differ by at most `max_angle` radians.
+    /// Includes the time of each keyframe.
+    pub fn sample_times(&self, max_angle: Float) -> Vec<Float> {
+        let mut times: Vec<Float> = self.keyframes.first().map(|k| k.time).into_iter().collect();
+        for pair in self.keyframes.windows(2) {
+            let angle = pair[0].rotation.angle_between(pair[1].rotation);
+            let steps = (angle / max_angle).ceil().max(1.0);
+            let dt = (pair[1].time - pair[0].time) / steps;
+            times.extend((1..=steps as usize).map(|i| pair[0].time + i as Float * dt));
+        }
+        times
+    }
+}
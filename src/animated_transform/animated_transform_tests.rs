@@ -0,0 +1,44 @@
+use super::*;
+use crate::{Point, PI};
+
+fn close(a: Point, b: Point) -> bool {
+    a.distance(b) < 1e-9
+}
+
+#[test]
+fn keyframes_interpolated() {
+    let mut motion = AnimatedTransform::default();
+    assert!(close(motion.at(0.5).transform_point3(Point::X), Point::X));
+
+    motion.add(1.0, Transform::from_translation(Vec3::Y)).unwrap();
+    motion.add(0.0, Transform::IDENTITY).unwrap();
+    assert!(close(motion.at(-1.0).transform_point3(Point::ZERO), Point::ZERO));
+    assert!(close(motion.at(0.25).transform_point3(Point::ZERO), 0.25 * Point::Y));
+    assert!(close(motion.at(2.0).transform_point3(Point::ZERO), Point::Y));
+    assert!(motion.is_rigid());
+}
+
+#[test]
+fn rotations_slerped() {
+    let mut motion = AnimatedTransform::default();
+    motion.add(0.0, Transform::IDENTITY).unwrap();
+    motion.add(1.0, Transform::from_rotation_z(0.5 * PI)).unwrap();
+
+    // the point stays on the unit circle instead of cutting the corner
+    let xo = motion.at(0.5).transform_point3(Point::X);
+    let expected = Point::new(1.0, 1.0, 0.0).normalize();
+    assert!(close(xo, expected), "{} != {}", xo, expected);
+
+    let times = motion.sample_times(0.12 * PI);
+    assert!(times.len() == 6);
+    assert!(times[0] == 0.0 && times[5] == 1.0);
+}
+
+#[test]
+fn keyframes_validated() {
+    let mut motion = AnimatedTransform::default();
+    assert!(motion.add(Float::NAN, Transform::IDENTITY).is_err());
+    assert!(motion.add(0.0, Transform::from_scale(Vec3::ZERO)).is_err());
+    assert!(motion.add(0.0, Transform::from_scale(Vec3::splat(2.0))).is_ok());
+    assert!(!motion.is_rigid());
+}
@@ -1,7 +1,8 @@
+use crate::RenderSettings;
 use crate::tracer::Integrator;
 
 #[derive(argh::FromArgs)]
-/// Optional CLI configuration of renderer. Renderer setter methods have priority.
+/// Optional CLI configuration of the examples. Renderer setter methods have priority.
 pub struct TracerCli {
     /// number of samples per pixel (defaults to 1)
     #[argh(option, short = 's', default = "1")]
@@ -18,17 +19,39 @@ pub struct TracerCli {
     /// use bidirectional path tracing instead of path tracing
     #[argh(switch, short = 'b', long = "bdpt")]
     pub bd_path_trace: bool,
+
+    /// render with sampled wavelengths instead of RGB
+    #[argh(switch)]
+    pub spectral: bool,
+
+    /// seed of the random number generators (defaults to random)
+    #[argh(option)]
+    pub seed: Option<u64>,
 }
 
 impl TracerCli {
-    /// Sets the configured number of threads. Called by renderer on creation.
-    pub fn set_threads(&self) {
-        if let Some(t) = self.threads {
-            rayon::ThreadPoolBuilder::new()
-                .num_threads(t)
-                .build_global()
-                .unwrap();
+    /// Parses the command line arguments of the process. Prints the usage
+    /// and exits the process if they are invalid. Never called by lumo
+    /// itself, opt in with
+    /// `Renderer::with_settings(scene, camera, TracerCli::parse().settings())`.
+    pub fn parse() -> Self {
+        argh::from_env()
+    }
+
+    /// Render settings with the configured samples, threads,
+    /// integrator, spectral rendering and seed
+    pub fn settings(&self) -> RenderSettings {
+        let mut settings = RenderSettings::default()
+            .samples(self.samples)
+            .integrator(self.get_integrator())
+            .spectral(self.spectral);
+        if let Some(threads) = self.threads {
+            settings = settings.threads(threads);
+        }
+        if let Some(seed) = self.seed {
+            settings = settings.seed(seed);
         }
+        settings
     }
 
     /// Get the configured integrator.
@@ -0,0 +1,94 @@
+use png::{DecodingError, EncodingError};
+use rayon::ThreadPoolBuildError;
+use std::{fmt, io};
+
+#[cfg(test)]
+mod error_tests;
+
+/// Errors returned by the fallible `try_` constructors and
+/// other entry points of lumo
+#[derive(Debug)]
+pub enum Error {
+    /// Argument outside of its valid range. Describes the argument,
+    /// the constraint and the given value.
+    InvalidArgument(String),
+    /// The scene has no lights to render with
+    NoLights,
+    /// Reading or writing a file failed
+    Io(io::Error),
+    /// Decoding a .png file failed
+    Decoding(DecodingError),
+    /// The .png file uses a format that is not supported, e.g. grayscale
+    Unsupported(String),
+    /// Parsing an .obj or .mtl file failed
+    Parse(String),
+    /// Encoding a .png file failed
+    Encoding(EncodingError),
+    /// Building the thread pool of the renderer failed
+    ThreadPool(ThreadPoolBuildError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
+            Self::NoLights => write!(f, "Scene has no lights"),
+            Self::Io(err) => write!(f, "I/O error: {}", err),
+            Self::Decoding(err) => write!(f, "Decoding error: {}", err),
+            Self::Unsupported(msg) => write!(f, "Unsupported {}", msg),
+            Self::Parse(msg) => write!(f, "Parse error: {}", msg),
+            Self::Encoding(err) => write!(f, "Encoding error: {}", err),
+            Self::ThreadPool(err) => write!(f, "Thread pool error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Decoding(err) => Some(err),
+            Self::Encoding(err) => Some(err),
+            Self::ThreadPool(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<DecodingError> for Error {
+    fn from(err: DecodingError) -> Self {
+        Self::Decoding(err)
+    }
+}
+
+impl From<EncodingError> for Error {
+    fn from(err: EncodingError) -> Self {
+        Self::Encoding(err)
+    }
+}
+
+impl From<ThreadPoolBuildError> for Error {
+    fn from(err: ThreadPoolBuildError) -> Self {
+        Self::ThreadPool(err)
+    }
+}
+
+/// `Error::InvalidArgument` with `msg` unless `valid`
+pub fn check(valid: bool, msg: impl FnOnce() -> String) -> Result<(), Error> {
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgument(msg()))
+    }
+}
+
+/// Unwraps the result of a `try_` constructor in its panicking counterpart
+pub fn unwrap<T>(result: Result<T, Error>) -> T {
+    result.unwrap_or_else(|err| panic!("{}", err))
+}
@@ -0,0 +1,107 @@
+use super::*;
+use crate::{Image, Float, Point, Vec3, Direction, Transform};
+use crate::tracer::{
+    Camera, Material, Medium, Sphere, Cylinder, Plane, Texture, Color, Spectrum,
+    Projection, FisheyeMapping, Shutter, Instanceable,
+};
+
+fn assert_invalid<T>(result: Result<T, Error>, expected: &str) {
+    match result {
+        Err(Error::InvalidArgument(msg)) => assert!(msg.contains(expected), "{}", msg),
+        Err(err) => panic!("unexpected error {}", err),
+        Ok(_) => panic!("expected error about {}", expected),
+    }
+}
+
+#[test]
+fn objects_validated() {
+    assert!(Sphere::try_new(Point::ZERO, 1.0, Material::Blank).is_ok());
+    assert_invalid(Sphere::try_new(Point::ZERO, 0.0, Material::Blank), "radius");
+    assert_invalid(Sphere::try_new(Point::ZERO, Float::NAN, Material::Blank), "radius");
+    assert_invalid(Cylinder::try_new(-1.0, 1.0, Material::Blank), "height");
+    assert_invalid(Plane::try_new(Point::ZERO, Vec3::ZERO, Material::Blank), "normal");
+    assert_invalid(Medium::try_new(Vec3::splat(2.0), Vec3::ONE, 0.0), "absorption");
+    assert_invalid(Medium::try_new(Vec3::ZERO, Vec3::ONE, 1.0), "scattering parameter");
+    let sphere = || Sphere::new(Point::ZERO, 1.0, Material::Blank);
+    assert!(sphere().try_scale(1.0, 2.0, 3.0).is_ok());
+    assert_invalid(sphere().try_scale(1.0, 0.0, 1.0), "scale");
+    assert_invalid(sphere().translate(1.0, 0.0, 0.0).try_scale(Float::INFINITY, 1.0, 1.0), "scale");
+}
+
+#[test]
+fn materials_validated() {
+    let texture = || Texture::Solid(Color::WHITE);
+    assert!(Material::try_metal(texture(), 0.5, 1.5, 2.0).is_ok());
+    assert_invalid(Material::try_metal(texture(), 1.5, 1.5, 2.0), "roughness");
+    assert_invalid(Material::try_transparent(texture(), 0.5, -1.5), "refraction index");
+    assert_invalid(Material::try_glass(0.0), "refraction index");
+    assert!(Material::try_microfacet_spectral(
+        texture(), 0.1, Spectrum::GOLD_ETA, Spectrum::GOLD_K, false, true,
+    ).is_ok());
+    assert_invalid(Material::try_microfacet_spectral(
+        texture(), 0.1, Spectrum::Tabulated(&[(400.0, 1.5), (700.0, -1.0)]),
+        Spectrum::Constant(0.0), true, true,
+    ), "nm");
+}
+
+#[test]
+fn cameras_validated() {
+    let perspective = |origin: Point, up: Direction, vfov: Float, width: i32| {
+        Camera::try_perspective(origin, Point::NEG_Z, up, vfov, 0.0, 0.0, width, 10)
+    };
+    assert!(perspective(Point::ZERO, Direction::Y, 90.0, 10).is_ok());
+    assert_invalid(perspective(Point::ZERO, Direction::Y, 180.0, 10), "field of view");
+    assert_invalid(perspective(Point::ZERO, Direction::Y, 90.0, 0), "resolution");
+    assert_invalid(perspective(Point::NEG_Z, Direction::Y, 90.0, 10), "origin");
+    assert_invalid(perspective(Point::ZERO, Direction::Z, 90.0, 10), "up");
+    assert_invalid(Camera::try_orthographic(
+        Point::ZERO, Point::NEG_Z, Direction::Y, 0.0, 0.0, 0.0, 10, 10,
+    ), "image plane scale");
+    let panoramic = |projection: Projection| {
+        Camera::try_panoramic(Point::ZERO, Point::NEG_Z, Direction::Y, projection, 10, 10)
+    };
+    assert!(panoramic(Projection::Fisheye(FisheyeMapping::Equisolid, 360.0)).is_ok());
+    assert_invalid(panoramic(Projection::Cylindrical(180.0)), "field of view");
+    assert_invalid(panoramic(Projection::Fisheye(FisheyeMapping::Equidistant, 400.0)), "field of view");
+}
+
+#[test]
+fn motion_validated() {
+    assert!(Shutter::try_new(0.0, 1.0).and_then(|s| s.try_rolling(0.5)).is_ok());
+    assert_invalid(Shutter::try_new(1.0, 0.0), "shutter must close");
+    assert_invalid(Shutter::new(0.0, 1.0).try_rolling(1.0), "readout");
+    let sphere = || Sphere::new(Point::ZERO, 1.0, Material::Blank).translate(0.0, 0.0, -1.0);
+    assert!(sphere().try_keyframe(0.0, Transform::IDENTITY).is_ok());
+    assert_invalid(sphere().try_keyframe(Float::NAN, Transform::IDENTITY), "time");
+    assert_invalid(sphere().try_keyframe(0.0, Transform::from_scale(Vec3::ZERO)), "invertible");
+    let mut camera = Camera::default(10, 10);
+    assert!(camera.try_add_keyframe(0.0, Transform::from_rotation_x(0.1)).is_ok());
+    assert_invalid(camera.try_add_keyframe(1.0, Transform::from_scale(Vec3::splat(2.0))), "scale");
+}
+
+#[test]
+fn unsupported_image() {
+    let file = tempfile::Builder::new().suffix(".png").tempfile().unwrap();
+    {
+        let mut encoder = png::Encoder::new(file.reopen().unwrap(), 1, 1);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.write_header().unwrap().write_image_data(&[128]).unwrap();
+    }
+
+    match Image::from_path(file.path().to_str().unwrap()) {
+        Err(Error::Unsupported(msg)) => assert!(msg == "color type Grayscale", "{}", msg),
+        Err(err) => panic!("unexpected error {}", err),
+        Ok(_) => panic!("expected unsupported color type"),
+    }
+}
+
+#[test]
+fn parse_errors() {
+    let file = tempfile::Builder::new().suffix(".obj").tempfile().unwrap();
+    std::fs::write(file.path(), "v 1.0 x 0.0\n").unwrap();
+
+    let mesh = crate::parser::mesh_from_path(file.path().to_str().unwrap(), Material::Blank);
+    assert!(matches!(mesh, Err(Error::Parse(_))));
+    let mesh = crate::parser::mesh_from_path("not/a/mesh.obj", Material::Blank);
+    assert!(matches!(mesh, Err(Error::Io(_))));
+}
@@ -1,6 +1,7 @@
-use png::{BitDepth, ColorType, Decoder, DecodingError};
+use png::{BitDepth, ColorType, Decoder, Info};
 use std::fs::File;
-use crate::tracer::Color;
+use crate::{Error, Float, Vec3, logging::{log_debug, log_info}};
+use crate::tracer::{Color, ColorSpace, Transfer};
 
 /// Loaded texture images stored in a Rust vector
 #[derive(Clone)]
@@ -15,34 +16,37 @@ pub struct Image {
 
 impl Image {
     /// Creates an `image` struct from a file at `path`
-    pub fn from_path(path: &str) -> Result<Self, DecodingError> {
-        println!("Decoding \"{}\"", path);
+    pub fn from_path(path: &str) -> Result<Self, Error> {
+        log_info!("Decoding \"{}\"", path);
         Self::from_file(File::open(path)?)
     }
 
-    /// Creates an `image` from `file`
-    pub fn from_file(file: File) -> Result<Self, DecodingError> {
+    /// Creates an `image` from `file`. Supports 8 and 16 bit RGB
+    /// and RGBA images.
+    pub fn from_file(file: File) -> Result<Self, Error> {
         let decoder = Decoder::new(file);
         let mut reader = decoder.read_info()?;
+        let (space, transfer) = Self::tagged_encoding(reader.info());
         let mut bytes = vec![0; reader.output_buffer_size()];
         let info = reader.next_frame(&mut bytes)?;
 
-        assert!(info.bit_depth == BitDepth::Eight);
+        let bytes = &bytes[..info.buffer_size()];
+        // 16-bit channels are stored big endian
+        let values: Vec<Float> = match info.bit_depth {
+            BitDepth::Eight => bytes.iter().map(|b| *b as Float / 255.0).collect(),
+            BitDepth::Sixteen => bytes.chunks(2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]) as Float / 65535.0)
+                .collect(),
+            depth => return Err(Error::Unsupported(format!("bit depth {:?}", depth))),
+        };
 
+        let decode = |rgb: &[Float]| {
+            Color::from_encoded(Vec3::new(rgb[0], rgb[1], rgb[2]), space, transfer)
+        };
         let buffer = match info.color_type {
-            ColorType::Rgb => {
-                bytes[..info.buffer_size()]
-                    .chunks(3)
-                    .map(|rgb| Color::new(rgb[0], rgb[1], rgb[2]))
-                    .collect()
-            }
-            ColorType::Rgba => {
-                bytes[..info.buffer_size()]
-                    .chunks(4)
-                    .map(|rgba| Color::new(rgba[0], rgba[1], rgba[2]))
-                    .collect()
-            }
-            _ => panic!("unsupported image type {:?}", info.color_type),
+            ColorType::Rgb => values.chunks(3).map(decode).collect(),
+            ColorType::Rgba => values.chunks(4).map(decode).collect(),
+            color => return Err(Error::Unsupported(format!("color type {:?}", color))),
         };
 
         let width = info.width;
@@ -51,11 +55,53 @@ impl Image {
         // maybe not correct for textures, but we do it anyway
         // assert!(width == height);
         // not correct for textures!
-        println!("Decoded succesfully");
+        log_debug!("Decoded succesfully");
         Ok(Self {
             buffer,
             width,
             height,
         })
     }
+
+    /// Color space and transfer function tagged in the color chunks of
+    /// the image. Untagged images are assumed to be sRGB.
+    fn tagged_encoding(info: &Info) -> (ColorSpace, Transfer) {
+        if info.srgb.is_some() {
+            return (ColorSpace::Srgb, Transfer::Srgb);
+        }
+
+        let transfer = match info.gama_chunk {
+            None => Transfer::Srgb,
+            Some(gamma) => {
+                let gamma = 1.0 / gamma.into_value() as Float;
+                if (gamma - 1.0).abs() < 0.01 {
+                    Transfer::Linear
+                } else if (gamma - 2.2).abs() < 0.01 {
+                    Transfer::Srgb
+                } else {
+                    Transfer::Gamma(gamma)
+                }
+            }
+        };
+
+        // space with the closest primaries
+        let space = match info.chrm_chunk {
+            None => ColorSpace::Srgb,
+            Some(chrm) => {
+                let tagged = [chrm.red, chrm.green, chrm.blue]
+                    .map(|(x, y)| (x.into_value() as Float, y.into_value() as Float));
+                let distance = |space: &ColorSpace| {
+                    space.chromaticities().iter()
+                        .zip(tagged)
+                        .map(|(a, (x, y))| (a.x - x).abs() + (a.y - y).abs())
+                        .sum::<Float>()
+                };
+                ColorSpace::ALL.into_iter()
+                    .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+                    .unwrap_or(ColorSpace::Srgb)
+            }
+        };
+
+        (space, transfer)
+    }
 }
@@ -2,9 +2,14 @@
 #![warn(missing_docs)]
 
 pub use cli::TracerCli;
+pub use error::Error;
 pub use image::Image;
+pub use logging::{LogLevel, Logger, StdoutLogger, set_logger, set_log_level, set_quiet};
 pub use perlin::Perlin;
-pub use renderer::Renderer;
+pub use post_process::{PostEffect, PostProcess};
+pub use renderer::{Renderer, RenderProgress, RenderSettings};
+pub use samplers::{Sampler, SamplerType};
+pub use stats::{HeatMap, RenderStats};
 pub use tone_mapping::ToneMap;
 
 /// Wavefront .mtl and .obj parser
@@ -12,22 +17,32 @@ pub mod parser;
 /// The heart.
 pub mod tracer;
 
+/// Keyframed transformations used for motion blur
+mod animated_transform;
 /// Command line interface
 mod cli;
 /// `Float` with built in tracking of floating point error
 mod efloat;
+/// Error type of the fallible entry points
+mod error;
 /// Wrapper for writing image buffer to file.
 mod image;
+/// Pluggable logging of messages and rendering progress
+mod logging;
 /// Perlin noise generator.
 mod perlin;
+/// Image space effects applied to the resolved image
+mod post_process;
 /// Wrapper around rand. Provides functions to sample from various geometrics.
 mod rand_utils;
 /// Configures and computes the image.
 mod renderer;
-/// Different iterators that stream values sampled from the unit square.
+/// Samplers that generate the random values of each sample.
 mod samplers;
 /// Utility functions when working with vectors in shading space
 mod spherical_utils;
+/// Thread local counters of render statistics
+mod stats;
 /// Tone mapping functions
 mod tone_mapping;
 
@@ -35,7 +50,8 @@ mod tone_mapping;
 /// Implementation of adaptive simpson for numerical integration. Used in tests only.
 mod simpson_integration;
 
-type Transform = glam::DAffine3;
+/// Affine transformation type alias
+pub type Transform = glam::DAffine3;
 type Vec4 = glam::DVec4;
 type Mat4 = glam::DMat4;
 type Vec2 = glam::DVec2;
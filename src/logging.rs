@@ -0,0 +1,156 @@
+use std::{
+    fmt, io::{self, Write},
+    sync::{Arc, RwLock, atomic::{AtomicBool, AtomicU8, Ordering}},
+    time::Duration,
+};
+
+#[cfg(test)]
+mod logging_tests;
+
+/// Severity of a log message. Ordered from the most to the least severe.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Something failed, e.g. saving a checkpoint
+    Error = 0,
+    /// Something looks wrong, e.g. numerical issues during rendering
+    Warn = 1,
+    /// Progress of loading and rendering
+    Info = 2,
+    /// Details useful when debugging
+    Debug = 3,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "ERROR"),
+            Self::Warn => write!(f, "WARN"),
+            Self::Info => write!(f, "INFO"),
+            Self::Debug => write!(f, "DEBUG"),
+        }
+    }
+}
+
+/// Receives the log messages and rendering progress of lumo. Set with
+/// `set_logger`. Only messages at or above the level set with
+/// `set_log_level` get passed to the logger.
+pub trait Logger: Send + Sync {
+    /// Logs `msg` of severity `level`
+    fn log(&self, level: LogLevel, msg: &str);
+
+    /// Reports that `done` of the `total` tiles in the current pass of
+    /// the renderer are finished, `eta` being the estimated time left
+    /// in the whole render. Ignored by default.
+    fn progress(&self, done: usize, total: usize, eta: Duration) {
+        let _ = (done, total, eta);
+    }
+}
+
+/// Default logger. Prints messages to stdout and
+/// draws a progress bar of the tiles.
+pub struct StdoutLogger;
+
+/// Width of the progress bar in characters
+const PROGRESS_BAR_WIDTH: usize = 40;
+
+impl Logger for StdoutLogger {
+    fn log(&self, level: LogLevel, msg: &str) {
+        match level {
+            LogLevel::Info => println!("{}", msg),
+            _ => println!("[{}] {}", level, msg),
+        }
+    }
+
+    fn progress(&self, done: usize, total: usize, eta: Duration) {
+        let filled = PROGRESS_BAR_WIDTH * done / total.max(1);
+        let mut stdout = io::stdout().lock();
+        // redraw the bar on the same line until the pass is done
+        let _ = write!(
+            stdout,
+            "\r[{}{}] {:>3}% ETA {:.1}s ",
+            "#".repeat(filled),
+            ".".repeat(PROGRESS_BAR_WIDTH - filled),
+            100 * done / total.max(1),
+            eta.as_secs_f64(),
+        );
+        let _ = if done == total { writeln!(stdout) } else { stdout.flush() };
+    }
+}
+
+static LOGGER: RwLock<Option<Arc<dyn Logger>>> = RwLock::new(None);
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Sends the log messages and progress of lumo to `logger`
+/// instead of the default `StdoutLogger`
+pub fn set_logger(logger: impl Logger + 'static) {
+    *LOGGER.write().unwrap() = Some(Arc::new(logger));
+}
+
+/// Sets the least severe level of messages logged. Defaults to `Info`.
+pub fn set_log_level(level: LogLevel) {
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Disables all log messages and progress reports if `quiet`
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Are messages of `level` logged
+pub fn enabled(level: LogLevel) -> bool {
+    !QUIET.load(Ordering::Relaxed) && level as u8 <= LOG_LEVEL.load(Ordering::Relaxed)
+}
+
+fn with_logger(f: impl FnOnce(&dyn Logger)) {
+    let logger = LOGGER.read().unwrap().clone();
+    match logger {
+        Some(logger) => f(logger.as_ref()),
+        None => f(&StdoutLogger),
+    }
+}
+
+/// Passes the message to the logger if `level` is enabled.
+/// Use through the macros.
+pub fn log(level: LogLevel, args: fmt::Arguments) {
+    if enabled(level) {
+        with_logger(|logger| logger.log(level, &args.to_string()));
+    }
+}
+
+/// Reports rendering progress to the logger, unless quiet
+pub fn progress(done: usize, total: usize, eta: Duration) {
+    if !QUIET.load(Ordering::Relaxed) {
+        with_logger(|logger| logger.progress(done, total, eta));
+    }
+}
+
+/// Logs an error message
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::LogLevel::Error, format_args!($($arg)*))
+    };
+}
+
+/// Logs a warning
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::LogLevel::Warn, format_args!($($arg)*))
+    };
+}
+
+/// Logs an informative message
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::LogLevel::Info, format_args!($($arg)*))
+    };
+}
+
+/// Logs a debug message
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::LogLevel::Debug, format_args!($($arg)*))
+    };
+}
+
+pub(crate) use {log_error, log_warn, log_info, log_debug};
@@ -0,0 +1,59 @@
+use super::*;
+use std::sync::Mutex;
+
+#[derive(Clone, Default)]
+struct Capture {
+    messages: Arc<Mutex<Vec<(LogLevel, String)>>>,
+    progress: Arc<Mutex<Vec<(usize, usize)>>>,
+}
+
+impl Logger for Capture {
+    fn log(&self, level: LogLevel, msg: &str) {
+        self.messages.lock().unwrap().push((level, msg.to_string()));
+    }
+
+    fn progress(&self, done: usize, total: usize, _eta: Duration) {
+        self.progress.lock().unwrap().push((done, total));
+    }
+}
+
+impl Capture {
+    /// Messages of this test, other tests log concurrently
+    fn logged(&self, tag: &str) -> Vec<(LogLevel, String)> {
+        self.messages.lock().unwrap().iter()
+            .filter(|(_, msg)| msg.starts_with(tag))
+            .cloned()
+            .collect()
+    }
+}
+
+#[test]
+fn levels_and_quiet_mode() {
+    let capture = Capture::default();
+    set_logger(capture.clone());
+
+    log_error!("logging_tests {}", 1);
+    log_info!("logging_tests {}", 2);
+    log_debug!("logging_tests {}", 3);
+    assert!(capture.logged("logging_tests") == vec![
+        (LogLevel::Error, "logging_tests 1".to_string()),
+        (LogLevel::Info, "logging_tests 2".to_string()),
+    ]);
+
+    set_log_level(LogLevel::Warn);
+    log_info!("logging_tests level");
+    log_warn!("logging_tests level");
+    assert!(capture.logged("logging_tests level").len() == 1);
+    set_log_level(LogLevel::Info);
+
+    set_quiet(true);
+    log_error!("logging_tests quiet");
+    progress(1, 2, Duration::ZERO);
+    set_quiet(false);
+    progress(2, 2, Duration::ZERO);
+    assert!(capture.logged("logging_tests quiet").is_empty());
+    assert!(capture.progress.lock().unwrap().contains(&(2, 2)));
+    assert!(!capture.progress.lock().unwrap().contains(&(1, 2)));
+
+    set_logger(StdoutLogger);
+}
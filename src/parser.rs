@@ -1,4 +1,4 @@
-use crate::{Vec2, Vec3, Image, Float, Normal, Point};
+use crate::{Vec2, Vec3, Error, Image, Float, Normal, Point, logging::log_info};
 use crate::tracer::{
     Scene, Material, Texture,
     TriangleMesh, Face, Mesh
@@ -6,7 +6,7 @@ use crate::tracer::{
 use std::fs::File;
 use std::sync::Arc;
 use std::io::{
-    self, BufRead, BufReader, Result,
+    self, BufRead, BufReader,
     Cursor, Read, Seek, Write
 };
 use std::collections::HashMap;
@@ -23,9 +23,17 @@ mod mtl;
  * BEWARE WHO ENTERS! HERE BE DRAGONS!
  */
 
-/// Function to create io::Error
-fn obj_error(message: &str) -> io::Error {
-    io::Error::new(io::ErrorKind::InvalidData, message)
+/// Result of the parsers
+type Result<T> = std::result::Result<T, Error>;
+
+/// Function to create `Error::Parse`
+fn obj_error(message: &str) -> Error {
+    Error::Parse(message.to_string())
+}
+
+/// `Error::InvalidArgument` with `message`
+fn invalid(message: &str) -> Error {
+    Error::InvalidArgument(message.to_string())
 }
 
 /// For .obj and .mtl parsers
@@ -74,7 +82,7 @@ fn _get_url(url: &str) -> Result<Vec<u8>> {
 
     ureq::get(url)
         .call()
-        .map_err(|_| obj_error("Error during HTTP, error parsing not implemented"))?
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?
         .into_reader()
         .read_to_end(&mut bytes)?;
 
@@ -83,15 +91,15 @@ fn _get_url(url: &str) -> Result<Vec<u8>> {
 
 /// Extracts file matching `re` from zip file in `bytes`
 fn _extract_zip(bytes: Vec<u8>, re: Regex) -> Result<Vec<u8>> {
-    println!("Reading .zip");
-    let mut zip = ZipArchive::new(Cursor::new(bytes))?;
+    log_info!("Reading .zip");
+    let mut zip = ZipArchive::new(Cursor::new(bytes)).map_err(io::Error::from)?;
     let mut data = Vec::new();
 
     for i in 0..zip.len() {
-        let mut file = zip.by_index(i)?;
+        let mut file = zip.by_index(i).map_err(io::Error::from)?;
 
         if re.is_match(file.name()) {
-            println!("Extracting \"{}\"", file.name());
+            log_info!("Extracting \"{}\"", file.name());
             file.read_to_end(&mut data)?;
             break;
         }
@@ -104,6 +112,13 @@ fn _extract_zip(bytes: Vec<u8>, re: Regex) -> Result<Vec<u8>> {
     }
 }
 
+/// Regex matching files called `name` in a zip archive
+fn _name_regex(name: &str) -> Result<Regex> {
+    Regex::new(name).map_err(|err| Error::InvalidArgument(
+        format!("bad file name \"{}\": {}", name, err)
+    ))
+}
+
 /// Maps `Vec<u8>` to `File`
 fn _bytes_to_file(bytes: Vec<u8>) -> Result<File> {
     let mut tmp_file = tempfile::tempfile()?;
@@ -116,31 +131,30 @@ fn _bytes_to_file(bytes: Vec<u8>) -> Result<File> {
 
 /// Loads `tex_name` from `zip` to an `Image`
 fn _img_from_zip(zip: Vec<u8>, tex_name: &str) -> Result<Image> {
-    let file_bytes = _extract_zip(zip, Regex::new(tex_name).unwrap())?;
+    let file_bytes = _extract_zip(zip, _name_regex(tex_name)?)?;
     let file = _bytes_to_file(file_bytes)?;
 
-    println!("Decoding texture");
+    log_info!("Decoding texture");
     Image::from_file(file)
-        .map_err(|decode_error| obj_error(&decode_error.to_string()))
 }
 
 /// Loads a .OBJ file at the given path
 pub fn mesh_from_path(path: &str, material: Material) -> Result<Mesh> {
-    println!("Loading .OBJ file \"{}\"", path);
+    log_info!("Loading .OBJ file \"{}\"", path);
     obj::load_file(File::open(path)?, material)
 }
 
 /// Loads .OBJ file from resource at an URL. Supports direct .OBJ files and
 /// .OBJ files within a zip archive.
 pub fn mesh_from_url(url: &str, material: Material) -> Result<Mesh> {
-    println!("Loading .OBJ from \"{}\"", url);
+    log_info!("Loading .OBJ from \"{}\"", url);
     let mut bytes = _get_url(url)?;
 
     if url.ends_with(".zip") {
-        println!("Found zip archive, searching for .OBJ files");
+        log_info!("Found zip archive, searching for .OBJ files");
         bytes = _extract_zip(bytes, Regex::new(r".+\.obj$").unwrap())?;
     } else if !url.ends_with(".obj") {
-        return Err(obj_error(
+        return Err(invalid(
             "Bad URL, or at least does not end with .zip or .obj",
         ));
     }
@@ -152,13 +166,13 @@ pub fn mesh_from_url(url: &str, material: Material) -> Result<Mesh> {
 /// Loads `tex_name` from .zip at `url`
 pub fn texture_from_url(url: &str, tex_name: &str) -> Result<Image> {
     if !tex_name.ends_with(".png") {
-        return Err(obj_error("Can only load .png files"));
+        return Err(invalid("Can only load .png files"));
     }
     if !url.ends_with(".zip") {
-        return Err(obj_error("Can only extract textures from zip archives"));
+        return Err(invalid("Can only extract textures from zip archives"));
     }
 
-    println!("Loading texture \"{}\" from \"{}\"", tex_name, url);
+    log_info!("Loading texture \"{}\" from \"{}\"", tex_name, url);
 
     let resp = _get_url(url)?;
 
@@ -170,17 +184,17 @@ pub fn texture_from_url(url: &str, tex_name: &str) -> Result<Image> {
 #[allow(clippy::single_match)]
 pub fn scene_from_url(url: &str, obj_name: &str) -> Result<Scene> {
     if !url.ends_with(".zip") {
-        return Err(obj_error("Can only load scenes from .zip"));
+        return Err(invalid("Can only load scenes from .zip"));
     }
     if !obj_name.ends_with(".obj") {
-        return Err(obj_error("Can only parse .obj files"));
+        return Err(invalid("Can only parse .obj files"));
     }
 
-    println!("Loading scene \"{}\" from \"{}\"", obj_name, url);
+    log_info!("Loading scene \"{}\" from \"{}\"", obj_name, url);
 
     let resp = _get_url(url)?;
 
-    let obj_bytes = _extract_zip(resp.clone(), Regex::new(obj_name).unwrap())?;
+    let obj_bytes = _extract_zip(resp.clone(), _name_regex(obj_name)?)?;
 
     let obj_file = _bytes_to_file(obj_bytes.clone())?;
 
@@ -197,7 +211,7 @@ pub fn scene_from_url(url: &str, obj_name: &str) -> Result<Scene> {
         match tokens[0] {
             "mtllib" => {
                 let mtllib_name = tokens[1];
-                let mtl_bytes = _extract_zip(resp.clone(), Regex::new(mtllib_name).unwrap())?;
+                let mtl_bytes = _extract_zip(resp.clone(), _name_regex(mtllib_name)?)?;
                 let mtl_file = _bytes_to_file(mtl_bytes)?;
 
                 mtl::load_file(mtl_file, Some(resp.clone()), &mut materials)?;
@@ -1,5 +1,5 @@
 use super::*;
-use crate::tracer::Color;
+use crate::tracer::{Color, ColorSpace};
 
 /// Holds the properties of a microfacet material
 #[allow(non_snake_case)]
@@ -96,7 +96,7 @@ pub fn load_file(
             /* diffuse color */
             "Kd" => {
                 let kd = parse_vec3(&tokens)?;
-                mtl.Kd = Color::from(kd);
+                mtl.Kd = Color::from_linear(kd, ColorSpace::Srgb);
             }
             /* texture map */
             "map_Kd" => {
@@ -109,12 +109,12 @@ pub fn load_file(
             /* emission color */
             "Ke" => {
                 let ke = parse_vec3(&tokens)?;
-                mtl.Ke = Color::from(ke);
+                mtl.Ke = Color::from_linear(ke, ColorSpace::Srgb);
             }
             /* specular color */
             "Ks" => {
                 let ks = parse_vec3(&tokens)?;
-                mtl.Ks = Color::from(ks);
+                mtl.Ks = Color::from_linear(ks, ColorSpace::Srgb);
             }
             /* transmission filter */
             "Tf" => {
@@ -21,18 +21,24 @@ pub struct Perlin {
 
 impl Default for Perlin {
     fn default() -> Self {
-        Self {
+        Self::new(0)
+    }
+}
+
+impl Perlin {
+    /// Creates a Perlin noise generator with random lattice and
+    /// permutations generated from `seed`
+    pub fn new(seed: u64) -> Self {
+        rand_utils::with_seed(seed, || Self {
             lattice: rand_utils::rand_vec_vec3(PERLIN_POINTS),
             perm: PermutationXyz {
                 x: rand_utils::perm_n(PERLIN_POINTS),
                 y: rand_utils::perm_n(PERLIN_POINTS),
                 z: rand_utils::perm_n(PERLIN_POINTS),
             },
-        }
+        })
     }
-}
 
-impl Perlin {
     /// Computes Perlin noise at point `p`
     pub fn noise_at(&self, p: Vec3) -> Float {
         let weight = p.fract();
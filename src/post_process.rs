@@ -0,0 +1,227 @@
+use crate::{Float, Vec3, rand_utils, ToneMap};
+use crate::tracer::{Color, ColorSpace};
+use glam::IVec2;
+
+#[cfg(test)]
+mod post_process_tests;
+
+/// Color temperature of the reference white. White balancing to this
+/// temperature leaves the image unchanged.
+const REFERENCE_TEMPERATURE: Float = 6504.0;
+
+/// Image space effects applied to the resolved linear image in the working
+/// space of the film
+#[derive(Clone, Copy, Debug)]
+pub enum PostEffect {
+    /// Scales the image by `2^ev` where the parameter is `ev`
+    Exposure(Float),
+    /// Balances the image shot under an illuminant of the given color
+    /// temperature in Kelvin, such that the illuminant appears white.
+    /// `6504.0` leaves the image unchanged.
+    WhiteBalance(Float),
+    /// Glow around bright pixels. Light above luminance `threshold` gets
+    /// blurred with a Gaussian of `radius` in pixels and added back scaled
+    /// by `intensity`. Parameters in order `threshold`, `intensity`, `radius`.
+    Bloom(Float, Float, Float),
+    /// Darkens the image towards the corners. Parameter is the fraction
+    /// of light lost in the corners.
+    Vignette(Float),
+    /// Multiplicative noise imitating film grain with the given strength.
+    /// Same for each render of the image with the same seed.
+    FilmGrain(Float),
+}
+
+impl PostEffect {
+    /// Applies the effect to `pixels` of an image of `resolution` in the
+    /// color `space`. `seed` varies the noise of film grain.
+    pub fn apply(
+        &self,
+        resolution: IVec2,
+        pixels: &mut [Color],
+        seed: u64,
+        space: ColorSpace,
+    ) {
+        match self {
+            Self::Exposure(ev) => {
+                let scale = ev.exp2();
+                pixels.iter_mut().for_each(|c| *c = *c * scale);
+            }
+            Self::WhiteBalance(temperature) => {
+                let gains = white_balance_gains(*temperature, space);
+                pixels.iter_mut().for_each(|c| *c *= gains);
+            }
+            Self::Bloom(threshold, intensity, radius) => {
+                bloom(resolution, pixels, *threshold, *intensity, *radius, space);
+            }
+            Self::Vignette(strength) => {
+                let center = resolution.as_dvec2() / 2.0;
+                let max_dist2 = center.length_squared().max(crate::EPSILON);
+                for (idx, c) in pixels.iter_mut().enumerate() {
+                    let x = (idx as i32 % resolution.x) as Float + 0.5;
+                    let y = (idx as i32 / resolution.x) as Float + 0.5;
+                    let dist2 = (glam::DVec2::new(x, y) - center).length_squared();
+                    *c = *c * (1.0 - strength * dist2 / max_dist2).max(0.0);
+                }
+            }
+            Self::FilmGrain(strength) => {
+                let seed = rand_utils::hash(seed);
+                for (idx, c) in pixels.iter_mut().enumerate() {
+                    let h = rand_utils::hash(seed ^ idx as u64);
+                    let u = (h >> 11) as Float / (1u64 << 53) as Float;
+                    *c = *c * (1.0 + strength * (2.0 * u - 1.0));
+                }
+            }
+        }
+    }
+}
+
+/// Chain of post effects followed by tone mapping. Turns the resolved linear
+/// radiance of the film to the image that gets saved to low dynamic range
+/// formats.
+#[derive(Clone)]
+pub struct PostProcess {
+    effects: Vec<PostEffect>,
+    tone_map: ToneMap,
+    /// Seed of the noise in the effects
+    seed: u64,
+}
+
+impl Default for PostProcess {
+    fn default() -> Self {
+        Self { effects: vec![], tone_map: ToneMap::NoMap, seed: 0 }
+    }
+}
+
+impl PostProcess {
+    /// Applies `effects` in order and then `tone_map`
+    pub fn new(effects: &[PostEffect], tone_map: ToneMap) -> Self {
+        Self { effects: effects.to_vec(), tone_map, seed: 0 }
+    }
+
+    /// Effects of the chain in the order they get applied
+    pub fn effects(&self) -> &[PostEffect] {
+        &self.effects
+    }
+
+    /// Tone mapping applied after the effects
+    pub fn tone_map(&self) -> &ToneMap {
+        &self.tone_map
+    }
+
+    /// Sets the tone mapping applied after the effects
+    pub fn set_tone_map(&mut self, tone_map: ToneMap) {
+        self.tone_map = tone_map;
+    }
+
+    /// Sets the effects applied in order
+    pub fn set_effects(&mut self, effects: &[PostEffect]) {
+        self.effects = effects.to_vec();
+    }
+
+    /// Sets the seed of the noise in the effects, e.g. film grain
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Processes linear `pixels` of an image of `resolution` in the
+    /// color `space`
+    pub fn apply(&self, resolution: IVec2, pixels: &[Color], space: ColorSpace) -> Vec<Color> {
+        let mut pixels = pixels.to_vec();
+        for effect in &self.effects {
+            effect.apply(resolution, &mut pixels, self.seed, space);
+        }
+        // negative lobes of filters can leave pixels slightly negative
+        pixels.iter()
+            .map(|c| self.tone_map.map(Color::from(c.rgb.max(Vec3::ZERO)), space))
+            .collect()
+    }
+}
+
+/// Linear RGB color in `space` of a black body of `temperature` Kelvin with
+/// unit luminance. Uses the cubic spline fit of the Planckian locus by Kim et al.
+fn blackbody_color(temperature: Float, space: ColorSpace) -> Color {
+    let t = temperature.clamp(1667.0, 25000.0);
+    let (t2, t3) = (t * t, t * t * t);
+    let x = if t <= 4000.0 {
+        -0.2661239e9 / t3 - 0.2343589e6 / t2 + 0.8776956e3 / t + 0.179910
+    } else {
+        -3.0258469e9 / t3 + 2.1070379e6 / t2 + 0.2226347e3 / t + 0.240390
+    };
+    let (x2, x3) = (x * x, x * x * x);
+    let y = if t <= 2222.0 {
+        -1.1063814 * x3 - 1.34811020 * x2 + 2.18555832 * x - 0.20219683
+    } else if t <= 4000.0 {
+        -0.9549476 * x3 - 1.37418593 * x2 + 2.09137015 * x - 0.16748867
+    } else {
+        3.0817580 * x3 - 5.87338670 * x2 + 3.75112997 * x - 0.37001483
+    };
+
+    // colors are adapted from sRGB to the working space, do the same to
+    // the black body such that the reference temperature stays white
+    let xyz = Vec3::new(x / y, 1.0, (1.0 - x - y) / y);
+    let srgb = ColorSpace::Srgb.rgb_to_xyz().inverse() * xyz;
+    let rgb = ColorSpace::conversion(ColorSpace::Srgb, space) * srgb;
+
+    Color::from(rgb.max(Vec3::ZERO))
+}
+
+/// Per channel gains that map the color of an illuminant of `temperature`
+/// to the reference white while keeping luminance in `space`
+fn white_balance_gains(temperature: Float, space: ColorSpace) -> Color {
+    let illuminant = blackbody_color(temperature, space);
+    let reference = blackbody_color(REFERENCE_TEMPERATURE, space);
+    let gains = reference.rgb / illuminant.rgb.max(Vec3::splat(crate::EPSILON));
+    let gains = Color::from(gains);
+
+    gains / gains.luminance_in(space)
+}
+
+/// Blurs the light above luminance `threshold` in `space` with a separable
+/// Gaussian and adds it back to `pixels` scaled by `intensity`
+fn bloom(
+    resolution: IVec2,
+    pixels: &mut [Color],
+    threshold: Float,
+    intensity: Float,
+    radius: Float,
+    space: ColorSpace,
+) {
+    let bright: Vec<Color> = pixels.iter()
+        .map(|c| {
+            let lum = c.luminance_in(space);
+            if lum <= threshold {
+                Color::BLACK
+            } else {
+                *c * ((lum - threshold) / lum)
+            }
+        })
+        .collect();
+
+    let r = radius.ceil().max(1.0) as i32;
+    let sigma = (radius / 3.0).max(0.5);
+    let kernel: Vec<Float> = (-r..=r)
+        .map(|i| (-0.5 * (i * i) as Float / (sigma * sigma)).exp())
+        .collect();
+    let kernel_sum: Float = kernel.iter().sum();
+    let kernel: Vec<Float> = kernel.iter().map(|k| k / kernel_sum).collect();
+
+    // energy that would go outside the image is dropped
+    let blur = |src: &[Color], step: IVec2| -> Vec<Color> {
+        (0..src.len()).map(|idx| {
+            let xy = IVec2::new(idx as i32 % resolution.x, idx as i32 / resolution.x);
+            let mut sum = Color::BLACK;
+            for (k, i) in kernel.iter().zip(-r..=r) {
+                let p = xy + step * i;
+                if p.cmpge(IVec2::ZERO).all() && p.cmplt(resolution).all() {
+                    sum += src[(p.x + p.y * resolution.x) as usize] * *k;
+                }
+            }
+            sum
+        }).collect()
+    };
+
+    let blurred = blur(&blur(&bright, IVec2::X), IVec2::Y);
+    pixels.iter_mut()
+        .zip(blurred)
+        .for_each(|(c, b)| *c += b * intensity);
+}
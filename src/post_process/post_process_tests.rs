@@ -0,0 +1,138 @@
+use super::*;
+
+const RES: IVec2 = IVec2::new(9, 7);
+
+fn flat(value: Float) -> Vec<Color> {
+    vec![Color::splat(value); (RES.x * RES.y) as usize]
+}
+
+fn apply(effect: PostEffect, pixels: &[Color]) -> Vec<Color> {
+    let mut pixels = pixels.to_vec();
+    effect.apply(RES, &mut pixels, 0, ColorSpace::Srgb);
+    pixels
+}
+
+fn total(pixels: &[Color]) -> Float {
+    pixels.iter().map(|c| c.luminance()).sum()
+}
+
+#[test]
+fn exposure_in_stops() {
+    let out = apply(PostEffect::Exposure(1.0), &flat(0.25));
+    assert!(out.iter().all(|c| (c.rgb - 0.5).abs().max_element() < 1e-10));
+    let out = apply(PostEffect::Exposure(-2.0), &flat(0.25));
+    assert!(out.iter().all(|c| (c.rgb - 0.0625).abs().max_element() < 1e-10));
+}
+
+#[test]
+fn white_balance_reference_unchanged() {
+    let out = apply(PostEffect::WhiteBalance(REFERENCE_TEMPERATURE), &flat(0.5));
+    assert!(out.iter().all(|c| (c.rgb - 0.5).abs().max_element() < 1e-10));
+}
+
+#[test]
+fn white_balance_neutralizes_illuminant() {
+    for temperature in [2700.0, 4000.0, 9000.0] {
+        let illuminant = blackbody_color(temperature, ColorSpace::Srgb);
+        let reference = blackbody_color(REFERENCE_TEMPERATURE, ColorSpace::Srgb);
+        let mut pixels = vec![illuminant];
+        PostEffect::WhiteBalance(temperature).apply(IVec2::ONE, &mut pixels, 0, ColorSpace::Srgb);
+        let balanced = pixels[0].rgb / reference.rgb;
+        assert!((balanced - balanced.x).abs().max_element() < 1e-6);
+    }
+}
+
+#[test]
+fn warm_illuminants_are_red() {
+    let warm = blackbody_color(2700.0, ColorSpace::Srgb);
+    let cool = blackbody_color(10000.0, ColorSpace::Srgb);
+    assert!(warm.rgb.x > warm.rgb.z);
+    assert!(cool.rgb.z > cool.rgb.x);
+}
+
+#[test]
+fn bloom_spreads_light_above_threshold() {
+    let mut pixels = flat(0.5);
+    let center = (4 + 3 * RES.x) as usize;
+    pixels[center] = Color::splat(11.0);
+    let out = apply(PostEffect::Bloom(1.0, 0.5, 2.0), &pixels);
+
+    // neighbours get brighter, total grows by intensity times the excess
+    assert!(out[center + 1].luminance() > 0.5);
+    assert!(out[center - RES.x as usize].luminance() > 0.5);
+    let added = total(&out) - total(&pixels);
+    assert!((added - 0.5 * 10.0).abs() < 1e-6);
+
+    let out = apply(PostEffect::Bloom(1.0, 0.5, 2.0), &flat(0.5));
+    assert!((total(&out) - total(&flat(0.5))).abs() < 1e-10);
+}
+
+#[test]
+fn vignette_darkens_corners() {
+    let out = apply(PostEffect::Vignette(0.5), &flat(1.0));
+    let center = out[(4 + 3 * RES.x) as usize].luminance();
+    let corner = out[0].luminance();
+    assert!(center > 0.99);
+    assert!(corner < center);
+    assert!(corner > 0.5);
+}
+
+#[test]
+fn film_grain_deterministic_and_unbiased() {
+    let res = IVec2::new(64, 64);
+    let mut a = vec![Color::splat(0.5); 64 * 64];
+    let mut b = a.clone();
+    let mut c = a.clone();
+    PostEffect::FilmGrain(0.2).apply(res, &mut a, 1, ColorSpace::Srgb);
+    PostEffect::FilmGrain(0.2).apply(res, &mut b, 1, ColorSpace::Srgb);
+    PostEffect::FilmGrain(0.2).apply(res, &mut c, 2, ColorSpace::Srgb);
+    assert!(a.iter().zip(&b).all(|(a, b)| a.rgb == b.rgb));
+    // different seeds give different grain
+    assert!(a.iter().zip(&c).any(|(a, c)| a.rgb != c.rgb));
+
+    let mean = total(&a) / a.len() as Float;
+    assert!((mean - 0.5).abs() < 0.01);
+    assert!(a.iter().any(|c| (c.luminance() - 0.5).abs() > 0.05));
+
+    let mut black = vec![Color::BLACK; 4];
+    PostEffect::FilmGrain(0.2).apply(IVec2::new(2, 2), &mut black, 1, ColorSpace::Srgb);
+    assert!(black.iter().all(|c| c.is_black()));
+}
+
+#[test]
+fn tone_map_after_effects() {
+    let post = PostProcess::new(&[PostEffect::Exposure(1.0)], ToneMap::Clamp);
+    let out = post.apply(RES, &flat(0.75), ColorSpace::Srgb);
+    assert!(out.iter().all(|c| (c.rgb - 1.0).abs().max_element() < 1e-10));
+}
+
+#[test]
+fn effects_in_working_space() {
+    // AP1 green has lower luminance than Rec. 709 green
+    let space = ColorSpace::AcesCg;
+    let green = Color::from(Vec3::new(0.0, 1.45, 0.0));
+    assert!(green.luminance() > 1.0 && green.luminance_in(space) < 1.0);
+    let mut pixels = vec![green; (RES.x * RES.y) as usize];
+    PostEffect::Bloom(1.0, 0.5, 2.0).apply(RES, &mut pixels, 0, space);
+    assert!(pixels.iter().all(|c| c.rgb == green.rgb));
+
+    // reference white stays white and gains keep the luminance
+    let mut pixels = flat(0.5);
+    PostEffect::WhiteBalance(REFERENCE_TEMPERATURE).apply(RES, &mut pixels, 0, space);
+    assert!(pixels.iter().all(|c| (c.rgb - 0.5).abs().max_element() < 1e-10));
+    let gains = white_balance_gains(3000.0, space);
+    assert!((gains.luminance_in(space) - 1.0).abs() < 1e-10);
+    assert!(gains.luminance() != 1.0);
+
+    // illuminants match the ones converted from sRGB
+    let warm = blackbody_color(3000.0, ColorSpace::Srgb);
+    let expected = ColorSpace::conversion(ColorSpace::Srgb, space) * warm.rgb;
+    assert!((blackbody_color(3000.0, space).rgb - expected).abs().max_element() < 1e-10);
+
+    // white has unit luminance in each space
+    let post = PostProcess::new(&[], ToneMap::Reinhard);
+    for space in ColorSpace::ALL {
+        let out = post.apply(RES, &flat(1.0), space);
+        assert!(out.iter().all(|c| (c.rgb - 0.5).abs().max_element() < 1e-6));
+    }
+}
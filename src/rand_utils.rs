@@ -1,20 +1,43 @@
 use crate::{Vec2, Vec3, Float};
 use rand::{
     prelude::SliceRandom,
-    rngs::ThreadRng, Rng
+    rngs::StdRng, Rng, SeedableRng
 };
+use std::cell::RefCell;
 
-type MyRng = ThreadRng;
-fn _get_rng() -> MyRng {
-    rand::thread_rng()
+#[cfg(test)]
+mod rand_utils_tests;
+
+type MyRng = StdRng;
+
+thread_local! {
+    static RNG: RefCell<MyRng> = RefCell::new(MyRng::from_entropy());
+}
+
+fn _with_rng<T>(f: impl FnOnce(&mut MyRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
 }
 
-/* should figure better way to rng creation??
- * (thread_rng() always creates new?) */
+/// Calls `f` with the random number generator of the current thread
+/// seeded with `seed`. Restores the previous state of the generator after.
+pub fn with_seed<T>(seed: u64, f: impl FnOnce() -> T) -> T {
+    let prev = RNG.with(|rng| rng.replace(MyRng::seed_from_u64(seed)));
+    let res = f();
+    RNG.with(|rng| rng.replace(prev));
+    res
+}
+
+/// Mixes the bits of `x`. splitmix64 finalizer
+pub fn hash(x: u64) -> u64 {
+    let mut h = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^ (h >> 31)
+}
 
 /// Random Float
 pub fn rand_float() -> Float {
-    _get_rng().gen()
+    _with_rng(|rng| rng.gen())
 }
 
 /// return `n` normalized random Vec3s in a vector
@@ -25,7 +48,7 @@ pub fn rand_vec_vec3(n: usize) -> Vec<Vec3> {
 /// Random permutation of 0..n
 pub fn perm_n(n: usize) -> Vec<usize> {
     let mut v: Vec<usize> = (0..n).collect();
-    v.shuffle(&mut _get_rng());
+    _with_rng(|rng| v.shuffle(rng));
     v
 }
 
@@ -0,0 +1,30 @@
+use super::*;
+
+#[test]
+fn seeded_sequences_repeat() {
+    let draw = |seed: u64| with_seed(seed, || (0..16).map(|_| rand_float()).collect::<Vec<_>>());
+
+    assert!(draw(42) == draw(42));
+    assert!(draw(42) != draw(43));
+}
+
+#[test]
+fn with_seed_restores_state() {
+    let (a, b) = with_seed(7, || {
+        let a = with_seed(8, || (0..4).map(|_| rand_float()).collect::<Vec<_>>());
+        let b = with_seed(8, || (0..4).map(|_| rand_float()).collect::<Vec<_>>());
+        assert!(a == b);
+        (rand_float(), rand_float())
+    });
+
+    assert!(with_seed(7, || (rand_float(), rand_float())) == (a, b));
+}
+
+#[test]
+fn hash_mixes_bits() {
+    assert!(hash(0) != 0);
+    assert!(hash(1) != hash(2));
+    // flipping one input bit flips roughly half of the output bits
+    let flipped = (hash(7) ^ hash(7 ^ 1)).count_ones();
+    assert!((16..=48).contains(&flipped));
+}
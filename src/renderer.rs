@@ -1,145 +1,742 @@
 use crate::{
-    Vec2, Float, TracerCli,
-    samplers::JitteredSampler, ToneMap
+    Vec2, Float, stats::{self, Counter}, HeatMap, RenderStats,
+    error::{self, Error},
+    logging::{self, log_error, log_info},
+    samplers::SamplerType, ToneMap, PostEffect
 };
 use crate::tracer::{
-    Camera, Film, FilmSample,
-    Integrator, Scene, Filter, FilmTile
+    Camera, Color, ColorSpace, Film, FilmSample, Aov, Denoiser, SampledWavelengths,
+    Integrator, Scene, Filter, FilterSampler, FilmTile
 };
 use glam::IVec2;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use std::{sync::Mutex, time::Instant};
+use png::EncodingError;
+use preview::Preview;
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use std::{collections::BTreeMap, io, mem, sync::mpsc, thread, time::{Duration, Instant}};
 
-type PxSampler = JitteredSampler;
+pub use settings::RenderSettings;
+
+/// Snapshots of progressive renders written to disk
+mod preview;
+/// Builder of the configuration of a render
+mod settings;
 
 const TILE_SIZE: i32 = 16;
-const SAMPLES_INCREMENT: i32 = 256;
+/// Samples a pixel needs before adaptive sampling trusts its error estimate
+const MIN_ADAPTIVE_SAMPLES: u32 = 16;
+
+/// Progress of a render. Given to the callback of progressive rendering
+/// after each pass.
+pub struct RenderProgress {
+    /// Number of passes finished
+    pub passes: i32,
+    /// Samples per pixel taken so far. With adaptive sampling
+    /// the number of samples taken in the least converged pixels.
+    pub samples_taken: i32,
+    /// Maximum number of samples per pixel to take
+    pub num_samples: i32,
+    /// Time elapsed since the start of the render
+    pub elapsed: Duration,
+    /// Estimate of the noise left in the image. Mean estimated relative
+    /// error of pixel luminance, `None` if less than two samples per pixel
+    /// have been taken.
+    pub noise: Option<Float>,
+    /// Estimate of the time left until `num_samples` samples have been
+    /// taken or the time budget runs out
+    pub eta: Duration,
+    /// Camera rays traced per second since the start of the render
+    pub rays_per_second: Float,
+}
 
 /// Configures the image to be rendered
 pub struct Renderer {
     scene: Scene,
     camera: Camera,
     resolution: IVec2,
-    num_samples: i32,
-    integrator: Integrator,
-    tone_map: ToneMap,
-    filter: Filter,
+    settings: RenderSettings,
+    pool: ThreadPool,
+    filter_sampler: Option<FilterSampler>,
+    preview: Option<Preview>,
 }
 
 impl Renderer {
-    /// Constructs a new renderer. Defaults to 1000x1000 image with 1 sample
-    /// per pixel and path tracing as the integrator. Configured through the CLI
-    /// or the setter functions of the struct.
+    /// Constructs a new renderer with the default `RenderSettings`, i.e.
+    /// 1 sample per pixel and path tracing as the integrator. Configured
+    /// through the setter functions of the struct.
     pub fn new(scene: Scene, camera: Camera) -> Self {
-        assert!(scene.num_lights() != 0);
+        error::unwrap(Self::try_new(scene, camera))
+    }
 
-        let cli_args: TracerCli = argh::from_env();
-        cli_args.set_threads();
+    /// Fallible `new`. Fails if the scene has no lights.
+    pub fn try_new(scene: Scene, camera: Camera) -> Result<Self, Error> {
+        Self::try_with_settings(scene, camera, RenderSettings::default())
+    }
+
+    /// Constructs a new renderer configured by `settings`. The renderer
+    /// owns a thread pool of `settings.threads` threads.
+    pub fn with_settings(scene: Scene, camera: Camera, settings: RenderSettings) -> Self {
+        error::unwrap(Self::try_with_settings(scene, camera, settings))
+    }
+
+    /// Fallible `with_settings`. Fails if the scene has no lights, the
+    /// settings are invalid or the thread pool can not be built.
+    pub fn try_with_settings(
+        scene: Scene,
+        camera: Camera,
+        settings: RenderSettings,
+    ) -> Result<Self, Error> {
+        if scene.num_lights() == 0 {
+            return Err(Error::NoLights);
+        }
 
         let resolution = camera.get_resolution();
+        settings.validate(resolution)?;
+        let pool = Self::build_pool(settings.threads)?;
+        let filter_sampler = settings.filter_importance_sampling
+            .then(|| FilterSampler::new(settings.filter));
+        let preview = settings.preview.as_deref().map(Preview::new);
 
-        Self {
+        Ok(Self {
             scene,
             camera,
             resolution,
-            filter: Filter::Box,
-            num_samples: cli_args.samples,
-            integrator: cli_args.get_integrator(),
-            tone_map: ToneMap::NoMap,
-        }
+            settings,
+            pool,
+            filter_sampler,
+            preview,
+        })
+    }
+
+    fn build_pool(threads: Option<usize>) -> Result<ThreadPool, Error> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads.unwrap_or(0))
+            .thread_name(|i| format!("lumo-{}", i))
+            .build()?;
+        Ok(pool)
+    }
+
+    /// Applies `f` to the settings of the renderer
+    fn configure(&mut self, f: impl FnOnce(RenderSettings) -> RenderSettings) {
+        self.settings = f(mem::take(&mut self.settings));
+    }
+
+    /// Settings of the renderer
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    /// Sets the number of threads used to render the image.
+    /// Replaces the thread pool of the renderer.
+    pub fn set_threads(&mut self, threads: usize) {
+        error::unwrap(self.try_set_threads(threads))
+    }
+
+    /// Fallible `set_threads`. Fails if `threads` is zero or the thread
+    /// pool can not be built.
+    pub fn try_set_threads(&mut self, threads: usize) -> Result<(), Error> {
+        settings::check_positive("number of threads", threads)?;
+        self.pool = Self::build_pool(Some(threads))?;
+        self.configure(|s| s.threads(threads));
+        Ok(())
     }
 
-    /// Sets the tone mapping algorithm used
+    /// Sets the tone mapping algorithm applied to the resolved image when
+    /// saving it with `save`
     pub fn set_tone_map(&mut self, tone_map: ToneMap) {
-        self.tone_map = tone_map;
+        self.configure(|s| s.tone_map(tone_map));
+    }
+
+    /// Sets the effects applied in order to the resolved image before
+    /// tone mapping when saving it with `save`
+    pub fn set_post_effects(&mut self, effects: &[PostEffect]) {
+        self.configure(|s| s.post_effects(effects));
     }
 
     /// Sets the pixel filter
     pub fn set_filter(&mut self, filter: Filter) {
-        self.filter = filter;
+        self.configure(|s| s.filter(filter));
+        if self.filter_sampler.is_some() {
+            self.filter_sampler = Some(FilterSampler::new(filter));
+        }
+    }
+
+    /// Enables filter importance sampling. Instead of splatting each sample
+    /// to all pixels within the radius of the filter, offsets of the camera
+    /// rays get sampled from the filter and each sample contributes only to
+    /// the pixel it was taken for. Avoids correlation between pixels.
+    pub fn set_filter_importance_sampling(&mut self, enabled: bool) {
+        self.configure(|s| s.filter_importance_sampling(enabled));
+        self.filter_sampler = enabled.then(|| FilterSampler::new(self.settings.filter));
     }
 
     /// Sets number of samples per pixel
     pub fn set_samples(&mut self, samples: i32) {
-        self.num_samples = samples;
+        error::unwrap(self.try_set_samples(samples))
+    }
+
+    /// Fallible `set_samples`. Fails if `samples` is not positive.
+    pub fn try_set_samples(&mut self, samples: i32) -> Result<(), Error> {
+        settings::check_positive("samples per pixel", samples)?;
+        self.configure(|s| s.samples(samples));
+        Ok(())
+    }
+
+    /// Sets the number of samples per pixel taken in each pass of the
+    /// progressive render. Should be a square number.
+    pub fn set_samples_increment(&mut self, samples: i32) {
+        error::unwrap(self.try_set_samples_increment(samples))
+    }
+
+    /// Fallible `set_samples_increment`. Fails if `samples` is not positive.
+    pub fn try_set_samples_increment(&mut self, samples: i32) -> Result<(), Error> {
+        settings::check_positive("samples increment", samples)?;
+        self.configure(|s| s.samples_increment(samples));
+        Ok(())
+    }
+
+    /// Stops the render after the pass, during which `budget`
+    /// would be exceeded. At least one pass is always rendered.
+    pub fn set_time_budget(&mut self, budget: Duration) {
+        self.configure(|s| s.time_budget(budget));
+    }
+
+    /// Stops the render once the noise estimate of a pass goes below `noise`
+    pub fn set_target_noise(&mut self, noise: Float) {
+        error::unwrap(self.try_set_target_noise(noise))
+    }
+
+    /// Fallible `set_target_noise`. Fails if `noise` is not positive.
+    pub fn try_set_target_noise(&mut self, noise: Float) -> Result<(), Error> {
+        settings::check_positive("target noise", noise)?;
+        self.configure(|s| s.target_noise(noise));
+        Ok(())
+    }
+
+    /// Enables adaptive sampling. After the first pass, only pixels with
+    /// estimated relative error above `threshold` or with too few samples
+    /// for a reliable estimate get sampled. The render
+    /// stops early once every pixel is below the threshold.
+    pub fn set_adaptive_threshold(&mut self, threshold: Float) {
+        error::unwrap(self.try_set_adaptive_threshold(threshold))
+    }
+
+    /// Fallible `set_adaptive_threshold`. Fails if `threshold` is not positive.
+    pub fn try_set_adaptive_threshold(&mut self, threshold: Float) -> Result<(), Error> {
+        settings::check_positive("adaptive threshold", threshold)?;
+        self.configure(|s| s.adaptive_threshold(threshold));
+        Ok(())
+    }
+
+    /// Sets the seed of the random number generators. Renders with the same
+    /// seed are identical regardless of the number of threads. Renders with
+    /// different seeds can be merged with `Film::merge`. A random seed gets
+    /// used if not set.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.configure(|s| s.seed(seed));
+    }
+
+    /// Renders only the pixels from raster coordinates `px_min` to `px_max`,
+    /// exclusive. The camera projection stays that of the full image.
+    /// Use `Film::cropped` to get an image of just the region.
+    pub fn set_crop(&mut self, px_min: (i32, i32), px_max: (i32, i32)) {
+        error::unwrap(self.try_set_crop(px_min, px_max))
+    }
+
+    /// Fallible `set_crop`. Fails if the region is empty or
+    /// not within the image.
+    pub fn try_set_crop(
+        &mut self,
+        px_min: (i32, i32),
+        px_max: (i32, i32),
+    ) -> Result<(), Error> {
+        Film::check_crop_window(
+            IVec2::new(px_min.0, px_min.1),
+            IVec2::new(px_max.0, px_max.1),
+            self.resolution,
+        )?;
+        self.configure(|s| s.crop(px_min, px_max));
+        Ok(())
+    }
+
+    /// Sets the rendered region in coordinates normalized to `\[0,1\]`
+    /// with `(0,0)` in the top left corner, see `set_crop`. The region
+    /// gets extended to cover any partially contained pixels.
+    pub fn set_crop_normalized(&mut self, min: (Float, Float), max: (Float, Float)) {
+        error::unwrap(self.try_set_crop_normalized(min, max))
+    }
+
+    /// Fallible `set_crop_normalized`, see `try_set_crop`
+    pub fn try_set_crop_normalized(
+        &mut self,
+        min: (Float, Float),
+        max: (Float, Float),
+    ) -> Result<(), Error> {
+        let res = Vec2::new(self.resolution.x as Float, self.resolution.y as Float);
+        let px_min = (Vec2::new(min.0, min.1) * res).floor().as_ivec2();
+        let px_max = (Vec2::new(max.0, max.1) * res).ceil().as_ivec2();
+        self.try_set_crop((px_min.x, px_min.y), (px_max.x, px_max.y))
+    }
+
+    /// Saves a checkpoint of the film to `fname` after each pass. An
+    /// interrupted render can be continued from it with `resume`.
+    pub fn set_checkpoint(&mut self, fname: &str) {
+        self.configure(|s| s.checkpoint(fname));
+    }
+
+    /// Saves a preview of the film to the .png file `fname` after each pass.
+    /// The progress of the render, i.e. samples taken, time elapsed, ETA and
+    /// rays per second, gets saved as JSON to `fname` with the extension
+    /// replaced by `.json`.
+    pub fn set_preview(&mut self, fname: &str) {
+        self.configure(|s| s.preview(fname));
+        self.preview = Some(Preview::new(fname));
+    }
+
+    /// Records the cost of rendering each pixel to the film as `heat_map`.
+    /// Saved with `Film::save_heat_map`.
+    pub fn set_heat_map(&mut self, heat_map: HeatMap) {
+        self.configure(|s| s.heat_map(heat_map));
+    }
+
+    /// Sets the arbitrary output variables rendered alongside the image
+    pub fn set_aovs(&mut self, aovs: &[Aov]) {
+        self.configure(|s| s.aovs(aovs));
+    }
+
+    /// Enables denoising of the rendered film with `denoiser`. The albedo,
+    /// normal and depth AOVs that guide the denoiser get stored in the film.
+    /// The denoised image gets saved with `save_denoised`, while the
+    /// other save functions keep saving the raw image.
+    pub fn set_denoiser(&mut self, denoiser: Denoiser) {
+        self.configure(|s| s.denoiser(denoiser));
+    }
+
+    /// Sets the sampler that generates the random values of the samples
+    pub fn set_sampler(&mut self, sampler: SamplerType) {
+        self.configure(|s| s.sampler(sampler));
     }
 
     /// Sets the integrator used to render the image
     pub fn set_integrator(&mut self, integrator: Integrator) {
-        self.integrator = integrator;
+        self.configure(|s| s.integrator(integrator));
+    }
+
+    /// Enables spectral rendering. Each path carries a set of sampled
+    /// wavelengths, RGB colors get upsampled to spectra and the samples get
+    /// accumulated to the film through the CIE XYZ matching functions.
+    /// Needed for dispersion and spectral `eta` and `k` of materials.
+    pub fn set_spectral(&mut self, spectral: bool) {
+        self.configure(|s| s.spectral(spectral));
+    }
+
+    /// Sets the color space in which RGB rendering multiplies the colors of
+    /// the scene. Defaults to sRGB. Wide gamut spaces, e.g. ACEScg, keep
+    /// saturated colors from clipping over multiple bounces. The film stores
+    /// values in the working space and converts them to its output encoding.
+    pub fn set_working_space(&mut self, space: ColorSpace) {
+        self.configure(|s| s.working_space(space));
     }
 
     /// Starts the rendering process and returns the rendered image
     pub fn render(&self) -> Film {
-        println!(
-            "Rendering scene as a {} x {} image \
-                  with {} thread(s) and {} sample(s) per pixel using {}",
+        self.render_progressive(|_, _| ())
+    }
+
+    /// Saves `film` denoised with the denoiser of the renderer. Format chosen
+    /// based on the extension of `fname`, see `Film::save_denoised`.
+    /// Denoises in the thread pool of the renderer.
+    pub fn save_denoised(&self, film: &Film, fname: &str) -> io::Result<()> {
+        let denoiser = self.settings.denoiser.ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No denoiser set for the renderer",
+        ))?;
+        self.pool.install(|| film.save_denoised(fname, denoiser, &self.settings.post_process))
+    }
+
+    /// Saves `film` to a .png file with the tone mapping and post effects
+    /// of the renderer applied
+    pub fn save(&self, film: &Film, fname: &str) -> Result<(), EncodingError> {
+        film.save_processed(fname, &self.settings.post_process)
+    }
+
+    /// Renders the image in passes of `samples_increment` samples per pixel.
+    /// `callback` gets called with the current film and progress after each
+    /// pass, e.g. to save a snapshot. Stops once `num_samples` samples have
+    /// been taken, the time budget runs out or the target noise is reached.
+    pub fn render_progressive<F>(&self, callback: F) -> Film
+    where
+        F: FnMut(&Film, &RenderProgress)
+    {
+        let mut film = Film::new(
             self.resolution.x,
             self.resolution.y,
-            rayon::current_num_threads(),
-            self.num_samples,
-            self.integrator,
+            &self.settings.aovs,
         );
+        if let Some((px_min, px_max)) = self.settings.crop {
+            film.set_crop_window(px_min, px_max);
+        }
+        film.set_working_space(self.settings.working_space);
+        self.render_film(film, callback)
+    }
 
-        let start = Instant::now();
-        let mut film = Film::new(
+    /// Continues the render saved in the checkpoint at `fname` until
+    /// `num_samples` samples per pixel have been taken
+    pub fn resume(&self, fname: &str) -> io::Result<Film> {
+        let film = Film::load_checkpoint(fname)?;
+        self.resume_progressive(film, |_, _| ())
+    }
+
+    /// Continues rendering `film` progressively, see `render_progressive`.
+    /// Fails if the film does not match the configuration of the renderer.
+    pub fn resume_progressive<F>(&self, film: Film, callback: F) -> io::Result<Film>
+    where
+        F: FnMut(&Film, &RenderProgress)
+    {
+        let invalid = |msg: String| Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+
+        if film.resolution != self.resolution {
+            return invalid(format!(
+                "Film resolution {} x {} does not match the renderer {} x {}",
+                film.resolution.x, film.resolution.y,
+                self.resolution.x, self.resolution.y,
+            ));
+        }
+        if film.aovs() != self.settings.aovs {
+            return invalid("Film AOVs do not match the renderer".to_string());
+        }
+        if film.crop_window() != self.settings.crop.unwrap_or((IVec2::ZERO, self.resolution)) {
+            return invalid("Film crop window does not match the renderer".to_string());
+        }
+        if film.working_space() != self.settings.working_space {
+            return invalid("Film working space does not match the renderer".to_string());
+        }
+        if film.samples() > self.settings.num_samples {
+            return invalid(format!(
+                "Film has {} samples per pixel, more than the {} asked",
+                film.samples(), self.settings.num_samples,
+            ));
+        }
+
+        log_info!("Resuming render with {} sample(s) per pixel taken", film.samples());
+        Ok(self.render_film(film, callback))
+    }
+
+    /// Adds samples to `film` until the render is finished
+    fn render_film<F>(&self, mut film: Film, mut callback: F) -> Film
+    where
+        F: FnMut(&Film, &RenderProgress)
+    {
+        log_info!(
+            "Rendering scene as a {} x {} image \
+                  with {} thread(s) and {} sample(s) per pixel using {}",
             self.resolution.x,
             self.resolution.y,
-            self.num_samples,
+            self.pool.current_num_threads(),
+            self.settings.num_samples,
+            self.settings.integrator,
         );
 
-        let mutex = Mutex::new(&mut film);
-
-        let tiles_x = (self.resolution.x + TILE_SIZE - 1) / TILE_SIZE;
-        let tiles_y = (self.resolution.y + TILE_SIZE - 1) / TILE_SIZE;
-        let mut samples_taken = 0;
-        while samples_taken < self.num_samples {
-            let prev = samples_taken;
-            samples_taken += SAMPLES_INCREMENT;
-            samples_taken = samples_taken.min(self.num_samples);
-            let samples = samples_taken - prev;
-
-            (0..tiles_y).into_par_iter()
-                .for_each(|y: i32| {
-                    (0..tiles_x).for_each(|x: i32| {
-                        let px_min = IVec2::new(x, y) * TILE_SIZE;
-                        let px_max = px_min + TILE_SIZE;
-                        let mut tile = self.get_tile(px_min, px_max);
-
-                        for y in tile.px_min.y..tile.px_max.y {
-                            for x in tile.px_min.x..tile.px_max.x {
-                                self.get_samples(&mut tile, samples, x, y)
-                            }
+        if let Some(heat_map) = self.settings.heat_map {
+            film.set_heat_map(heat_map);
+        }
+
+        let start = Instant::now();
+        let seed = self.settings.seed.unwrap_or_else(rand::random);
+
+        // only trace the tiles intersecting the crop window
+        let (crop_min, crop_max) = film.crop_window();
+        if (crop_min, crop_max) != (IVec2::ZERO, self.resolution) {
+            log_info!(
+                "Cropped to pixels ({}, {}) - ({}, {})",
+                crop_min.x, crop_min.y, crop_max.x, crop_max.y,
+            );
+        }
+        let tiles_min = crop_min / TILE_SIZE;
+        let tiles_max = (crop_max + TILE_SIZE - 1) / TILE_SIZE;
+        let tiles: Vec<IVec2> = (tiles_min.y..tiles_max.y)
+            .flat_map(|y| (tiles_min.x..tiles_max.x).map(move |x| IVec2::new(x, y)))
+            .collect();
+        let in_crop = |idx: usize| {
+            let raster = IVec2::new(
+                idx as i32 % self.resolution.x,
+                idx as i32 / self.resolution.x,
+            );
+            raster.cmpge(crop_min).all() && raster.cmplt(crop_max).all()
+        };
+        let start_samples = film.samples();
+        let mut passes = 0;
+        while film.samples() < self.settings.num_samples {
+            // the sampler may take fewer samples than asked, e.g. jittered
+            let samples = self.settings.sampler.sample_count(
+                self.settings.samples_increment.min(self.settings.num_samples - film.samples())
+            );
+            let sample_offset = film.samples();
+            let pass_start = Instant::now();
+
+            // pixels to sample during this pass, all of them if `None`
+            let active = match self.settings.adaptive_threshold {
+                Some(threshold) if film.samples() > 0 => {
+                    let active: Vec<bool> = film.pixel_errors().iter()
+                        .zip(film.sample_counts())
+                        .enumerate()
+                        .map(|(idx, (err, n))| in_crop(idx)
+                             && (n < MIN_ADAPTIVE_SAMPLES || *err > threshold))
+                        .collect();
+                    if !active.contains(&true) {
+                        log_info!("All pixels converged after {} pass(es)", passes);
+                        break;
+                    }
+                    Some(active)
+                }
+                _ => None,
+            };
+            let is_active = |x: i32, y: i32| {
+                active.as_ref().map_or(true, |active| {
+                    active[(x + y * self.resolution.x) as usize]
+                })
+            };
+
+            let render_tile = |tile_xy: &IVec2| {
+                let px_min = (*tile_xy * TILE_SIZE).max(crop_min);
+                let px_max = ((*tile_xy + 1) * TILE_SIZE).min(crop_max);
+                let mut tile = self.get_tile(px_min, px_max);
+                let mut sampled = false;
+                // counters of the thread now belong to this tile
+                stats::take();
+
+                for y in tile.px_min.y..tile.px_max.y {
+                    for x in tile.px_min.x..tile.px_max.x {
+                        if is_active(x, y) {
+                            self.get_pixel_samples(&mut tile, seed, sample_offset, samples, x, y);
+                            sampled = true;
                         }
+                    }
+                }
+
+                if sampled { Some((tile, stats::take())) } else { None }
+            };
+
+            // tiles get added to the film in the order of `tiles`, so that
+            // splats sum up the same way regardless of the number of threads
+            let mut pass_stats = RenderStats::default();
+            thread::scope(|scope| {
+                let (tx, rx) = mpsc::channel();
+                scope.spawn(|| self.pool.install(|| {
+                    tiles.par_iter().enumerate().for_each_with(tx, |tx, (i, tile_xy)| {
+                        // receiver lives until all tiles are sent
+                        let _ = tx.send((i, render_tile(tile_xy)));
+                    });
+                }));
 
-                        mutex.lock().unwrap().add_tile(tile);
-                    })
-                });
+                // tiles that finished before some tile preceding them
+                let mut pending: BTreeMap<usize, Option<(FilmTile, RenderStats)>> =
+                    BTreeMap::new();
+                let mut done = 0;
+                for (i, rendered) in rx {
+                    pending.insert(i, rendered);
+                    while let Some(rendered) = pending.remove(&done) {
+                        if let Some((tile, stats)) = rendered {
+                            film.add_tile(tile);
+                            pass_stats += &stats;
+                        }
+                        done += 1;
+                    }
+
+                    let pass_done = samples as Float * done as Float / tiles.len() as Float;
+                    let eta = self.eta(
+                        start.elapsed(),
+                        (film.samples() - start_samples) as Float + pass_done,
+                        film.samples() as Float + pass_done,
+                    );
+                    logging::progress(done, tiles.len(), eta);
+                }
+            });
+
+            pass_stats.elapsed = pass_start.elapsed();
+            film.add_stats(&pass_stats);
+            film.add_samples(samples);
+            passes += 1;
+            let noise = film.noise();
+
+            if let Some(fname) = &self.settings.checkpoint {
+                if let Err(err) = film.save_checkpoint(fname) {
+                    log_error!("Failed to save checkpoint to \"{}\": {}", fname, err);
+                }
+            }
+
+            let elapsed = start.elapsed();
+            let progress = RenderProgress {
+                passes,
+                samples_taken: film.samples(),
+                num_samples: self.settings.num_samples,
+                elapsed,
+                noise,
+                eta: self.eta(
+                    elapsed,
+                    (film.samples() - start_samples) as Float,
+                    film.samples() as Float,
+                ),
+                rays_per_second: film.stats().rays_per_second(),
+            };
+            if let Some(preview) = &self.preview {
+                if let Err(err) = preview.update(&film, &self.settings.post_process, &progress) {
+                    log_error!("Failed to save preview: {}", err);
+                }
+            }
+            callback(&film, &progress);
+
+            if let Some(budget) = self.settings.time_budget {
+                if progress.elapsed + pass_start.elapsed() > budget {
+                    log_info!("Time budget reached after {} pass(es)", passes);
+                    break;
+                }
+            }
+            if let (Some(target), Some(noise)) = (self.settings.target_noise, noise) {
+                if noise < target {
+                    log_info!("Target noise reached after {} pass(es)", passes);
+                    break;
+                }
+            }
         }
-        println!("Finished rendering in {:#?}", start.elapsed());
+        log_info!("Finished rendering in {:#?}", start.elapsed());
+        log_info!("{}", film.stats());
         film
     }
 
+    /// Time left in the render after `taken` samples per pixel took
+    /// `elapsed`, `samples` samples per pixel taken in total
+    fn eta(&self, elapsed: Duration, taken: Float, samples: Float) -> Duration {
+        let left = (self.settings.num_samples as Float - samples).max(0.0);
+        let eta = if taken > 0.0 {
+            elapsed.mul_f64(left / taken)
+        } else {
+            Duration::ZERO
+        };
+        match self.settings.time_budget {
+            Some(budget) => eta.min(budget.saturating_sub(elapsed)),
+            None => eta,
+        }
+    }
+
     fn get_tile(&self, px_min: IVec2, px_max: IVec2) -> FilmTile {
-        FilmTile::new(px_min, px_max.min(self.resolution), self.filter)
+        FilmTile::new(
+            px_min,
+            px_max.min(self.resolution),
+            self.settings.filter,
+            &self.settings.aovs,
+        )
     }
 
-    /// Sends `num_samples` rays towards the given pixel and averages the result
-    fn get_samples(&self, tile: &mut FilmTile, num_samples: i32, x: i32, y: i32) {
+    /// Takes the samples of the given pixel with `get_samples` and records
+    /// their cost to the heat map
+    fn get_pixel_samples(
+        &self,
+        tile: &mut FilmTile,
+        seed: u64,
+        sample_offset: i32,
+        num_samples: i32,
+        x: i32,
+        y: i32,
+    ) {
+        match self.settings.heat_map {
+            None => self.get_samples(tile, seed, sample_offset, num_samples, x, y),
+            Some(HeatMap::Time) => {
+                let start = Instant::now();
+                self.get_samples(tile, seed, sample_offset, num_samples, x, y);
+                tile.add_cost(IVec2::new(x, y), start.elapsed().as_secs_f64());
+            }
+            Some(HeatMap::Traversal) => {
+                let start = stats::current().traversal_cost();
+                self.get_samples(tile, seed, sample_offset, num_samples, x, y);
+                let cost = stats::current().traversal_cost() - start;
+                tile.add_cost(IVec2::new(x, y), cost as Float);
+            }
+        }
+    }
+
+    /// Sends `num_samples` rays towards the given pixel starting from
+    /// sample `sample_offset` of the sampler and averages the result
+    fn get_samples(
+        &self,
+        tile: &mut FilmTile,
+        seed: u64,
+        sample_offset: i32,
+        num_samples: i32,
+        x: i32,
+        y: i32,
+    ) {
+        let pixel = IVec2::new(x, y);
         let xy = Vec2::new(x as Float, y as Float);
-        PxSampler::new(num_samples)
-            .flat_map(|rand_sq: Vec2| {
-                let raster_xy = xy + rand_sq;
-                self.integrator.integrate(
-                    &self.scene,
-                    &self.camera,
-                    raster_xy,
-                    self.camera.generate_ray(raster_xy),
-                )
+        let mut sampler = self.settings.sampler.new_sampler(seed, num_samples);
+
+        (0..num_samples)
+            .flat_map(|i: i32| {
+                sampler.start_sample(pixel, (sample_offset + i) as u32);
+                // with filter importance sampling the sample belongs to
+                // the center of the pixel, weighted by the filter
+                let (raster_xy, ray_xy, weight) = match &self.filter_sampler {
+                    None => {
+                        let raster_xy = xy + sampler.get_2d();
+                        (raster_xy, raster_xy, None)
+                    }
+                    Some(filter_sampler) => {
+                        let (offset, weight) = filter_sampler.sample(sampler.get_2d());
+                        (xy + 0.5, xy + 0.5 + offset, Some(weight))
+                    }
+                };
+                let mut lambda = None;
+                let space = self.settings.working_space;
+                let mut camera_weight = 1.0;
+                let rand_sq = sampler.get_2d();
+                // only draw the time if the shutter stays open
+                let time = if self.camera.shutter().is_instant() {
+                    self.camera.shutter().open
+                } else {
+                    self.camera.sample_time(ray_xy, sampler.get_1d())
+                };
+                let samples = match self.camera.generate_ray(ray_xy, rand_sq, time) {
+                    // no light reaches the sensor through the lens
+                    None => vec![FilmSample::new(Color::BLACK, raster_xy, false)],
+                    Some((mut ro, ray_weight)) => {
+                        camera_weight = ray_weight;
+                        if self.settings.spectral {
+                            ro.lambda = Some(SampledWavelengths::sample(sampler.get_1d()));
+                        }
+                        lambda = ro.lambda;
+                        ro.space = space;
+                        stats::count(Counter::CameraRays, 1);
+                        self.settings.integrator.integrate(
+                            &self.scene,
+                            &self.camera,
+                            raster_xy,
+                            ro,
+                            !self.settings.aovs.is_empty(),
+                            sampler.as_mut(),
+                        )
+                    }
+                };
+                samples.into_iter().map(move |mut sample| {
+                    if let Some(lambda) = &lambda {
+                        sample.spectral_to_rgb(lambda, space);
+                    }
+                    if !sample.splat {
+                        sample.color = sample.color * camera_weight;
+                    }
+                    // counted per sample, tone mapping only sees filtered
+                    // pixels clamped to non-negative values
+                    if sample.color.rgb.is_nan() {
+                        stats::count(Counter::NanSamples, 1);
+                    } else if sample.color.rgb.is_negative_bitmask() != 0 {
+                        stats::count(Counter::NegativeSamples, 1);
+                    }
+                    (sample, weight)
+                })
             })
-            .for_each(|mut sample: FilmSample| {
-                sample.color = self.tone_map.map(sample.color);
-                tile.add_sample(sample)
+            .for_each(|(sample, weight): (FilmSample, Option<Float>)| {
+                match weight {
+                    Some(weight) => tile.add_weighted_sample(sample, weight),
+                    None => tile.add_sample(sample),
+                }
             })
     }
 }
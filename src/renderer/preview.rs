@@ -0,0 +1,77 @@
+use crate::{PostProcess, RenderProgress};
+use crate::tracer::Film;
+use std::{io, io::Write, path::{Path, PathBuf}};
+
+#[cfg(test)]
+mod preview_tests;
+
+/// Live preview of a progressive render. After each pass the current film
+/// gets saved as a .png and the progress as a small JSON status file next
+/// to it. Both files get replaced atomically, so viewers polling them never
+/// see a partially written file.
+pub struct Preview {
+    image: PathBuf,
+    status: PathBuf,
+}
+
+impl Preview {
+    /// Preview saved to the .png file `fname`. The status gets saved
+    /// to `fname` with the extension replaced by `.json`.
+    pub fn new(fname: &str) -> Self {
+        let image = PathBuf::from(fname);
+        let status = image.with_extension("json");
+        Self { image, status }
+    }
+
+    /// Replaces the preview files with `film` processed with `post_process`
+    /// and `progress`
+    pub fn update(
+        &self,
+        film: &Film,
+        post_process: &PostProcess,
+        progress: &RenderProgress,
+    ) -> io::Result<()> {
+        let image = Self::temp_file(&self.image, ".png")?;
+        let fname = image.path().to_str().ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Preview path is not valid unicode",
+        ))?;
+        film.save_snapshot(fname, post_process)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        image.persist(&self.image)?;
+
+        let mut status = Self::temp_file(&self.status, ".json")?;
+        status.write_all(Self::status_json(progress).as_bytes())?;
+        status.persist(&self.status)?;
+        Ok(())
+    }
+
+    /// Temporary file in the same directory as `path`, so that it can be
+    /// renamed over `path`
+    fn temp_file(path: &Path, suffix: &str) -> io::Result<tempfile::NamedTempFile> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        tempfile::Builder::new().suffix(suffix).tempfile_in(dir)
+    }
+
+    /// Progress of the render as JSON. Durations are in seconds and
+    /// unknown values `null`.
+    fn status_json(progress: &RenderProgress) -> String {
+        let noise = progress.noise
+            .map_or("null".to_string(), |noise| noise.to_string());
+        format!(
+            "{{\n  \"passes\": {},\n  \"samples_taken\": {},\n  \"num_samples\": {},\n  \
+             \"elapsed\": {:.3},\n  \"eta\": {:.3},\n  \"rays_per_second\": {:.1},\n  \
+             \"noise\": {}\n}}\n",
+            progress.passes,
+            progress.samples_taken,
+            progress.num_samples,
+            progress.elapsed.as_secs_f64(),
+            progress.eta.as_secs_f64(),
+            progress.rays_per_second,
+            noise,
+        )
+    }
+}
@@ -0,0 +1,53 @@
+use super::*;
+use crate::tracer::{Color, FilmSample, FilmTile, Filter};
+use crate::{Float, Vec2};
+use glam::IVec2;
+use std::{fs, time::Duration};
+
+fn progress(noise: Option<Float>) -> RenderProgress {
+    RenderProgress {
+        passes: 2,
+        samples_taken: 8,
+        num_samples: 32,
+        elapsed: Duration::from_millis(1500),
+        noise,
+        eta: Duration::from_millis(4500),
+        rays_per_second: 1234.5,
+    }
+}
+
+#[test]
+fn status_json_fields() {
+    let json = Preview::status_json(&progress(Some(0.25)));
+    assert!(json.contains("\"samples_taken\": 8,"));
+    assert!(json.contains("\"num_samples\": 32,"));
+    assert!(json.contains("\"elapsed\": 1.500,"));
+    assert!(json.contains("\"eta\": 4.500,"));
+    assert!(json.contains("\"rays_per_second\": 1234.5,"));
+    assert!(json.contains("\"noise\": 0.25"));
+
+    let json = Preview::status_json(&progress(None));
+    assert!(json.contains("\"noise\": null"));
+}
+
+#[test]
+fn preview_files_replaced() {
+    let dir = tempfile::tempdir().unwrap();
+    let fname = dir.path().join("preview.png");
+    let preview = Preview::new(fname.to_str().unwrap());
+
+    let mut film = Film::new(2, 2, &[]);
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(0.5), &[]);
+    tile.add_sample(FilmSample::new(Color::WHITE, Vec2::splat(0.5), false));
+    film.add_tile(tile);
+
+    preview.update(&film, &PostProcess::default(), &progress(None)).unwrap();
+    preview.update(&film, &PostProcess::default(), &progress(Some(0.5))).unwrap();
+
+    let reader = png::Decoder::new(fs::File::open(&fname).unwrap()).read_info().unwrap();
+    assert!(reader.info().width == 2 && reader.info().height == 2);
+    let status = fs::read_to_string(dir.path().join("preview.json")).unwrap();
+    assert!(status.contains("\"noise\": 0.5"));
+    // no temporary files left behind
+    assert!(fs::read_dir(dir.path()).unwrap().count() == 2);
+}
@@ -0,0 +1,245 @@
+use crate::{Float, HeatMap, samplers::SamplerType, ToneMap, PostEffect, PostProcess};
+use crate::error::{self, Error};
+use crate::tracer::{Aov, ColorSpace, Denoiser, Film, Filter, Integrator};
+use glam::IVec2;
+use std::{fmt, time::Duration};
+
+#[cfg(test)]
+mod settings_tests;
+
+/// Default number of samples per pixel in each pass of the progressive render
+const SAMPLES_INCREMENT: i32 = 256;
+
+/// Configuration of a render, passed to `Renderer::with_settings`.
+/// Built by chaining the methods on the default settings, e.g.
+/// `RenderSettings::default().samples(64).threads(4)`. The defaults are
+/// 1 sample per pixel, path tracing as the integrator and all available
+/// threads. Validated when given to the renderer.
+pub struct RenderSettings {
+    pub(super) num_samples: i32,
+    pub(super) samples_increment: i32,
+    pub(super) integrator: Integrator,
+    pub(super) post_process: PostProcess,
+    pub(super) filter: Filter,
+    pub(super) filter_importance_sampling: bool,
+    pub(super) aovs: Vec<Aov>,
+    pub(super) denoiser: Option<Denoiser>,
+    pub(super) time_budget: Option<Duration>,
+    pub(super) target_noise: Option<Float>,
+    pub(super) adaptive_threshold: Option<Float>,
+    pub(super) checkpoint: Option<String>,
+    pub(super) preview: Option<String>,
+    pub(super) heat_map: Option<HeatMap>,
+    pub(super) seed: Option<u64>,
+    pub(super) crop: Option<(IVec2, IVec2)>,
+    pub(super) sampler: SamplerType,
+    pub(super) spectral: bool,
+    pub(super) working_space: ColorSpace,
+    pub(super) threads: Option<usize>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            num_samples: 1,
+            samples_increment: SAMPLES_INCREMENT,
+            integrator: Integrator::PathTrace,
+            post_process: PostProcess::default(),
+            filter: Filter::Box(0.5),
+            filter_importance_sampling: false,
+            aovs: vec![],
+            denoiser: None,
+            time_budget: None,
+            target_noise: None,
+            adaptive_threshold: None,
+            checkpoint: None,
+            preview: None,
+            heat_map: None,
+            seed: None,
+            crop: None,
+            sampler: SamplerType::Jittered,
+            spectral: false,
+            working_space: ColorSpace::Srgb,
+            threads: None,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Number of samples per pixel
+    pub fn samples(mut self, samples: i32) -> Self {
+        self.num_samples = samples;
+        self
+    }
+
+    /// Number of samples per pixel taken in each pass of the
+    /// progressive render. Should be a square number.
+    pub fn samples_increment(mut self, samples: i32) -> Self {
+        self.samples_increment = samples;
+        self
+    }
+
+    /// Integrator used to render the image
+    pub fn integrator(mut self, integrator: Integrator) -> Self {
+        self.integrator = integrator;
+        self
+    }
+
+    /// Tone mapping algorithm applied to the resolved image
+    pub fn tone_map(mut self, tone_map: ToneMap) -> Self {
+        self.post_process.set_tone_map(tone_map);
+        self
+    }
+
+    /// Effects applied in order to the resolved image before tone mapping
+    pub fn post_effects(mut self, effects: &[PostEffect]) -> Self {
+        self.post_process.set_effects(effects);
+        self
+    }
+
+    /// Pixel filter
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Filter importance sampling, see `Renderer::set_filter_importance_sampling`
+    pub fn filter_importance_sampling(mut self, enabled: bool) -> Self {
+        self.filter_importance_sampling = enabled;
+        self
+    }
+
+    /// Arbitrary output variables rendered alongside the image
+    pub fn aovs(mut self, aovs: &[Aov]) -> Self {
+        self.aovs = aovs.to_vec();
+        if self.denoiser.is_some() {
+            self.add_denoiser_features();
+        }
+        self
+    }
+
+    /// Denoiser of the rendered film, see `Renderer::set_denoiser`
+    pub fn denoiser(mut self, denoiser: Denoiser) -> Self {
+        self.denoiser = Some(denoiser);
+        self.add_denoiser_features();
+        self
+    }
+
+    fn add_denoiser_features(&mut self) {
+        for aov in Denoiser::FEATURES {
+            if !self.aovs.contains(&aov) {
+                self.aovs.push(aov);
+            }
+        }
+    }
+
+    /// Stops the render after the pass, during which `budget`
+    /// would be exceeded
+    pub fn time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Stops the render once the noise estimate of a pass goes below `noise`
+    pub fn target_noise(mut self, noise: Float) -> Self {
+        self.target_noise = Some(noise);
+        self
+    }
+
+    /// Adaptive sampling, see `Renderer::set_adaptive_threshold`
+    pub fn adaptive_threshold(mut self, threshold: Float) -> Self {
+        self.adaptive_threshold = Some(threshold);
+        self
+    }
+
+    /// Saves a checkpoint of the film to `fname` after each pass
+    pub fn checkpoint(mut self, fname: &str) -> Self {
+        self.checkpoint = Some(fname.to_string());
+        self
+    }
+
+    /// Saves a preview of the film to `fname` after each pass,
+    /// see `Renderer::set_preview`
+    pub fn preview(mut self, fname: &str) -> Self {
+        self.preview = Some(fname.to_string());
+        self
+    }
+
+    /// Records the cost of rendering each pixel to the film as `heat_map`
+    pub fn heat_map(mut self, heat_map: HeatMap) -> Self {
+        self.heat_map = Some(heat_map);
+        self
+    }
+
+    /// Seed of the random number generators, random if not set. Also
+    /// seeds the noise of the post effects.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self.post_process.set_seed(seed);
+        self
+    }
+
+    /// Renders only the pixels from raster coordinates `px_min` to `px_max`,
+    /// exclusive. See `Renderer::set_crop`.
+    pub fn crop(mut self, px_min: (i32, i32), px_max: (i32, i32)) -> Self {
+        self.crop = Some((
+            IVec2::new(px_min.0, px_min.1),
+            IVec2::new(px_max.0, px_max.1),
+        ));
+        self
+    }
+
+    /// Sampler that generates the random values of the samples
+    pub fn sampler(mut self, sampler: SamplerType) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    /// Spectral rendering, see `Renderer::set_spectral`
+    pub fn spectral(mut self, spectral: bool) -> Self {
+        self.spectral = spectral;
+        self
+    }
+
+    /// Color space of rendering, see `Renderer::set_working_space`
+    pub fn working_space(mut self, space: ColorSpace) -> Self {
+        self.working_space = space;
+        self
+    }
+
+    /// Number of threads in the thread pool of the renderer.
+    /// Defaults to the number of logical cores.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Checks that the settings are valid for an image of `resolution`
+    pub(super) fn validate(&self, resolution: IVec2) -> Result<(), Error> {
+        check_positive("samples per pixel", self.num_samples)?;
+        check_positive("samples increment", self.samples_increment)?;
+        if let Some(noise) = self.target_noise {
+            check_positive("target noise", noise)?;
+        }
+        if let Some(threshold) = self.adaptive_threshold {
+            check_positive("adaptive threshold", threshold)?;
+        }
+        if let Some(threads) = self.threads {
+            check_positive("number of threads", threads)?;
+        }
+        if let Some((min, max)) = self.crop {
+            Film::check_crop_window(min, max, resolution)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the setting `what` has a positive `value`
+pub(super) fn check_positive<T>(what: &str, value: T) -> Result<(), Error>
+where
+    T: PartialOrd + Default + fmt::Display
+{
+    error::check(value > T::default(), || format!(
+        "{} must be positive, got {}", what, value,
+    ))
+}
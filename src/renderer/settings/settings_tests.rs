@@ -0,0 +1,194 @@
+use super::*;
+use crate::Renderer;
+use crate::{Point, Vec3};
+use crate::tracer::{Camera, Color, ColorSpace, Material, Medium, Scene, Sphere, Texture};
+
+const RES: IVec2 = IVec2::new(8, 6);
+
+fn scene() -> Scene {
+    Scene::empty_box(
+        Color::WHITE,
+        Material::diffuse(Texture::Solid(Color::WHITE)),
+        Material::diffuse(Texture::Solid(Color::WHITE)),
+    )
+}
+
+fn assert_invalid(settings: RenderSettings, expected: &str) {
+    match settings.validate(RES) {
+        Err(Error::InvalidArgument(msg)) => assert!(msg.contains(expected), "{}", msg),
+        Err(err) => panic!("unexpected error {}", err),
+        Ok(_) => panic!("expected error about {}", expected),
+    }
+}
+
+#[test]
+fn settings_validated() {
+    assert!(RenderSettings::default().validate(RES).is_ok());
+    assert!(RenderSettings::default().crop((0, 0), (8, 6)).validate(RES).is_ok());
+    assert_invalid(RenderSettings::default().samples(0), "samples per pixel");
+    assert_invalid(RenderSettings::default().samples_increment(-1), "increment");
+    assert_invalid(RenderSettings::default().target_noise(0.0), "noise");
+    assert_invalid(RenderSettings::default().adaptive_threshold(-0.1), "threshold");
+    assert_invalid(RenderSettings::default().threads(0), "threads");
+    assert_invalid(RenderSettings::default().crop((2, 2), (9, 4)), "crop window");
+}
+
+#[test]
+fn denoiser_adds_features() {
+    let settings = RenderSettings::default()
+        .denoiser(Denoiser::CrossBilateral(2))
+        .aovs(&[Aov::Depth]);
+    for aov in Denoiser::FEATURES {
+        assert!(settings.aovs.contains(&aov));
+    }
+    assert!(settings.aovs.len() == Denoiser::FEATURES.len());
+}
+
+#[test]
+fn renderer_owns_thread_pool() {
+    let settings = RenderSettings::default().threads(3).samples(2);
+    let mut renderer = Renderer::with_settings(scene(), Camera::default(8, 6), settings);
+    assert!(renderer.pool.current_num_threads() == 3);
+    assert!(renderer.settings().num_samples == 2);
+
+    renderer.set_threads(2);
+    assert!(renderer.pool.current_num_threads() == 2);
+    assert!(renderer.settings().threads == Some(2));
+}
+
+#[test]
+fn seeded_renders_independent_of_threads() {
+    let render = |bdpt: bool, threads: usize| {
+        let integrator = if bdpt { Integrator::BDPathTrace } else { Integrator::PathTrace };
+        let mut scene = scene();
+        scene.add(Sphere::new(Point::new(0.0, -0.5, -1.3), 0.3, Material::glass(1.5)));
+        scene.set_medium(Medium::new(Vec3::splat(0.1), Vec3::splat(0.5), 0.2));
+        let settings = RenderSettings::default()
+            .integrator(integrator)
+            .sampler(SamplerType::Independent)
+            .threads(threads)
+            .samples(4)
+            .seed(2);
+        Renderer::with_settings(scene, Camera::default(RES.x, RES.y), settings)
+            .render()
+            .resolve()
+            .iter()
+            .map(|px| px.rgb)
+            .collect::<Vec<Vec3>>()
+    };
+
+    for bdpt in [false, true] {
+        let pixels = render(bdpt, 1);
+        assert!(pixels.iter().any(|px| *px != Vec3::ZERO));
+        assert!(pixels == render(bdpt, 3));
+    }
+}
+
+#[test]
+fn film_records_samples_taken() {
+    let settings = RenderSettings::default()
+        .sampler(SamplerType::Jittered)
+        .samples(10)
+        .samples_increment(5);
+    let film = Renderer::with_settings(scene(), Camera::default(RES.x, RES.y), settings)
+        .render();
+    // jittered takes 4 + 4 + 1 + 1 samples
+    assert!(film.samples() == 10);
+    assert!(film.sample_counts().iter().all(|n| *n == 10));
+}
+
+#[test]
+fn save_denoised_needs_denoiser() {
+    let mut renderer = Renderer::with_settings(
+        scene(),
+        Camera::default(RES.x, RES.y),
+        RenderSettings::default().samples(1),
+    );
+    let film = renderer.render();
+    let file = tempfile::Builder::new().suffix(".pfm").tempfile().unwrap();
+    let fname = file.path().to_str().unwrap();
+    assert!(renderer.save_denoised(&film, fname).is_err());
+
+    renderer.set_denoiser(Denoiser::VarianceGuided(2));
+    renderer.save_denoised(&film, fname).unwrap();
+}
+
+#[test]
+fn save_applies_post_process() {
+    let settings = RenderSettings::default()
+        .samples(1)
+        .post_effects(&[PostEffect::Exposure(-20.0)]);
+    let renderer = Renderer::with_settings(scene(), Camera::default(RES.x, RES.y), settings);
+    let film = renderer.render();
+    let read = |save: &dyn Fn(&str)| {
+        let file = tempfile::Builder::new().suffix(".png").tempfile().unwrap();
+        save(file.path().to_str().unwrap());
+        std::fs::read(file.path()).unwrap()
+    };
+
+    let raw = read(&|fname| film.save(fname).unwrap());
+    let processed = read(&|fname| renderer.save(&film, fname).unwrap());
+    let dark = read(&|fname| film.save_processed(fname, &renderer.settings().post_process).unwrap());
+    assert!(raw != processed);
+    assert!(processed == dark);
+}
+
+#[test]
+fn film_grain_seeded_with_render_seed() {
+    let grain = |seed: u64| {
+        let settings = RenderSettings::default()
+            .post_effects(&[PostEffect::FilmGrain(0.5)])
+            .seed(seed);
+        settings.post_process
+            .apply(RES, &vec![Color::WHITE; (RES.x * RES.y) as usize], ColorSpace::Srgb)
+            .iter()
+            .map(|c| c.rgb)
+            .collect::<Vec<Vec3>>()
+    };
+    assert!(grain(1) == grain(1));
+    assert!(grain(1) != grain(2));
+}
+
+#[test]
+fn working_space_set_on_film() {
+    let render = |space: ColorSpace| {
+        let settings = RenderSettings::default()
+            .sampler(SamplerType::Independent)
+            .working_space(space)
+            .seed(3);
+        Renderer::with_settings(scene(), Camera::default(RES.x, RES.y), settings).render()
+    };
+    let srgb = render(ColorSpace::Srgb);
+    let aces = render(ColorSpace::AcesCg);
+    assert!(aces.working_space() == ColorSpace::AcesCg);
+
+    // white stays white in both spaces
+    let to_srgb = ColorSpace::conversion(ColorSpace::AcesCg, ColorSpace::Srgb);
+    for (a, b) in srgb.resolve().iter().zip(aces.resolve()) {
+        assert!((a.rgb - to_srgb * b.rgb).abs().max_element() < 1e-3);
+    }
+
+    let renderer = Renderer::with_settings(
+        scene(),
+        Camera::default(RES.x, RES.y),
+        RenderSettings::default().working_space(ColorSpace::AcesCg),
+    );
+    assert!(renderer.resume_progressive(srgb, |_, _| ()).is_err());
+}
+
+#[test]
+fn renderer_setters_validated() {
+    let mut renderer = Renderer::new(scene(), Camera::default(RES.x, RES.y));
+    let invalid = |result: Result<(), Error>| matches!(result, Err(Error::InvalidArgument(_)));
+    assert!(invalid(renderer.try_set_threads(0)));
+    assert!(invalid(renderer.try_set_samples(0)));
+    assert!(invalid(renderer.try_set_samples_increment(-4)));
+    assert!(invalid(renderer.try_set_target_noise(Float::NAN)));
+    assert!(invalid(renderer.try_set_adaptive_threshold(0.0)));
+    assert!(renderer.settings().validate(RES).is_ok());
+
+    renderer.try_set_samples(4).unwrap();
+    renderer.try_set_threads(2).unwrap();
+    assert!(renderer.settings().num_samples == 4);
+    assert!(renderer.pool.current_num_threads() == 2);
+}
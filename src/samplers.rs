@@ -1,74 +1,167 @@
 use crate::{Float, Vec2, rand_utils};
+use glam::IVec2;
+use std::fmt;
 
-/// Choose each sample point uniformly at random
-pub struct UniformSampler {
-    /// How many samples have been given?
-    state: i32,
-    /// How many samples was asked?
-    samples: i32,
+use halton::HaltonSampler;
+use independent::IndependentSampler;
+use jittered::JitteredSampler;
+use pmj02::Pmj02Sampler;
+use sobol::SobolSampler;
+
+#[cfg(test)]
+mod samplers_tests;
+
+/// Scrambled Halton sequence
+mod halton;
+/// Uniform random samples
+mod independent;
+/// Stratified samples with random jitter
+mod jittered;
+/// Progressive multi-jittered (0,2) sequence
+mod pmj02;
+/// Owen scrambled Sobol sequence
+mod sobol;
+
+/// Largest float below one
+const ONE_MINUS_EPSILON: Float = 1.0 - Float::EPSILON / 2.0;
+
+/// Sampler that hands out consecutive dimensions of a sample vector. The
+/// integrators draw the dimensions in the same order for each sample of a
+/// pixel, e.g. first the pixel position, then the lens and so on.
+pub trait Sampler {
+    /// Starts sample `index` of `pixel`. The next value is drawn
+    /// from the first dimension.
+    fn start_sample(&mut self, pixel: IVec2, index: u32);
+    /// Next dimension of the current sample
+    fn get_1d(&mut self) -> Float;
+    /// Next two dimensions of the current sample
+    fn get_2d(&mut self) -> Vec2;
+}
+
+/// Enum to choose which sampler to use
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SamplerType {
+    /// Uniform random values for each dimension
+    Independent,
+    /// Each dimension divided to strata with one random sample from each.
+    /// The number of samples per pixel gets rounded down to a square.
+    Jittered,
+    /// Sobol sequence with Owen scrambling. The sequence has six
+    /// dimensions, after which dimensions are padded with copies of it that
+    /// are scrambled and shuffled independently. Padded dimensions keep
+    /// their own stratification, but are not stratified jointly with
+    /// earlier copies.
+    Sobol,
+    /// Halton sequence with random digit scrambling
+    Halton,
+    /// Progressive multi-jittered (0,2) sequences
+    Pmj02,
 }
 
-impl UniformSampler {
-    /// Constructs an uniform sampler with `samples` samples
-    #[allow(dead_code)]
-    pub fn new(samples: i32) -> Self {
-        Self { samples, state: 0 }
+impl fmt::Display for SamplerType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Independent => write!(f, "independent"),
+            Self::Jittered => write!(f, "jittered"),
+            Self::Sobol => write!(f, "Sobol"),
+            Self::Halton => write!(f, "Halton"),
+            Self::Pmj02 => write!(f, "PMJ02"),
+        }
     }
 }
 
-impl Iterator for UniformSampler {
-    type Item = Vec2;
+impl SamplerType {
+    /// Number of samples actually taken when `samples` are asked for
+    pub fn sample_count(&self, samples: i32) -> i32 {
+        match self {
+            Self::Jittered => {
+                let dim = (samples as Float).sqrt() as i32;
+                dim * dim
+            }
+            _ => samples,
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.state == self.samples {
-            None
-        } else {
-            self.state += 1;
-            Some(rand_utils::unit_square())
+    /// Creates a sampler seeded with `seed`. `samples` is the number of
+    /// samples taken for each pixel at a time, used by the jittered sampler.
+    pub fn new_sampler(&self, seed: u64, samples: i32) -> Box<dyn Sampler> {
+        match self {
+            Self::Independent => Box::new(IndependentSampler::new(seed)),
+            Self::Jittered => Box::new(JitteredSampler::new(seed, samples)),
+            Self::Sobol => Box::new(SobolSampler::new(seed)),
+            Self::Halton => Box::new(HaltonSampler::new(seed)),
+            Self::Pmj02 => Box::new(Pmj02Sampler::new(seed)),
         }
     }
 }
 
-/// Divide unit square to `n`x`n` strata and provide one sample from each strata.
-pub struct JitteredSampler {
-    /// Width of one strata
-    scale: Float,
-    /// How many samples have been given?
-    state: i32,
-    /// How many strata per dimension?
-    strata_dim: i32,
-    /// How many samples have been asked for? Should be a square,
-    /// otherwise gets rounded down to the nearest square.
-    samples: i32,
+/// Hash of `seed`, `pixel` and `dim`
+fn hash_dimension(seed: u64, pixel: IVec2, dim: u32) -> u64 {
+    let h = rand_utils::hash(seed ^ pixel.x as u64);
+    let h = rand_utils::hash(h ^ pixel.y as u64);
+    rand_utils::hash(h ^ dim as u64)
 }
 
-impl JitteredSampler {
-    /// Constructs a jittered sampler with `floor(sqrt(samples))^2` samples
-    pub fn new(samples: i32) -> Self {
-        let dim = (samples as Float).sqrt() as i32;
-        Self {
-            scale: (dim as Float).recip(),
-            samples: dim * dim,
-            strata_dim: dim,
-            state: 0,
-        }
-    }
+/// Uniform value in `\[0,1)` for sample `index` and `k` in the dimension
+/// with hash `h`
+fn hash_to_float(h: u64, index: u32, k: u64) -> Float {
+    to_float(rand_utils::hash(rand_utils::hash(h ^ index as u64) ^ k) as u32)
+}
+
+/// Maps 32 bits to a float in `\[0,1)`
+fn to_float(x: u32) -> Float {
+    (x as Float * (-32.0 as Float).exp2()).min(ONE_MINUS_EPSILON)
+}
+
+/// Laine-Karras style hash that scrambles the bits of `x` such that each
+/// bit only depends on the less significant bits
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x
 }
 
-impl Iterator for JitteredSampler {
-    type Item = Vec2;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.state == self.samples {
-            None
-        } else {
-            let offset = self.scale
-                * Vec2::new(
-                    (self.state % self.strata_dim) as Float,
-                    (self.state / self.strata_dim) as Float,
-                );
-            self.state += 1;
-            Some(self.scale * rand_utils::unit_square() + offset)
+/// Owen scrambling of the bits of `x`, i.e. each bit gets flipped based on
+/// a hash of the more significant bits. Burley 2020
+fn owen_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+/// Element `i` of a random permutation of `0..n` chosen by `seed`. Kensler 2013
+fn permutation_element(mut i: u32, n: u32, seed: u32) -> u32 {
+    let mut w = n - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < n {
+            break;
         }
     }
+
+    (i.wrapping_add(seed)) % n
 }
@@ -0,0 +1,74 @@
+use super::*;
+
+/// Prime bases of the dimensions. Further dimensions get padded with
+/// differently scrambled copies.
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53,
+    59, 61, 67, 71, 73, 79, 83, 89, 97, 101, 103, 107, 109, 113, 127, 131,
+];
+
+/// Radical inverse of `a` in `base` with each digit shifted by a
+/// random amount chosen by `seed` and the position of the digit
+pub fn scrambled_radical_inverse(base: u64, mut a: u64, seed: u64) -> Float {
+    let inv_base = 1.0 / base as Float;
+    let mut inv_base_m = 1.0;
+    let mut result = 0.0;
+    let mut position = 0;
+
+    // continue until the digits no longer change the value
+    while 1.0 - (base - 1) as Float * inv_base_m < 1.0 {
+        let digit = a % base;
+        a /= base;
+        let shift = rand_utils::hash(seed ^ position) % base;
+        inv_base_m *= inv_base;
+        result += ((digit + shift) % base) as Float * inv_base_m;
+        position += 1;
+    }
+
+    result.min(ONE_MINUS_EPSILON)
+}
+
+/// Halton sequence with random digit scrambling, different for each pixel
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: IVec2,
+    index: u32,
+    dim: u32,
+}
+
+impl HaltonSampler {
+    /// Constructs a Halton sampler with scrambling seeded by `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { seed, pixel: IVec2::ZERO, index: 0, dim: 0 }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, pixel: IVec2, index: u32) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dim = 0;
+    }
+
+    fn get_1d(&mut self) -> Float {
+        let dim = self.dim;
+        self.dim += 1;
+
+        let base = PRIMES[dim as usize % PRIMES.len()];
+        let seed = hash_dimension(self.seed, self.pixel, dim);
+        // padded copies need their own order, otherwise they would
+        // correlate with the dimensions of the same base
+        let index = if (dim as usize) < PRIMES.len() {
+            self.index
+        } else {
+            owen_scramble(self.index, rand_utils::hash(seed) as u32)
+        };
+        scrambled_radical_inverse(base, index as u64, seed)
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let x = self.get_1d();
+        let y = self.get_1d();
+        Vec2::new(x, y)
+    }
+}
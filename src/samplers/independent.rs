@@ -0,0 +1,43 @@
+use super::*;
+
+/// Draws each dimension uniformly at random. The values are hashed from
+/// the seed, pixel, sample index and dimension, so they do not depend on
+/// the order in which samples get taken.
+pub struct IndependentSampler {
+    seed: u64,
+    pixel: IVec2,
+    index: u32,
+    dim: u32,
+}
+
+impl IndependentSampler {
+    /// Constructs an independent sampler seeded with `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { seed, pixel: IVec2::ZERO, index: 0, dim: 0 }
+    }
+
+    /// Hash of the current dimension. Moves to the next dimension.
+    fn next_dimension(&mut self) -> u64 {
+        let h = hash_dimension(self.seed, self.pixel, self.dim);
+        self.dim += 1;
+        h
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, pixel: IVec2, index: u32) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dim = 0;
+    }
+
+    fn get_1d(&mut self) -> Float {
+        let h = self.next_dimension();
+        hash_to_float(h, self.index, 0)
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let h = self.next_dimension();
+        Vec2::new(hash_to_float(h, self.index, 0), hash_to_float(h, self.index, 1))
+    }
+}
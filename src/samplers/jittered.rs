@@ -0,0 +1,63 @@
+use super::*;
+
+/// Divides each dimension to strata and takes one jittered sample from each.
+/// The strata get visited in a different random order for each dimension.
+pub struct JitteredSampler {
+    seed: u64,
+    /// Number of strata in 1D
+    strata: u32,
+    /// Number of strata per axis in 2D
+    strata_dim: u32,
+    pixel: IVec2,
+    index: u32,
+    dim: u32,
+}
+
+impl JitteredSampler {
+    /// Constructs a jittered sampler for `floor(sqrt(samples))^2` samples
+    pub fn new(seed: u64, samples: i32) -> Self {
+        let strata_dim = ((samples as Float).sqrt() as u32).max(1);
+        Self {
+            seed,
+            strata: strata_dim * strata_dim,
+            strata_dim,
+            pixel: IVec2::ZERO,
+            index: 0,
+            dim: 0,
+        }
+    }
+
+    /// Stratum of the current sample in the current dimension and the hash
+    /// of the dimension used for the jitter. Moves to the next dimension.
+    fn stratum(&mut self) -> (u32, u64) {
+        // new permutation for each round of `strata` samples
+        let round = self.index / self.strata;
+        let h = hash_dimension(self.seed, self.pixel, self.dim);
+        let perm = rand_utils::hash(h ^ round as u64) as u32;
+        self.dim += 1;
+        (permutation_element(self.index % self.strata, self.strata, perm), h)
+    }
+}
+
+impl Sampler for JitteredSampler {
+    fn start_sample(&mut self, pixel: IVec2, index: u32) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dim = 0;
+    }
+
+    fn get_1d(&mut self) -> Float {
+        let (stratum, h) = self.stratum();
+        (stratum as Float + hash_to_float(h, self.index, 0)) / self.strata as Float
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let (stratum, h) = self.stratum();
+        let xy = Vec2::new(
+            (stratum % self.strata_dim) as Float,
+            (stratum / self.strata_dim) as Float,
+        );
+        let jitter = Vec2::new(hash_to_float(h, self.index, 0), hash_to_float(h, self.index, 1));
+        (xy + jitter) / self.strata_dim as Float
+    }
+}
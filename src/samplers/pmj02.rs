@@ -0,0 +1,50 @@
+use super::*;
+use super::sobol::sobol;
+
+/// Progressive multi-jittered (0,2) sequences. Each pair of dimensions gets
+/// an independent sequence, that is stratified in all of the elementary
+/// intervals of its prefixes with power of two lengths. Generated
+/// stochastically by random digit flips of the (0,2) sequence formed by the
+/// first two Sobol dimensions, which gives the same distribution as
+/// pmj02. Helmer et al. 2021
+pub struct Pmj02Sampler {
+    seed: u64,
+    pixel: IVec2,
+    index: u32,
+    dim: u32,
+}
+
+impl Pmj02Sampler {
+    /// Constructs a PMJ02 sampler with randomization seeded by `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { seed, pixel: IVec2::ZERO, index: 0, dim: 0 }
+    }
+
+    /// Point of the sequence for the current dimension
+    fn point(&mut self) -> (u32, u32) {
+        let h = hash_dimension(self.seed, self.pixel, self.dim);
+        self.dim += 1;
+
+        let index = owen_scramble(self.index, h as u32);
+        let x = owen_scramble(sobol(index, 0), (h >> 32) as u32);
+        let y = owen_scramble(sobol(index, 1), rand_utils::hash(h) as u32);
+        (x, y)
+    }
+}
+
+impl Sampler for Pmj02Sampler {
+    fn start_sample(&mut self, pixel: IVec2, index: u32) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dim = 0;
+    }
+
+    fn get_1d(&mut self) -> Float {
+        to_float(self.point().0)
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let (x, y) = self.point();
+        Vec2::new(to_float(x), to_float(y))
+    }
+}
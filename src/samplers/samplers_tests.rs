@@ -0,0 +1,150 @@
+use super::*;
+
+const ALL: [SamplerType; 5] = [
+    SamplerType::Independent,
+    SamplerType::Jittered,
+    SamplerType::Sobol,
+    SamplerType::Halton,
+    SamplerType::Pmj02,
+];
+
+/// Draws `n` samples with `dims` 2D dimensions each
+fn draw(sampler: SamplerType, n: u32, dims: usize) -> Vec<Vec<Vec2>> {
+    let pixel = IVec2::new(3, 7);
+    let mut sampler = sampler.new_sampler(5, n as i32);
+    (0..n).map(|i| {
+        sampler.start_sample(pixel, i);
+        (0..dims).map(|_| sampler.get_2d()).collect()
+    }).collect()
+}
+
+/// Is each elementary interval of area `1 / pts.len()` hit exactly once?
+fn is_02_net(pts: &[Vec2]) -> bool {
+    let m = pts.len().trailing_zeros();
+    (0..=m).all(|a| {
+        let nx = 1 << a;
+        let ny = 1 << (m - a);
+        let mut hits = vec![0; pts.len()];
+        for p in pts {
+            let x = (p.x * nx as Float) as usize;
+            let y = (p.y * ny as Float) as usize;
+            hits[x + y * nx] += 1;
+        }
+        hits.iter().all(|h| *h == 1)
+    })
+}
+
+/// Is each of the `pts.len()` intervals hit exactly once?
+fn is_stratified(pts: &[Float]) -> bool {
+    let n = pts.len();
+    let mut hits = vec![0; n];
+    pts.iter().for_each(|p| hits[(p * n as Float) as usize] += 1);
+    hits.iter().all(|h| *h == 1)
+}
+
+#[test]
+fn permutations_are_bijections() {
+    for n in [1, 2, 5, 16, 100, 1000] {
+        for seed in [0, 1, 0xdead_beef] {
+            let mut seen = vec![false; n as usize];
+            for i in 0..n {
+                let j = permutation_element(i, n, seed);
+                assert!(!seen[j as usize]);
+                seen[j as usize] = true;
+            }
+        }
+    }
+}
+
+#[test]
+fn samples_in_unit_square() {
+    for sampler in ALL {
+        for sample in draw(sampler, 64, 40) {
+            assert!(sample.iter().all(|v| {
+                (0.0..1.0).contains(&v.x) && (0.0..1.0).contains(&v.y)
+            }));
+        }
+    }
+}
+
+#[test]
+fn samplers_deterministic() {
+    for sampler in ALL {
+        assert!(draw(sampler, 16, 4) == draw(sampler, 16, 4));
+    }
+}
+
+#[test]
+fn sobol_first_dimensions_form_nets() {
+    for n in [16, 64, 256] {
+        let samples = draw(SamplerType::Sobol, n, 3);
+        let pts: Vec<Vec2> = samples.iter().map(|s| s[0]).collect();
+        assert!(is_02_net(&pts));
+
+        for dim in 1..3 {
+            let xs: Vec<Float> = samples.iter().map(|s| s[dim].x).collect();
+            let ys: Vec<Float> = samples.iter().map(|s| s[dim].y).collect();
+            assert!(is_stratified(&xs));
+            assert!(is_stratified(&ys));
+        }
+    }
+}
+
+#[test]
+fn pmj02_dimensions_form_nets() {
+    for n in [16, 64, 256] {
+        let samples = draw(SamplerType::Pmj02, n, 8);
+        for dim in 0..8 {
+            let pts: Vec<Vec2> = samples.iter().map(|s| s[dim]).collect();
+            assert!(is_02_net(&pts));
+        }
+    }
+}
+
+#[test]
+fn pmj02_progressive() {
+    // every power of two prefix is stratified
+    let samples = draw(SamplerType::Pmj02, 256, 1);
+    let pts: Vec<Vec2> = samples.iter().map(|s| s[0]).collect();
+    for m in 0..=8 {
+        assert!(is_02_net(&pts[0..1 << m]));
+    }
+}
+
+#[test]
+fn halton_dimensions_stratified() {
+    // first two dimensions have bases 2 and 3
+    let samples = draw(SamplerType::Halton, 64, 1);
+    let xs: Vec<Float> = samples.iter().map(|s| s[0].x).collect();
+    assert!(is_stratified(&xs));
+
+    let samples = draw(SamplerType::Halton, 81, 1);
+    let ys: Vec<Float> = samples.iter().map(|s| s[0].y).collect();
+    assert!(is_stratified(&ys));
+}
+
+#[test]
+fn jittered_strata_covered() {
+    let samples = draw(SamplerType::Jittered, 64, 2);
+    for dim in 0..2 {
+        let mut hits = [0; 64];
+        for s in &samples {
+            let xy = (s[dim] * 8.0).floor();
+            hits[(xy.x + 8.0 * xy.y) as usize] += 1;
+        }
+        assert!(hits.iter().all(|h| *h == 1));
+    }
+    assert!(SamplerType::Jittered.sample_count(10) == 9);
+    assert!(SamplerType::Sobol.sample_count(10) == 10);
+}
+
+#[test]
+fn halton_scrambling() {
+    // digits shifted by the same amount for both points
+    let a = halton::scrambled_radical_inverse(3, 1, 9);
+    let b = halton::scrambled_radical_inverse(3, 2, 9);
+    assert!((a * 3.0).floor() != (b * 3.0).floor());
+    assert!((a - b).abs() > 0.0);
+    assert!(halton::scrambled_radical_inverse(2, 1, 9)
+            != halton::scrambled_radical_inverse(2, 1, 10));
+}
@@ -0,0 +1,112 @@
+use super::*;
+
+/// Number of dimensions of the Sobol sequence. Further dimensions get
+/// padded with differently scrambled copies.
+pub const SOBOL_DIMENSIONS: usize = 6;
+
+/// Degree, coefficients and initial direction numbers of the primitive
+/// polynomials for dimensions after the first. Joe & Kuo 2008
+const POLYNOMIALS: [(usize, u32, [u32; 4]); SOBOL_DIMENSIONS - 1] = [
+    (1, 0, [1, 0, 0, 0]),
+    (2, 1, [1, 3, 0, 0]),
+    (3, 1, [1, 3, 1, 0]),
+    (3, 2, [1, 1, 1, 0]),
+    (4, 1, [1, 1, 3, 3]),
+];
+
+/// Direction numbers of each dimension
+const DIRECTIONS: [[u32; 32]; SOBOL_DIMENSIONS] = directions();
+
+const fn directions() -> [[u32; 32]; SOBOL_DIMENSIONS] {
+    let mut v = [[0; 32]; SOBOL_DIMENSIONS];
+
+    // first dimension is the van der Corput sequence
+    let mut k = 0;
+    while k < 32 {
+        v[0][k] = 1 << (31 - k);
+        k += 1;
+    }
+
+    let mut d = 1;
+    while d < SOBOL_DIMENSIONS {
+        let (s, a, m) = POLYNOMIALS[d - 1];
+        let mut k = 0;
+        while k < 32 {
+            if k < s {
+                v[d][k] = m[k] << (31 - k);
+            } else {
+                v[d][k] = v[d][k - s] ^ (v[d][k - s] >> s);
+                let mut l = 1;
+                while l < s {
+                    if (a >> (s - 1 - l)) & 1 == 1 {
+                        v[d][k] ^= v[d][k - l];
+                    }
+                    l += 1;
+                }
+            }
+            k += 1;
+        }
+        d += 1;
+    }
+
+    v
+}
+
+/// Dimension `dim` of the `index`th point of the Sobol sequence
+pub fn sobol(mut index: u32, dim: usize) -> u32 {
+    let mut x = 0;
+    let mut k = 0;
+    while index != 0 {
+        if index & 1 == 1 {
+            x ^= DIRECTIONS[dim][k];
+        }
+        index >>= 1;
+        k += 1;
+    }
+    x
+}
+
+/// Owen scrambled Sobol sequence. Each pixel gets its own scrambling and
+/// shuffling of the sample indices, which keeps the stratification of the
+/// sequence. Dimensions after the first `SOBOL_DIMENSIONS` come from copies
+/// of the sequence with their own shuffling. Burley 2020
+pub struct SobolSampler {
+    seed: u64,
+    pixel: IVec2,
+    index: u32,
+    dim: u32,
+}
+
+impl SobolSampler {
+    /// Constructs a Sobol sampler with scrambling seeded by `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { seed, pixel: IVec2::ZERO, index: 0, dim: 0 }
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, pixel: IVec2, index: u32) {
+        self.pixel = pixel;
+        self.index = index;
+        self.dim = 0;
+    }
+
+    fn get_1d(&mut self) -> Float {
+        let dim = self.dim as usize;
+        self.dim += 1;
+
+        // each copy of the sequence has its own shuffling of the indices
+        let copy = (dim / SOBOL_DIMENSIONS) as u32;
+        let shuffle = rand_utils::hash(hash_dimension(self.seed, self.pixel, copy)) as u32;
+        let index = owen_scramble(self.index, shuffle);
+
+        let scramble = hash_dimension(self.seed, self.pixel, dim as u32) as u32;
+        to_float(owen_scramble(sobol(index, dim % SOBOL_DIMENSIONS), scramble))
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let x = self.get_1d();
+        let y = self.get_1d();
+        Vec2::new(x, y)
+    }
+}
@@ -0,0 +1,180 @@
+use crate::Float;
+use std::{array, cell::Cell, fmt, ops::AddAssign, time::Duration};
+
+#[cfg(test)]
+mod stats_tests;
+
+/// Counters of `RenderStats` incremented while rendering
+#[derive(Clone, Copy, Debug)]
+pub enum Counter {
+    /// `RenderStats::camera_rays`
+    CameraRays,
+    /// `RenderStats::shadow_rays`
+    ShadowRays,
+    /// `RenderStats::connections`
+    Connections,
+    /// `RenderStats::kd_nodes_visited`
+    KdNodesVisited,
+    /// `RenderStats::primitive_tests`
+    PrimitiveTests,
+    /// `RenderStats::path_vertices`
+    PathVertices,
+    /// `RenderStats::rr_terminations`
+    RrTerminations,
+    /// `RenderStats::nan_samples`
+    NanSamples,
+    /// `RenderStats::negative_samples`
+    NegativeSamples,
+}
+
+/// Number of variants in `Counter`
+const NUM_COUNTERS: usize = 9;
+
+thread_local! {
+    // one cell per counter keeps counting in hot loops to a single add
+    static COUNTERS: [Cell<u64>; NUM_COUNTERS] = const {[
+        Cell::new(0), Cell::new(0), Cell::new(0),
+        Cell::new(0), Cell::new(0), Cell::new(0),
+        Cell::new(0), Cell::new(0), Cell::new(0),
+    ]};
+}
+
+/// Adds `n` to `counter` of the current thread
+#[inline]
+pub fn count(counter: Counter, n: u64) {
+    COUNTERS.with(|counters| {
+        let cell = &counters[counter as usize];
+        cell.set(cell.get() + n);
+    })
+}
+
+/// Counters of the current thread so far
+pub fn current() -> RenderStats {
+    COUNTERS.with(|counters| stats_of(array::from_fn(|i| counters[i].get())))
+}
+
+/// Returns the counters of the current thread and resets them
+pub fn take() -> RenderStats {
+    COUNTERS.with(|counters| stats_of(array::from_fn(|i| counters[i].take())))
+}
+
+/// Statistics of the `values` of the counters
+fn stats_of(values: [u64; NUM_COUNTERS]) -> RenderStats {
+    let value = |counter: Counter| values[counter as usize];
+    RenderStats {
+        elapsed: Duration::ZERO,
+        camera_rays: value(Counter::CameraRays),
+        shadow_rays: value(Counter::ShadowRays),
+        connections: value(Counter::Connections),
+        kd_nodes_visited: value(Counter::KdNodesVisited),
+        primitive_tests: value(Counter::PrimitiveTests),
+        path_vertices: value(Counter::PathVertices),
+        rr_terminations: value(Counter::RrTerminations),
+        nan_samples: value(Counter::NanSamples),
+        negative_samples: value(Counter::NegativeSamples),
+    }
+}
+
+/// Cost of pixels to record in the heat map of the film
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HeatMap {
+    /// Time spent sampling the pixel in seconds
+    Time,
+    /// Number of kD-tree nodes visited and primitives tested
+    /// by the rays of the pixel
+    Traversal,
+}
+
+/// Statistics of a render. Counted by each thread and summed up after
+/// each tile. Accessible from the rendered film with `Film::stats`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    /// Time spent rendering
+    pub elapsed: Duration,
+    /// Rays generated by the camera
+    pub camera_rays: u64,
+    /// Rays shot towards lights in next event estimation
+    pub shadow_rays: u64,
+    /// Light and camera subpaths connected in bidirectional path tracing
+    pub connections: u64,
+    /// Nodes of kD-trees visited by rays
+    pub kd_nodes_visited: u64,
+    /// Primitives in the leaves of kD-trees tested for intersection
+    pub primitive_tests: u64,
+    /// Vertices of camera paths scattering or absorbing light
+    pub path_vertices: u64,
+    /// Paths terminated by Russian roulette
+    pub rr_terminations: u64,
+    /// Samples with a NaN value, would show up as errors in tone mapping
+    pub nan_samples: u64,
+    /// Samples with a negative value
+    pub negative_samples: u64,
+}
+
+impl RenderStats {
+    /// Mean number of vertices in camera paths
+    pub fn average_path_length(&self) -> Float {
+        self.path_vertices as Float / self.camera_rays.max(1) as Float
+    }
+
+    /// Camera rays traced per second
+    pub fn rays_per_second(&self) -> Float {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 { 0.0 } else { self.camera_rays as Float / secs }
+    }
+
+    /// Kd-tree cost of the counters, used in the heat map
+    pub fn traversal_cost(&self) -> u64 {
+        self.kd_nodes_visited + self.primitive_tests
+    }
+
+    /// Statistics as a JSON object
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\n  \"elapsed\": {:.3},\n  \"camera_rays\": {},\n  \"rays_per_second\": {:.1},\n  \
+             \"shadow_rays\": {},\n  \"connections\": {},\n  \"kd_nodes_visited\": {},\n  \
+             \"primitive_tests\": {},\n  \"average_path_length\": {:.3},\n  \
+             \"rr_terminations\": {},\n  \"nan_samples\": {},\n  \"negative_samples\": {}\n}}\n",
+            self.elapsed.as_secs_f64(),
+            self.camera_rays,
+            self.rays_per_second(),
+            self.shadow_rays,
+            self.connections,
+            self.kd_nodes_visited,
+            self.primitive_tests,
+            self.average_path_length(),
+            self.rr_terminations,
+            self.nan_samples,
+            self.negative_samples,
+        )
+    }
+}
+
+impl AddAssign<&RenderStats> for RenderStats {
+    fn add_assign(&mut self, other: &Self) {
+        self.elapsed += other.elapsed;
+        self.camera_rays += other.camera_rays;
+        self.shadow_rays += other.shadow_rays;
+        self.connections += other.connections;
+        self.kd_nodes_visited += other.kd_nodes_visited;
+        self.primitive_tests += other.primitive_tests;
+        self.path_vertices += other.path_vertices;
+        self.rr_terminations += other.rr_terminations;
+        self.nan_samples += other.nan_samples;
+        self.negative_samples += other.negative_samples;
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Render time          {:#?}", self.elapsed)?;
+        writeln!(f, "Camera rays          {} ({:.0} / s)", self.camera_rays, self.rays_per_second())?;
+        writeln!(f, "Shadow rays          {}", self.shadow_rays)?;
+        writeln!(f, "BDPT connections     {}", self.connections)?;
+        writeln!(f, "kD-tree nodes        {}", self.kd_nodes_visited)?;
+        writeln!(f, "Primitive tests      {}", self.primitive_tests)?;
+        writeln!(f, "Average path length  {:.3}", self.average_path_length())?;
+        writeln!(f, "RR terminations      {}", self.rr_terminations)?;
+        write!(f, "NaN / negative       {} / {}", self.nan_samples, self.negative_samples)
+    }
+}
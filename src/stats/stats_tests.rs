@@ -0,0 +1,76 @@
+use super::*;
+
+#[test]
+fn counters_per_thread() {
+    take();
+    count(Counter::CameraRays, 2);
+    std::thread::spawn(|| {
+        count(Counter::CameraRays, 5);
+        assert!(current().camera_rays == 5);
+    }).join().unwrap();
+
+    assert!(take().camera_rays == 2);
+    assert!(current() == RenderStats::default());
+}
+
+#[test]
+fn counters_mapped_to_fields() {
+    take();
+    let counters = [
+        Counter::CameraRays,
+        Counter::ShadowRays,
+        Counter::Connections,
+        Counter::KdNodesVisited,
+        Counter::PrimitiveTests,
+        Counter::PathVertices,
+        Counter::RrTerminations,
+        Counter::NanSamples,
+        Counter::NegativeSamples,
+    ];
+    for (i, counter) in counters.iter().enumerate() {
+        count(*counter, i as u64 + 1);
+    }
+
+    let stats = take();
+    assert!(stats.camera_rays == 1);
+    assert!(stats.shadow_rays == 2);
+    assert!(stats.connections == 3);
+    assert!(stats.kd_nodes_visited == 4);
+    assert!(stats.primitive_tests == 5);
+    assert!(stats.path_vertices == 6);
+    assert!(stats.rr_terminations == 7);
+    assert!(stats.nan_samples == 8);
+    assert!(stats.negative_samples == 9);
+}
+
+#[test]
+fn summed_and_averaged() {
+    let mut stats = RenderStats {
+        elapsed: Duration::from_secs(2),
+        camera_rays: 10,
+        path_vertices: 25,
+        ..Default::default()
+    };
+    stats += &stats.clone();
+
+    assert!(stats.camera_rays == 20);
+    assert!(stats.average_path_length() == 2.5);
+    assert!(stats.rays_per_second() == 5.0);
+    assert!(RenderStats::default().average_path_length() == 0.0);
+}
+
+#[test]
+fn json_summary() {
+    let stats = RenderStats {
+        elapsed: Duration::from_millis(500),
+        shadow_rays: 7,
+        nan_samples: 1,
+        ..Default::default()
+    };
+    let json = stats.to_json();
+    assert!(json.starts_with('{') && json.trim_end().ends_with('}'));
+    assert!(json.contains("\"elapsed\": 0.500,"));
+    assert!(json.contains("\"shadow_rays\": 7,"));
+    assert!(json.contains("\"nan_samples\": 1,"));
+    assert!(stats.to_string().contains("Shadow rays          7"));
+}
@@ -1,6 +1,7 @@
-use crate::tracer::Color;
+use crate::tracer::{Color, ColorSpace};
 
 /// Enum for different tone mappers
+#[derive(Clone, Copy)]
 pub enum ToneMap {
     /// Applies no tone mapping
     NoMap,
@@ -15,22 +16,22 @@ pub enum ToneMap {
 }
 
 impl ToneMap {
-    /// Tone maps the `rgb` sample with channels in `\[0,∞\]`
-    pub fn map(&self, rgb: Color) -> Color {
+    /// Tone maps the `rgb` sample in `space` with channels in `\[0,∞\]`
+    pub fn map(&self, rgb: Color, space: ColorSpace) -> Color {
         #[cfg(debug_assertions)]
         if rgb.rgb.is_nan() {
-            println!("Found NaN during tone mapping.");
+            crate::logging::log_warn!("Found NaN during tone mapping");
             return Color::new(0, 255, 0);
         }
         #[cfg(debug_assertions)]
         if rgb.rgb.is_negative_bitmask() > 0 {
-            println!("Found negative value during tone mapping.");
+            crate::logging::log_warn!("Found negative value during tone mapping");
             return Color::new(255, 0, 0);
         }
         match self {
             Self::NoMap => rgb,
             Self::Clamp => rgb.clamp(0.0, 1.0),
-            Self::Reinhard => rgb / (1.0 + rgb.luminance()),
+            Self::Reinhard => rgb / (1.0 + rgb.luminance_in(space)),
             Self::HableFilmic => {
                 let exposure = 2.0;
                 let curr = Self::hable_partial(rgb * exposure);
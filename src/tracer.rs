@@ -1,6 +1,9 @@
-pub use camera::Camera;
-pub use color::Color;
-pub use film::{Film, FilmTile, FilmSample};
+pub use camera::{
+    Camera, ThinLens, Aperture, ApertureMask, LensSystem, LensElement, DOUBLE_GAUSS_50MM,
+    Projection, FisheyeMapping, Shutter,
+};
+pub use color::{Color, ColorSpace, Transfer};
+pub use film::{Film, FilmTile, FilmSample, ExrPrecision, PngDepth, Aov, Denoiser};
 pub use integrator::Integrator;
 pub use material::Material;
 pub use object::{
@@ -10,7 +13,8 @@ pub use object::{
 };
 pub use scene::Scene;
 pub use texture::Texture;
-pub use filter::Filter;
+pub use filter::{Filter, FilterSampler};
+pub use spectrum::{SampledWavelengths, Spectrum};
 
 mod bxdf;
 mod bsdf;
@@ -40,3 +44,5 @@ mod scene;
 mod texture;
 /// Filters for film samples
 mod filter;
+/// Spectral curves and sampled wavelengths of the spectral mode
+mod spectrum;
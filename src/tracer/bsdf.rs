@@ -2,6 +2,7 @@ use crate::{ Direction, Transport, Float, Vec2 };
 use crate::tracer::{ Color, bxdf::BxDF, onb::Onb, hit::Hit };
 
 #[allow(non_snake_case, clippy::upper_case_acronyms)]
+#[derive(Clone, Copy)]
 pub struct BSDF {
     BxDF: BxDF
 }
@@ -21,6 +22,16 @@ impl BSDF {
         self.BxDF.is_delta()
     }
 
+    /// Does the BSDF vary with wavelength?
+    pub fn is_dispersive(&self) -> bool {
+        self.BxDF.is_dispersive()
+    }
+
+    /// The BSDF at the wavelength `lambda`
+    pub fn at_wavelength(&self, lambda: Float) -> Self {
+        Self::new(self.BxDF.at_wavelength(lambda))
+    }
+
     /// Evaluate the BSDF
     pub fn f(
         &self,
@@ -44,6 +55,7 @@ impl BSDF {
         &self,
         wo: Direction,
         h: &Hit,
+        rand_u: Float,
         rand_sq: Vec2
     ) -> Option<Direction> {
         let ns = h.ns;
@@ -51,7 +63,7 @@ impl BSDF {
 
         let wo_local = uvw.to_local(wo);
 
-        self.BxDF.sample(wo_local, rand_sq)
+        self.BxDF.sample(wo_local, rand_u, rand_sq)
             .map(|wi| uvw.to_world(wi))
     }
 
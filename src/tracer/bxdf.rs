@@ -50,6 +50,26 @@ impl BxDF {
         }
     }
 
+    /// Do the optical constants of the BxDF vary with wavelength?
+    pub fn is_dispersive(&self) -> bool {
+        match self {
+            Self::MfDiffuse(mfd)
+                | Self::MfConductor(mfd)
+                | Self::MfDielectric(mfd) => mfd.is_dispersive(),
+            _ => false,
+        }
+    }
+
+    /// The BxDF with optical constants evaluated at the wavelength `lambda`
+    pub fn at_wavelength(&self, lambda: Float) -> Self {
+        match self {
+            Self::MfDiffuse(mfd) => Self::MfDiffuse(mfd.at_wavelength(lambda)),
+            Self::MfConductor(mfd) => Self::MfConductor(mfd.at_wavelength(lambda)),
+            Self::MfDielectric(mfd) => Self::MfDielectric(mfd.at_wavelength(lambda)),
+            _ => *self,
+        }
+    }
+
     pub fn f(
         &self,
         wo: Direction,
@@ -66,12 +86,14 @@ impl BxDF {
         }
     }
 
-    pub fn sample(&self, wo: Direction, rand_sq: Vec2) -> Option<Direction> {
+    /// Samples a leaving direction for `wo`. Dielectrics choose between
+    /// reflection and transmission with `rand_u`.
+    pub fn sample(&self, wo: Direction, rand_u: Float, rand_sq: Vec2) -> Option<Direction> {
         match self {
             Self::Lambertian => Some( rand_utils::square_to_cos_hemisphere(rand_sq) ),
             Self::MfDiffuse(_) => Some( rand_utils::square_to_cos_hemisphere(rand_sq) ),
             Self::MfConductor(mfd) => microfacet::conductor_sample(wo, mfd, rand_sq),
-            Self::MfDielectric(mfd) => microfacet::dielectric_sample(wo, mfd, rand_sq),
+            Self::MfDielectric(mfd) => microfacet::dielectric_sample(wo, mfd, rand_u, rand_sq),
             Self::None => None,
         }
     }
@@ -206,6 +206,7 @@ pub fn dielectric_f(
 pub fn dielectric_sample(
     wo: Direction,
     mfd: &MfDistribution,
+    rand_u: Float,
     rand_sq: Vec2
 ) -> Option<Direction> {
     let v = -wo;
@@ -219,7 +220,7 @@ pub fn dielectric_sample(
     let pr = mfd.f(v, wh);
     let pt = 1.0 - pr;
 
-    if rand_utils::rand_float() < pr / (pr + pt) {
+    if rand_u < pr / (pr + pt) {
         util::reflect(v, wh)
     } else {
         util::refract(mfd.eta(), v, wh)
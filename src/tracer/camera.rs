@@ -1,12 +1,30 @@
 use crate::{
     Point, Direction, Float, Vec2, Transform, Normal,
-    Mat3, Mat4, Vec4, Vec3, rand_utils, spherical_utils
+    Mat3, Mat4, Vec4, Vec3, spherical_utils,
+    animated_transform::AnimatedTransform, error::{self, Error}
 };
 use glam::IVec2;
 use crate::tracer::{
     film::FilmSample, ray::Ray, Color
 };
 
+pub use aperture::{Aperture, ApertureMask};
+pub use lens_system::{LensElement, LensSystem, DOUBLE_GAUSS_50MM};
+pub use projection::{FisheyeMapping, Projection};
+pub use shutter::Shutter;
+
+/// Shapes of the camera aperture
+mod aperture;
+/// Rays traced through systems of real lens elements
+mod lens_system;
+/// Projections of panoramic cameras
+mod projection;
+/// Exposure of the sensor over time
+mod shutter;
+
+#[cfg(test)]
+mod camera_tests;
+
 /// Common configuration for cameras
 pub struct CameraConfig {
     /// Camera position in world space
@@ -17,12 +35,21 @@ pub struct CameraConfig {
     pub focal_length: Float,
     /// Radius of the camera lens
     pub lens_radius: Float,
+    /// Shape of the aperture of the lens
+    pub aperture: Aperture,
+    /// Strength of cat's eye vignetting in `\[0,1\]`. Towards the edges
+    /// of the image the aperture gets clipped by the lens barrel.
+    pub cats_eye: Float,
     /// Screen space to camera space transformation
     pub screen_to_camera: Mat4,
     /// Raster space to screen space transformation
     pub raster_to_screen: Transform,
     /// Camera space to world space transformation
     camera_to_world: Transform,
+    /// Interval of time the sensor gets exposed for
+    pub shutter: Shutter,
+    /// Keyframed motion applied after `camera_to_world`
+    motion: AnimatedTransform,
 }
 
 impl CameraConfig {
@@ -36,13 +63,41 @@ impl CameraConfig {
         resolution: (i32, i32),
         screen_to_camera: Mat4,
     ) -> Self {
-        assert!(resolution.0 > 0 && resolution.1 > 0);
-        assert!(lens_radius >= 0.0);
-        assert!(origin != towards);
-        assert!(up.length() != 0.0);
+        error::unwrap(Self::try_new(
+            origin, towards, up, lens_radius, focal_length, resolution, screen_to_camera
+        ))
+    }
 
-        // x = right, y = up, z = towards
+    /// Fallible `new`. Fails if the resolution is empty, the lens radius
+    /// negative or the viewing direction or `up` degenerate.
+    pub fn try_new(
+        origin: Point,
+        towards: Point,
+        up: Direction,
+        lens_radius: Float,
+        focal_length: Float,
+        resolution: (i32, i32),
+        screen_to_camera: Mat4,
+    ) -> Result<Self, Error> {
+        error::check(resolution.0 > 0 && resolution.1 > 0, || format!(
+            "camera resolution must be positive, got {} x {}", resolution.0, resolution.1
+        ))?;
+        error::check(lens_radius >= 0.0 && lens_radius.is_finite(), || format!(
+            "lens radius must be non-negative, got {}", lens_radius
+        ))?;
+        error::check(focal_length >= 0.0 && focal_length.is_finite(), || format!(
+            "focal length must be non-negative, got {}", focal_length
+        ))?;
+        error::check(origin.is_finite() && towards.is_finite() && origin != towards, || format!(
+            "camera must look towards a point other than its origin, got {} towards {}",
+            origin, towards
+        ))?;
         let forward = (towards - origin).normalize();
+        error::check(up.is_finite() && forward.cross(up).length_squared() != 0.0, || format!(
+            "camera up must be non-zero and not parallel to the viewing direction, got {}", up
+        ))?;
+
+        // x = right, y = up, z = towards
         let right = forward.cross(up).normalize();
         let up = right.cross(forward);
         let camera_to_world = Transform::from_mat3_translation(
@@ -63,15 +118,19 @@ impl CameraConfig {
             * Transform::from_translation(Vec3::new(-screen_min.x, -screen_max.y, 0.0));
         let raster_to_screen = screen_to_raster.inverse();
 
-        Self {
+        Ok(Self {
             lens_radius,
+            aperture: Aperture::Circular,
+            cats_eye: 0.0,
             focal_length,
             origin,
             screen_to_camera,
             camera_to_world,
             raster_to_screen,
             resolution: IVec2::new(width, height),
-        }
+            shutter: Shutter::default(),
+            motion: AnimatedTransform::default(),
+        })
     }
 
     pub fn point_to_local(&self, xo: Point) -> Point {
@@ -111,6 +170,137 @@ impl CameraConfig {
         let raster_xyz = self.raster_to_screen.inverse().transform_point3(screen_xyz);
         raster_xyz.truncate()
     }
+
+    /// Samples a point on the lens in camera space
+    fn sample_lens(&self, rand_sq: Vec2) -> Point {
+        if self.lens_radius == 0.0 {
+            Point::ZERO
+        } else {
+            (self.lens_radius * self.aperture.sample(rand_sq)).extend(0.0)
+        }
+    }
+
+    /// Area density of sampling `xo_local` on the lens.
+    /// One for pinhole cameras.
+    fn lens_pdf(&self, xo_local: Point) -> Float {
+        if self.lens_radius == 0.0 {
+            1.0
+        } else {
+            let r = self.lens_radius;
+            self.aperture.pdf(xo_local.truncate() / r) / (r * r)
+        }
+    }
+
+    /// Point on the screen `\[-1,1\]^2` of `raster_xy`
+    fn raster_to_screen_xy(&self, raster_xy: Vec2) -> Vec2 {
+        self.raster_to_screen
+            .transform_point3(raster_xy.extend(0.0))
+            .truncate()
+    }
+
+    /// Point in raster space of `screen_xy` on the screen
+    fn screen_to_raster_xy(&self, screen_xy: Vec2) -> Vec2 {
+        self.raster_to_screen
+            .inverse()
+            .transform_point3(screen_xy.extend(0.0))
+            .truncate()
+    }
+
+    /// Width of the image divided by its height
+    fn aspect(&self) -> Float {
+        self.resolution.x as Float / self.resolution.y as Float
+    }
+
+    /// Is the point `xo_local` on the lens blocked by the lens barrel for
+    /// rays towards `raster_xy`. The barrel is a unit disk shifted towards
+    /// the point on the screen, giving the cat's eye shaped bokeh.
+    fn vignetted(&self, xo_local: Point, raster_xy: Vec2) -> bool {
+        if self.cats_eye == 0.0 || self.lens_radius == 0.0 {
+            return false;
+        }
+        let screen_xy = self.raster_to_screen_xy(raster_xy);
+        let lens_xy = xo_local.truncate() / self.lens_radius;
+        lens_xy.distance_squared(self.cats_eye * screen_xy) > 1.0
+    }
+}
+
+/// Physical parameters of a thin lens camera, see `Camera::thin_lens`.
+/// Lengths of the lens and the sensor are in millimeters, while scene
+/// units are taken to be meters.
+#[derive(Clone)]
+pub struct ThinLens {
+    /// Focal length of the lens in millimeters
+    pub focal_length: Float,
+    /// Ratio of the focal length to the diameter of the aperture
+    pub f_number: Float,
+    /// Distance to the plane in focus in meters
+    pub focus_distance: Float,
+    /// Width of the sensor in millimeters. The height follows from
+    /// the aspect ratio of the image.
+    pub sensor_width: Float,
+    /// Shape of the aperture
+    pub aperture: Aperture,
+    /// Strength of cat's eye vignetting in `\[0,1\]`
+    pub cats_eye: Float,
+}
+
+impl ThinLens {
+    /// Lens of `focal_length` millimeters at f-number `f_number` focused at
+    /// `focus_distance` meters. Defaults to a 36mm wide full frame sensor,
+    /// circular aperture and no vignetting.
+    pub fn new(focal_length: Float, f_number: Float, focus_distance: Float) -> Self {
+        Self {
+            focal_length,
+            f_number,
+            focus_distance,
+            sensor_width: 36.0,
+            aperture: Aperture::Circular,
+            cats_eye: 0.0,
+        }
+    }
+
+    /// Sets the width of the sensor in millimeters
+    pub fn sensor_width(mut self, sensor_width: Float) -> Self {
+        self.sensor_width = sensor_width;
+        self
+    }
+
+    /// Sets the shape of the aperture
+    pub fn aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    /// Sets the strength of cat's eye vignetting
+    pub fn cats_eye(mut self, cats_eye: Float) -> Self {
+        self.cats_eye = cats_eye;
+        self
+    }
+
+    /// Radius of the aperture in meters
+    fn lens_radius(&self) -> Float {
+        self.focal_length / (2.0 * self.f_number) / 1000.0
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        let positive = |v: Float| v > 0.0 && v.is_finite();
+        error::check(positive(self.focal_length), || format!(
+            "focal length must be positive, got {}", self.focal_length
+        ))?;
+        error::check(positive(self.f_number), || format!(
+            "f-number must be positive, got {}", self.f_number
+        ))?;
+        error::check(positive(self.focus_distance), || format!(
+            "focus distance must be positive, got {}", self.focus_distance
+        ))?;
+        error::check(positive(self.sensor_width), || format!(
+            "sensor width must be positive, got {}", self.sensor_width
+        ))?;
+        error::check((0.0..=1.0).contains(&self.cats_eye), || format!(
+            "cat's eye vignetting must be in [0,1], got {}", self.cats_eye
+        ))?;
+        self.aperture.validate()
+    }
 }
 
 /// Camera abstraction
@@ -119,6 +309,10 @@ pub enum Camera {
     Perspective(CameraConfig),
     /// Orthographic camera that preserves angles with configurable image plane scale
     Orthographic(CameraConfig, Float),
+    /// Camera with rays traced through a system of lens elements
+    Realistic(CameraConfig, Box<LensSystem>),
+    /// Pinhole camera with a wide angle projection, see `Projection`
+    Panoramic(CameraConfig, Projection),
 }
 
 impl Camera {
@@ -145,15 +339,35 @@ impl Camera {
         width: i32,
         height: i32,
     ) -> Self {
-        assert!(image_plane_scale > 0.0);
+        error::unwrap(Self::try_orthographic(
+            origin, towards, up, image_plane_scale, lens_radius, focal_length, width, height
+        ))
+    }
+
+    /// Fallible `orthographic`. Fails if `image_plane_scale` is not positive
+    /// or the configuration is invalid, see `CameraConfig::try_new`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_orthographic(
+        origin: Point,
+        towards: Point,
+        up: Direction,
+        image_plane_scale: Float,
+        lens_radius: Float,
+        focal_length: Float,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, Error> {
+        error::check(image_plane_scale > 0.0 && image_plane_scale.is_finite(), || format!(
+            "image plane scale must be positive, got {}", image_plane_scale
+        ))?;
 
         let near = 0.0;
         let far = 1.0;
         let camera_to_screen = Mat4::from_scale(Vec3::new(1.0, 1.0, 1.0 / (far - near)))
             * Mat4::from_translation(Vec3::new(0.0, 0.0, -near));
 
-        Self::Orthographic(
-            CameraConfig::new(
+        Ok(Self::Orthographic(
+            CameraConfig::try_new(
                 origin,
                 towards,
                 up,
@@ -161,9 +375,9 @@ impl Camera {
                 focal_length,
                 (width, height),
                 camera_to_screen.inverse(),
-            ),
+            )?,
             image_plane_scale,
-        )
+        ))
     }
 
     /// Perspective camera where sense of depth is more profound. Rays are cast
@@ -189,8 +403,47 @@ impl Camera {
         width: i32,
         height: i32,
     ) -> Self {
-        assert!(vfov > 0.0 && vfov < 180.0);
+        error::unwrap(Self::try_perspective(
+            origin, towards, up, vfov, lens_radius, focal_length, width, height
+        ))
+    }
+
+    /// Fallible `perspective`. Fails unless `vfov` is in `(0,180)` or if
+    /// the configuration is invalid, see `CameraConfig::try_new`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_perspective(
+        origin: Point,
+        towards: Point,
+        up: Direction,
+        vfov: Float,
+        lens_radius: Float,
+        focal_length: Float,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, Error> {
+        error::check(vfov > 0.0 && vfov < 180.0, || format!(
+            "vertical field of view must be in (0,180), got {}", vfov
+        ))?;
+
+        let tan_vfov_inv = 1.0 / (vfov.to_radians() / 2.0);
+        let camera_to_screen = Self::perspective_projection(Vec2::splat(tan_vfov_inv));
+
+        Ok(Self::Perspective(
+            CameraConfig::try_new(
+                origin,
+                towards,
+                up,
+                lens_radius,
+                focal_length,
+                (width, height),
+                camera_to_screen.inverse(),
+            )?
+        ))
+    }
 
+    /// Perspective projection from camera space to screen space. Points at
+    /// `1 / scale` on the plane `z = 1` map to the edges of the screen.
+    fn perspective_projection(scale: Vec2) -> Mat4 {
         let near = 1e-2;
         let far = 1e3;
         let a = far / (far - near);
@@ -201,21 +454,161 @@ impl Camera {
             Vec4::new(0.0, 0.0, a,   b),
             Vec4::new(0.0, 0.0, 1.0, 0.0),
         ).transpose();
-        let tan_vfov_inv = 1.0 / (vfov.to_radians() / 2.0);
-        let scale = Mat4::from_scale(Vec3::new(tan_vfov_inv, tan_vfov_inv, 1.0));
-        let camera_to_screen = scale * projection;
+        Mat4::from_scale(scale.extend(1.0)) * projection
+    }
 
-        Self::Perspective(
-            CameraConfig::new(
-                origin,
-                towards,
-                up,
-                lens_radius,
-                focal_length,
-                (width, height),
-                camera_to_screen.inverse(),
-            )
-        )
+    /// Physically based thin lens camera. The field of view follows from the
+    /// focal length and the size of the sensor, depth of field from the
+    /// f-number and the focus distance.
+    ///
+    /// # Arguments
+    /// * `origin` - Camera position in world space
+    /// * `towards` - Point in world space the camera is looking at
+    /// * `up` - Up direction of the camera
+    /// * `lens` - Parameters of the lens and the sensor
+    /// * `width` - Width of the rendered image
+    /// * `height` - Height of the rendered image
+    pub fn thin_lens(
+        origin: Point,
+        towards: Point,
+        up: Direction,
+        lens: ThinLens,
+        width: i32,
+        height: i32,
+    ) -> Self {
+        error::unwrap(Self::try_thin_lens(origin, towards, up, lens, width, height))
+    }
+
+    /// Fallible `thin_lens`. Fails if the parameters of `lens` are not
+    /// positive, the aperture has less than three blades or the
+    /// configuration is invalid, see `CameraConfig::try_new`.
+    pub fn try_thin_lens(
+        origin: Point,
+        towards: Point,
+        up: Direction,
+        lens: ThinLens,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, Error> {
+        lens.validate()?;
+
+        let sensor_height = lens.sensor_width * height as Float / width as Float;
+        let scale = 2.0 * lens.focal_length / Vec2::new(lens.sensor_width, sensor_height);
+        let camera_to_screen = Self::perspective_projection(scale);
+
+        let mut cfg = CameraConfig::try_new(
+            origin,
+            towards,
+            up,
+            lens.lens_radius(),
+            lens.focus_distance,
+            (width, height),
+            camera_to_screen.inverse(),
+        )?;
+        cfg.aperture = lens.aperture;
+        cfg.cats_eye = lens.cats_eye;
+
+        Ok(Self::Perspective(cfg))
+    }
+
+    /// Camera that traces rays through the lens elements of `lens`
+    /// onto a film behind it. Focused with `LensSystem::focus_distance`.
+    /// Not supported by the light tracing strategies of bidirectional
+    /// path tracing.
+    ///
+    /// # Arguments
+    /// * `origin` - Camera position in world space, i.e. center of the film
+    /// * `towards` - Point in world space the camera is looking at
+    /// * `up` - Up direction of the camera
+    /// * `lens` - Lens system in front of the film
+    /// * `film_diagonal` - Length of the diagonal of the film in millimeters
+    /// * `width` - Width of the rendered image
+    /// * `height` - Height of the rendered image
+    pub fn realistic(
+        origin: Point,
+        towards: Point,
+        up: Direction,
+        lens: LensSystem,
+        film_diagonal: Float,
+        width: i32,
+        height: i32,
+    ) -> Self {
+        error::unwrap(Self::try_realistic(
+            origin, towards, up, lens, film_diagonal, width, height
+        ))
+    }
+
+    /// Fallible `realistic`. Fails if the film diagonal or focus distance
+    /// are not positive, the lens can not be focused at the distance or
+    /// the configuration is invalid, see `CameraConfig::try_new`.
+    pub fn try_realistic(
+        origin: Point,
+        towards: Point,
+        up: Direction,
+        lens: LensSystem,
+        film_diagonal: Float,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, Error> {
+        let cfg = CameraConfig::try_new(
+            origin,
+            towards,
+            up,
+            0.0,
+            0.0,
+            (width, height),
+            Mat4::IDENTITY,
+        )?;
+        let resolution = Vec2::new(width as Float, height as Float);
+        let lens = lens.build(film_diagonal, resolution)?;
+
+        Ok(Self::Realistic(cfg, Box::new(lens)))
+    }
+
+    /// Panoramic camera with rays cast from a single point in the directions
+    /// given by `projection`, e.g. 360° equirectangular images or fisheye lenses.
+    ///
+    /// # Arguments
+    /// * `origin` - Camera position in world space
+    /// * `towards` - Point in world space the camera is looking at, i.e. the
+    ///   center of the image
+    /// * `up` - Up direction of the camera
+    /// * `projection` - Mapping from the image to directions
+    /// * `width` - Width of the rendered image
+    /// * `height` - Height of the rendered image
+    pub fn panoramic(
+        origin: Point,
+        towards: Point,
+        up: Direction,
+        projection: Projection,
+        width: i32,
+        height: i32,
+    ) -> Self {
+        error::unwrap(Self::try_panoramic(origin, towards, up, projection, width, height))
+    }
+
+    /// Fallible `panoramic`. Fails if the field of view of `projection` is
+    /// invalid or the configuration is invalid, see `CameraConfig::try_new`.
+    pub fn try_panoramic(
+        origin: Point,
+        towards: Point,
+        up: Direction,
+        projection: Projection,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, Error> {
+        projection.validate()?;
+        let cfg = CameraConfig::try_new(
+            origin,
+            towards,
+            up,
+            0.0,
+            0.0,
+            (width, height),
+            Mat4::IDENTITY,
+        )?;
+
+        Ok(Self::Panoramic(cfg, projection))
     }
 
     /// The "default" camera. Perspective camera at world space origin
@@ -235,55 +628,260 @@ impl Camera {
 
     fn get_cfg(&self) -> &CameraConfig {
         match self {
-            Self::Orthographic(cfg, _) | Self::Perspective(cfg) => cfg,
+            Self::Orthographic(cfg, _) | Self::Perspective(cfg)
+                | Self::Realistic(cfg, _) | Self::Panoramic(cfg, _) => cfg,
         }
     }
 
+    fn get_cfg_mut(&mut self) -> &mut CameraConfig {
+        match self {
+            Self::Orthographic(cfg, _) | Self::Perspective(cfg)
+                | Self::Realistic(cfg, _) | Self::Panoramic(cfg, _) => cfg,
+        }
+    }
+
+    /// Sets the interval of time the sensor gets exposed for.
+    /// Rays get cast at random times within the interval.
+    pub fn set_shutter(&mut self, shutter: Shutter) {
+        self.get_cfg_mut().shutter = shutter;
+    }
+
+    /// Returns the shutter of the camera
+    pub fn shutter(&self) -> Shutter {
+        self.get_cfg().shutter
+    }
+
+    /// Adds a keyframe to the motion of the camera. At `time` the camera
+    /// gets moved by the rigid `transform` in world space. Between
+    /// keyframes the rotation and translation get interpolated.
+    pub fn add_keyframe(&mut self, time: Float, transform: Transform) {
+        error::unwrap(self.try_add_keyframe(time, transform))
+    }
+
+    /// Fallible `add_keyframe`. Fails if `time` is not finite or
+    /// `transform` is not a rotation followed by a translation.
+    pub fn try_add_keyframe(&mut self, time: Float, transform: Transform) -> Result<(), Error> {
+        let mut motion = self.get_cfg().motion.clone();
+        motion.add(time, transform)?;
+        error::check(motion.is_rigid(), || {
+            "camera keyframes must not scale the camera".to_string()
+        })?;
+        self.get_cfg_mut().motion = motion;
+        Ok(())
+    }
+
+    /// Can rays from the scene be connected to the camera. Needed for
+    /// the light tracing strategies of bidirectional path tracing.
+    pub fn supports_light_tracing(&self) -> bool {
+        !matches!(self, Self::Realistic(..))
+    }
+
     /// Returns the resolution of the image
     pub fn get_resolution(&self) -> IVec2 {
         self.get_cfg().resolution
     }
 
-    /// Adds depth of field to camera space ray and transform to world space ray
-    fn add_dof(xo_local: Point, wi_local: Direction, cfg: &CameraConfig) -> Ray {
+    /// Depth of the world space point `xo` in camera space at `time`.
+    /// Distance to the camera for panoramic cameras.
+    pub fn depth(&self, xo: Point, time: Float) -> Float {
+        let xo = match self.motion(time) {
+            None => xo,
+            Some(motion) => motion.inverse().transform_point3(xo),
+        };
+        match self {
+            Self::Panoramic(cfg, _) => cfg.origin.distance(xo),
+            _ => self.get_cfg().point_to_local(xo).z,
+        }
+    }
+
+    /// Adds depth of field to camera space ray and transform to world space ray.
+    /// `rand_sq` chooses the point on the aperture. `None` if the point is
+    /// blocked by cat's eye vignetting.
+    fn add_dof(
+        xo_local: Point,
+        wi_local: Direction,
+        cfg: &CameraConfig,
+        raster_xy: Vec2,
+        rand_sq: Vec2,
+    ) -> Option<Ray> {
         let (xo_local, wi_local) = if cfg.lens_radius == 0.0 {
             (xo_local, wi_local)
         } else {
-            let lens_xy = cfg.lens_radius
-                * rand_utils::square_to_disk(rand_utils::unit_square());
-            let lens_xyz = lens_xy.extend(0.0);
+            let lens_xyz = cfg.sample_lens(rand_sq);
+            if cfg.vignetted(lens_xyz, raster_xy) {
+                return None;
+            }
 
             let focus_distance = cfg.focal_length / wi_local.z;
 
-            let focus_xyz = focus_distance * wi_local;
+            let focus_xyz = xo_local + focus_distance * wi_local;
 
-            (lens_xyz, focus_xyz - lens_xyz)
+            (xo_local + lens_xyz, focus_xyz - xo_local - lens_xyz)
         };
 
         let xo = cfg.camera_to_world.transform_point3(xo_local);
         let wi = cfg.camera_to_world.transform_vector3(wi_local);
-        Ray::new(xo, wi)
+        Some(Ray::new(xo, wi))
+    }
+
+    /// Motion of the camera at `time`. `None` if the camera does not move.
+    fn motion(&self, time: Float) -> Option<Transform> {
+        let cfg = self.get_cfg();
+        if cfg.motion.is_empty() {
+            None
+        } else {
+            Some(cfg.motion.at(time))
+        }
+    }
+
+    /// Moves `ro` with the rigid `transform`
+    fn transform_ray(ro: &Ray, transform: Transform) -> Ray {
+        let mut ro = ro.transform(transform);
+        ro.dir = ro.dir.normalize();
+        ro
+    }
+
+    /// Samples the time of a ray towards `raster_xy` within the shutter
+    /// interval using `rand_u`
+    pub fn sample_time(&self, raster_xy: Vec2, rand_u: Float) -> Float {
+        let cfg = self.get_cfg();
+        cfg.shutter.sample(raster_xy.y / cfg.resolution.y as Float, rand_u)
     }
 
     /// Generates a ray given a point in raster space `\[0,width\] x \[0,height\]`
-    pub fn generate_ray(&self, raster_xy: Vec2) -> Ray {
+    /// at `time`. `rand_sq` chooses the point on the lens. Returns the ray and
+    /// the weight of the radiance it carries, or `None` if no light reaches the
+    /// sensor along the ray, e.g. due to vignetting.
+    pub fn generate_ray(
+        &self,
+        raster_xy: Vec2,
+        rand_sq: Vec2,
+        time: Float,
+    ) -> Option<(Ray, Float)> {
+        let (ro, weight) = self.generate_ray_at_rest(raster_xy, rand_sq)?;
+        let mut ro = match self.motion(time) {
+            None => ro,
+            Some(motion) => Self::transform_ray(&ro, motion),
+        };
+        ro.time = time;
+        Some((ro, weight))
+    }
+
+    /// Samples a ray leaving from the lens of the camera towards `xi`
+    /// at `time`
+    pub fn sample_towards(&self, xi: Point, rand_sq: Vec2, time: Float) -> Ray {
+        let mut ro = match self.motion(time) {
+            None => self.sample_towards_at_rest(xi, rand_sq),
+            Some(motion) => {
+                let xi = motion.inverse().transform_point3(xi);
+                Self::transform_ray(&self.sample_towards_at_rest(xi, rand_sq), motion)
+            }
+        };
+        ro.time = time;
+        ro
+    }
+
+    /// Probability that `ro` towards `xi` got sampled
+    pub fn sample_towards_pdf(&self, ro: &Ray, xi: Point) -> Float {
+        match self.motion(ro.time) {
+            None => self.sample_towards_pdf_at_rest(ro, xi),
+            Some(motion) => {
+                let inv_motion = motion.inverse();
+                self.sample_towards_pdf_at_rest(
+                    &Self::transform_ray(ro, inv_motion),
+                    inv_motion.transform_point3(xi),
+                )
+            }
+        }
+    }
+
+    /// Density of the camera sampling `xi` at `time` with a ray leaving from
+    /// `xo` with respect to surface area at `xi`. `ni` is the geometric normal
+    /// at `xi`, `None` if `xi` is in a medium.
+    pub fn pdf_xi(&self, xo: Point, xi: Point, ni: Option<Normal>, time: Float) -> Float {
+        match self.motion(time) {
+            None => self.pdf_xi_at_rest(xo, xi, ni),
+            Some(motion) => {
+                let inv_motion = motion.inverse();
+                self.pdf_xi_at_rest(
+                    inv_motion.transform_point3(xo),
+                    inv_motion.transform_point3(xi),
+                    ni.map(|ni| inv_motion.transform_vector3(ni)),
+                )
+            }
+        }
+    }
+
+    /// PDF for `wi` direction at `time`. Zero for orthographic cameras
+    /// without depth of field as all of their rays have the same direction,
+    /// see `pdf_xi`.
+    pub fn pdf_wi(&self, wi: Direction, time: Float) -> Float {
+        match self.motion(time) {
+            None => self.pdf_wi_at_rest(wi),
+            Some(motion) => self.pdf_wi_at_rest(motion.inverse().transform_vector3(wi)),
+        }
+    }
+
+    /// Incident importance for the ray `ro` starting from the camera lens.
+    /// Light arriving at a time the row is not exposed at gets discarded.
+    pub fn importance_sample(&self, ro: &Ray) -> FilmSample {
+        let mut sample = match self.motion(ro.time) {
+            None => self.importance_sample_at_rest(ro),
+            Some(motion) => {
+                self.importance_sample_at_rest(&Self::transform_ray(ro, motion.inverse()))
+            }
+        };
+        let cfg = self.get_cfg();
+        let v = sample.raster_xy.y / cfg.resolution.y as Float;
+        sample.color = sample.color * cfg.shutter.weight(ro.time, v);
+        sample
+    }
+
+    /// `generate_ray` with the camera in its pose without motion
+    fn generate_ray_at_rest(&self, raster_xy: Vec2, rand_sq: Vec2) -> Option<(Ray, Float)> {
         match self {
             Self::Perspective(cfg) => {
                 let wi_local = cfg.raster_to_camera(raster_xy).normalize();
-                Self::add_dof(Point::ZERO, wi_local, cfg)
+                Self::add_dof(Point::ZERO, wi_local, cfg, raster_xy, rand_sq)
+                    .map(|ro| (ro, 1.0))
             }
             Self::Orthographic(cfg, scale) => {
                 let xo_local = *scale * cfg.raster_to_camera(raster_xy);
-                Self::add_dof(xo_local, Direction::Z, cfg)
+                Self::add_dof(xo_local, Direction::Z, cfg, raster_xy, rand_sq)
+                    .map(|ro| (ro, 1.0))
+            }
+            Self::Realistic(cfg, lens) => {
+                let film_xyz = lens.film_point(cfg.raster_to_screen_xy(raster_xy));
+                let (xo_local, wi_local, weight) = lens.generate_ray(film_xyz, rand_sq)?;
+                let ro = Ray::new(cfg.point_to_world(xo_local), cfg.direction_to_world(wi_local));
+                Some((ro, weight))
+            }
+            Self::Panoramic(cfg, projection) => {
+                let screen_xy = cfg.raster_to_screen_xy(raster_xy);
+                let wi_local = projection.direction(screen_xy, cfg.aspect())?;
+                Some((Ray::new(cfg.origin, cfg.direction_to_world(wi_local)), 1.0))
             }
         }
     }
 
-    /// Samples a ray leaving from the lens of the camera towards `xi`
-    pub fn sample_towards(&self, xi: Point, rand_sq: Vec2) -> Ray {
+    /// `sample_towards` with the camera in its pose without motion
+    fn sample_towards_at_rest(&self, xi: Point, rand_sq: Vec2) -> Ray {
         let cfg = self.get_cfg();
-        let xo_local = rand_utils::square_to_disk(rand_sq).extend(0.0)
-            * cfg.lens_radius;
+        let lens_xyz = cfg.sample_lens(rand_sq);
+        let xo_local = match self {
+            Self::Orthographic(..) => {
+                // rays through the same point on the plane of focus leave
+                // the image plane at the point under it, offset by the lens
+                let xi_local = cfg.point_to_local(xi);
+                let lens_xy = if cfg.lens_radius == 0.0 {
+                    Vec2::ZERO
+                } else {
+                    xi_local.z / cfg.focal_length * lens_xyz.truncate()
+                };
+                (xi_local.truncate() + lens_xy).extend(0.0)
+            }
+            _ => lens_xyz,
+        };
         let xo = cfg.point_to_world(xo_local);
 
         let wi = (xi - xo).normalize();
@@ -291,31 +889,87 @@ impl Camera {
         Ray::new(xo, wi)
     }
 
-    /// Probability that `ro` towards `xi` got sampled
-    pub fn sample_towards_pdf(&self, ro: &Ray, xi: Point) -> Float {
+    /// `sample_towards_pdf` with the camera in its pose without motion
+    fn sample_towards_pdf_at_rest(&self, ro: &Ray, xi: Point) -> Float {
+        if !self.supports_light_tracing() {
+            return 0.0;
+        }
         let cfg = self.get_cfg();
         let xo = ro.origin;
         let wi = ro.dir;
-        let ng = cfg.normal_to_world(Normal::Z);
-
-        let lens_area = if cfg.lens_radius == 0.0 {
-            1.0
-        } else {
-            cfg.lens_radius * cfg.lens_radius * crate::PI
+        if let Self::Orthographic(..) = self {
+            return if cfg.direction_to_local(wi).z <= 0.0 {
+                0.0
+            } else if cfg.lens_radius == 0.0 {
+                // the direction is fixed, so the ray is sampled with certainty
+                1.0
+            } else {
+                self.pdf_wi_at_rest(wi)
+            };
+        }
+        // panoramic cameras have no image plane, so the cosine term is
+        // included in the density of the directions
+        let cos_theta = match self {
+            Self::Panoramic(..) => 1.0,
+            _ => cfg.normal_to_world(Normal::Z).dot(wi),
         };
+        let lens_pdf = cfg.lens_pdf(cfg.point_to_local(xo));
 
-        let pdf = xi.distance_squared(xo) / (ng.dot(wi) * lens_area);
+        let pdf = xi.distance_squared(xo) * lens_pdf / cos_theta;
         pdf.max(0.0)
     }
 
-    /// PDF for `wi` direction.
-    pub fn pdf_wi(&self, wi: Direction) -> Float {
+    /// `pdf_xi` with the camera in its pose without motion
+    fn pdf_xi_at_rest(&self, xo: Point, xi: Point, ni: Option<Normal>) -> Float {
+        let wi = (xi - xo).normalize();
+        let cos_theta = ni.map_or(1.0, |ni| wi.dot(ni).abs());
+        match self {
+            // rays from the image plane are parallel, so the density on the
+            // image plane gets projected to `xi` without falloff
+            Self::Orthographic(cfg, scale) if cfg.lens_radius == 0.0 => {
+                if cfg.direction_to_local(wi).z <= 0.0 {
+                    0.0
+                } else {
+                    cos_theta / Self::image_plane_area(cfg, *scale)
+                }
+            }
+            _ => self.pdf_wi_at_rest(wi) * cos_theta / xi.distance_squared(xo),
+        }
+    }
+
+    /// Area of the image plane of an orthographic camera in camera space
+    fn image_plane_area(cfg: &CameraConfig, scale: Float) -> Float {
+        let res = cfg.resolution;
+        let p_min = scale * cfg.raster_to_camera(Vec2::new(0.0, 0.0));
+        let p_max = scale * cfg.raster_to_camera(Vec2::new(res.x as Float, res.y as Float));
+
+        ((p_max.x - p_min.x) * (p_max.y - p_min.y)).abs()
+    }
+
+    /// `pdf_wi` with the camera in its pose without motion
+    fn pdf_wi_at_rest(&self, wi: Direction) -> Float {
+        if !self.supports_light_tracing() {
+            // only used by the light tracing strategies
+            return 0.0;
+        }
         let cfg = self.get_cfg();
         let wi_local = cfg.direction_to_local(wi);
+        if let Self::Panoramic(_, projection) = self {
+            return projection.pdf(wi_local, cfg.aspect());
+        }
         let cos_theta = spherical_utils::cos_theta(wi_local);
 
         if cos_theta <= 0.0 {
             0.0
+        } else if let Self::Orthographic(..) = self {
+            if cfg.lens_radius == 0.0 {
+                return 0.0;
+            }
+            // the direction from any point on the lens towards the plane
+            // of focus corresponds to a single point on the lens
+            let fl = cfg.focal_length;
+            let lens_xyz = (-fl / cos_theta * wi_local.truncate()).extend(0.0);
+            cfg.lens_pdf(lens_xyz) * fl * fl / (cos_theta * cos_theta * cos_theta)
         } else {
             let area_coeff = {
                 // store in cfg?
@@ -332,10 +986,52 @@ impl Camera {
         }
     }
 
-    /// Incident importance for the ray `ro` starting from the camera lens
-    pub fn importance_sample(&self, ro: &Ray) -> FilmSample {
+    /// `importance_sample` with the camera in its pose without motion
+    fn importance_sample_at_rest(&self, ro: &Ray) -> FilmSample {
         match self {
-            Self::Orthographic(..) => unimplemented!(),
+            Self::Orthographic(cfg, scale) => {
+                let wi = ro.dir;
+                let wi_local = cfg.direction_to_local(wi);
+                let cos_theta = spherical_utils::cos_theta(wi_local);
+                if cos_theta <= 0.0 {
+                    return FilmSample::default();
+                }
+
+                // find the point on the image plane the ray got cast from
+                let lens_xyz = if cfg.lens_radius == 0.0 {
+                    Point::ZERO
+                } else {
+                    (-cfg.focal_length / cos_theta * wi_local.truncate()).extend(0.0)
+                };
+                let xo_local = cfg.point_to_local(ro.origin) - lens_xyz;
+                let raster_xy = cfg.camera_to_raster(xo_local / *scale);
+
+                if cfg.vignetted(lens_xyz, raster_xy) {
+                    return FilmSample::default();
+                }
+                // points on the image plane are sampled uniformly
+                let pdf_origin = 1.0 / Self::image_plane_area(cfg, *scale);
+                let albedo = if cfg.lens_radius == 0.0 {
+                    pdf_origin
+                } else {
+                    self.pdf_wi_at_rest(wi) * pdf_origin / cos_theta
+                };
+
+                FilmSample::new(Color::splat(albedo), raster_xy, true)
+            }
+            // rays can not be traced backwards through the exit pupil
+            Self::Realistic(..) => FilmSample::default(),
+            Self::Panoramic(cfg, projection) => {
+                let wi_local = cfg.direction_to_local(ro.dir);
+                match projection.screen_xy(wi_local, cfg.aspect()) {
+                    None => FilmSample::default(),
+                    Some(screen_xy) => {
+                        let raster_xy = cfg.screen_to_raster_xy(screen_xy);
+                        let albedo = projection.pdf(wi_local, cfg.aspect());
+                        FilmSample::new(Color::splat(albedo), raster_xy, true)
+                    }
+                }
+            }
             Self::Perspective(cfg) => {
                 let wi = ro.dir;
                 let wi_local = cfg.direction_to_local(wi);
@@ -354,12 +1050,11 @@ impl Camera {
                 let focus_local = cfg.point_to_local(focus);
                 let raster_xy = cfg.camera_to_raster(focus_local);
 
-                let lens_area = if cfg.lens_radius == 0.0 {
-                    1.0
-                } else {
-                    crate::PI * cfg.lens_radius * cfg.lens_radius
-                };
-                let albedo = self.pdf_wi(wi) / (lens_area * cos_theta);
+                let xo_local = cfg.point_to_local(ro.origin);
+                if cfg.vignetted(xo_local, raster_xy) {
+                    return FilmSample::default();
+                }
+                let albedo = self.pdf_wi_at_rest(wi) * cfg.lens_pdf(xo_local) / cos_theta;
 
                 FilmSample::new(Color::splat(albedo), raster_xy, true)
             }
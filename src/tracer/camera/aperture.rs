@@ -0,0 +1,165 @@
+use crate::{Float, Vec2, Image, rand_utils, error::{self, Error}};
+use std::sync::Arc;
+
+/// Shape of the aperture of a camera lens. Gives the shape of the bokeh,
+/// i.e. out of focus highlights. Defined on `\[-1,1\]^2` and scaled by the
+/// lens radius of the camera.
+#[derive(Clone)]
+pub enum Aperture {
+    /// Unit disk, lens with infinitely many rounded blades
+    Circular,
+    /// Regular polygon inscribed in the unit disk. Parameters are the
+    /// number of blades and their rotation in degrees.
+    Polygonal(u32, Float),
+    /// Custom shape given by an image, see `Aperture::mask`
+    Mask(Arc<ApertureMask>),
+}
+
+impl Aperture {
+    /// Aperture shaped by the luminance of `image`. The image covers
+    /// `\[-1,1\]^2` and the luminance of each pixel is the fraction of light
+    /// let through. Black pixels block light entirely.
+    pub fn mask(image: &Image) -> Self {
+        error::unwrap(Self::try_mask(image))
+    }
+
+    /// Fallible `mask`. Fails if the image lets no light through.
+    pub fn try_mask(image: &Image) -> Result<Self, Error> {
+        Ok(Self::Mask(Arc::new(ApertureMask::try_new(image)?)))
+    }
+
+    /// Checks that the number of blades of a polygonal aperture is valid
+    pub(super) fn validate(&self) -> Result<(), Error> {
+        match self {
+            Self::Polygonal(blades, rotation) => error::check(
+                *blades >= 3 && rotation.is_finite(),
+                || format!("aperture needs at least 3 blades, got {}", blades),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Samples a point on the aperture using `rand_sq`
+    pub fn sample(&self, rand_sq: Vec2) -> Vec2 {
+        match self {
+            Self::Circular => rand_utils::square_to_disk(rand_sq),
+            Self::Polygonal(blades, rotation) => {
+                // choose a triangle between the center and a blade,
+                // then sample it uniformly
+                let n = *blades as Float;
+                let wedge = (rand_sq.x * n).floor().min(n - 1.0);
+                let u = rand_sq.x * n - wedge;
+                let v0 = Self::vertex(wedge, n, *rotation);
+                let v1 = Self::vertex(wedge + 1.0, n, *rotation);
+                u.sqrt() * v0.lerp(v1, rand_sq.y)
+            }
+            Self::Mask(mask) => mask.sample(rand_sq),
+        }
+    }
+
+    /// Area density of sampling `xy` with `sample`
+    pub fn pdf(&self, xy: Vec2) -> Float {
+        match self {
+            Self::Circular => {
+                if xy.length_squared() > 1.0 { 0.0 } else { 1.0 / crate::PI }
+            }
+            Self::Polygonal(blades, rotation) => {
+                let n = *blades as Float;
+                let half_wedge = crate::PI / n;
+                // angle relative to the apothem of the closest edge
+                let phi = xy.y.atan2(xy.x) - rotation.to_radians();
+                let phi = phi.rem_euclid(2.0 * half_wedge) - half_wedge;
+                if xy.length() * phi.cos() > half_wedge.cos() {
+                    0.0
+                } else {
+                    1.0 / (0.5 * n * (2.0 * half_wedge).sin())
+                }
+            }
+            Self::Mask(mask) => mask.pdf(xy),
+        }
+    }
+
+    /// Vertex `i` of a polygon with `n` blades
+    fn vertex(i: Float, n: Float, rotation: Float) -> Vec2 {
+        let theta = rotation.to_radians() + 2.0 * crate::PI * i / n;
+        Vec2::new(theta.cos(), theta.sin())
+    }
+}
+
+/// Tabulated distribution of the light let through by an image aperture
+pub struct ApertureMask {
+    width: usize,
+    height: usize,
+    /// Density of each pixel on `\[-1,1\]^2`
+    pdf: Vec<Float>,
+    /// Marginal cumulative distribution of the rows
+    cdf_rows: Vec<Float>,
+    /// Cumulative distributions of the pixels in each row
+    cdf_cols: Vec<Float>,
+}
+
+impl ApertureMask {
+    fn try_new(image: &Image) -> Result<Self, Error> {
+        let width = image.width as usize;
+        let height = image.height as usize;
+        let values: Vec<Float> = image.buffer.iter()
+            .map(|c| c.luminance().max(0.0))
+            .collect();
+        let total: Float = values.iter().sum();
+        error::check(total > 0.0 && total.is_finite(), || {
+            "aperture mask must let some light through".to_string()
+        })?;
+
+        let mut cdf_rows = vec![0.0; height + 1];
+        let mut cdf_cols = vec![0.0; height * (width + 1)];
+        for y in 0..height {
+            let row = &values[y * width..(y + 1) * width];
+            let cdf = &mut cdf_cols[y * (width + 1)..(y + 1) * (width + 1)];
+            for x in 0..width {
+                cdf[x + 1] = cdf[x] + row[x];
+            }
+            let row_total = cdf[width];
+            if row_total > 0.0 {
+                cdf.iter_mut().for_each(|c| *c /= row_total);
+            }
+            cdf_rows[y + 1] = cdf_rows[y] + row_total / total;
+        }
+
+        // pixels cover an area of 4 / (width * height) on the aperture
+        let scale = (width * height) as Float / (4.0 * total);
+        let pdf = values.iter().map(|v| v * scale).collect();
+
+        Ok(Self { width, height, pdf, cdf_rows, cdf_cols })
+    }
+
+    /// Samples a bin of `cdf` with `u`. Returns the bin and
+    /// the position within it.
+    fn sample_cdf(cdf: &[Float], u: Float) -> (usize, Float) {
+        let bins = cdf.len() - 1;
+        let bin = cdf.partition_point(|c| *c <= u).clamp(1, bins) - 1;
+        let width = cdf[bin + 1] - cdf[bin];
+        let t = if width > 0.0 { (u - cdf[bin]) / width } else { 0.5 };
+        (bin, t.clamp(0.0, 1.0))
+    }
+
+    fn sample(&self, rand_sq: Vec2) -> Vec2 {
+        let (y, ty) = Self::sample_cdf(&self.cdf_rows, rand_sq.y);
+        let row = &self.cdf_cols[y * (self.width + 1)..(y + 1) * (self.width + 1)];
+        let (x, tx) = Self::sample_cdf(row, rand_sq.x);
+
+        // first row of the image is the top of the aperture
+        Vec2::new(
+            -1.0 + 2.0 * (x as Float + tx) / self.width as Float,
+            1.0 - 2.0 * (y as Float + ty) / self.height as Float,
+        )
+    }
+
+    fn pdf(&self, xy: Vec2) -> Float {
+        if xy.abs().max_element() > 1.0 {
+            return 0.0;
+        }
+        let x = ((xy.x + 1.0) * 0.5 * self.width as Float) as usize;
+        let y = ((1.0 - xy.y) * 0.5 * self.height as Float) as usize;
+        self.pdf[y.min(self.height - 1) * self.width + x.min(self.width - 1)]
+    }
+}
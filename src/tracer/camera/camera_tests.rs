@@ -0,0 +1,262 @@
+use super::*;
+use crate::{Image, rand_utils};
+
+const NUM_SAMPLES: usize = 10_000;
+const WIDTH: i32 = 300;
+const HEIGHT: i32 = 200;
+
+fn thin_lens(lens: ThinLens) -> Camera {
+    Camera::thin_lens(Point::ZERO, Point::NEG_Z, Direction::Y, lens, WIDTH, HEIGHT)
+}
+
+fn apertures() -> Vec<Aperture> {
+    // ring shaped mask
+    let buffer = (0..64 * 64).map(|i| {
+        let xy = Vec2::new((i % 64) as Float, (i / 64) as Float) / 32.0 - 1.0;
+        let r = xy.length();
+        Color::splat(if (0.5..1.0).contains(&r) { 1.0 } else { 0.0 })
+    }).collect();
+    let image = Image { buffer, width: 64, height: 64 };
+
+    vec![Aperture::Circular, Aperture::Polygonal(6, 15.0), Aperture::mask(&image)]
+}
+
+#[test]
+fn aperture_pdfs_integrate_to_one() {
+    for aperture in apertures() {
+        // uniform samples of [-1,1]^2
+        let integral = (0..NUM_SAMPLES)
+            .map(|_| 4.0 * aperture.pdf(2.0 * rand_utils::unit_square() - 1.0))
+            .sum::<Float>() / NUM_SAMPLES as Float;
+        assert!((integral - 1.0).abs() < 0.05, "{}", integral);
+
+        for _ in 0..NUM_SAMPLES {
+            let xy = aperture.sample(rand_utils::unit_square());
+            assert!(aperture.pdf(xy) > 0.0);
+        }
+    }
+}
+
+#[test]
+fn thin_lens_field_of_view() {
+    let camera = thin_lens(ThinLens::new(50.0, 2.0, 3.0));
+    // center of the aperture, so the ray goes through the pinhole
+    let center = Vec2::splat(0.5);
+    let (right, _) = camera.generate_ray(Vec2::new(WIDTH as Float, 100.0), center, 0.0).unwrap();
+    let (top, _) = camera.generate_ray(Vec2::new(150.0, 0.0), center, 0.0).unwrap();
+
+    // 36 x 24 mm sensor behind a 50 mm lens
+    let angle = |wi: Direction| wi.angle_between(Direction::NEG_Z).to_degrees();
+    assert!((angle(right.dir) - (18.0 as Float / 50.0).atan().to_degrees()).abs() < 1e-6);
+    assert!((angle(top.dir) - (12.0 as Float / 50.0).atan().to_degrees()).abs() < 1e-6);
+}
+
+#[test]
+fn importance_matches_generated_rays() {
+    for aperture in apertures() {
+        let camera = thin_lens(ThinLens::new(35.0, 1.4, 2.0).aperture(aperture).cats_eye(0.5));
+        for _ in 0..1_000 {
+            let raster_xy = rand_utils::unit_square() * Vec2::new(WIDTH as Float, HEIGHT as Float);
+            let ro = match camera.generate_ray(raster_xy, rand_utils::unit_square(), 0.0) {
+                Some((ro, _)) => ro,
+                None => continue,
+            };
+            let sample = camera.importance_sample(&ro);
+            assert!(sample.raster_xy.distance(raster_xy) < 1e-6);
+            assert!(sample.color.luminance() > 0.0);
+        }
+    }
+}
+
+#[test]
+fn cats_eye_consistent_with_sample_towards() {
+    let camera = thin_lens(ThinLens::new(35.0, 1.4, 2.0)
+                           .aperture(Aperture::Polygonal(5, 0.0))
+                           .cats_eye(1.0));
+    let raster_xy = Vec2::new(10.0, 10.0);
+    let center = Vec2::splat(0.5);
+    let (ro, _) = camera.generate_ray(raster_xy, center, 0.0).unwrap();
+    // point in focus, seen at `raster_xy` through any point on the lens
+    let xi = ro.at(2.0 / ro.dir.dot(Direction::NEG_Z));
+
+    let blocked_camera = (0..NUM_SAMPLES)
+        .filter(|_| camera.generate_ray(raster_xy, rand_utils::unit_square(), 0.0).is_none())
+        .count() as Float / NUM_SAMPLES as Float;
+    let blocked_light = (0..NUM_SAMPLES)
+        .filter(|_| {
+            let ro = camera.sample_towards(xi, rand_utils::unit_square(), 0.0);
+            assert!(camera.sample_towards_pdf(&ro, xi) > 0.0);
+            camera.importance_sample(&ro).color.is_black()
+        })
+        .count() as Float / NUM_SAMPLES as Float;
+
+    assert!(blocked_camera > 0.1);
+    assert!((blocked_camera - blocked_light).abs() < 0.03);
+}
+
+fn realistic(focus_distance: Float) -> Camera {
+    let lens = LensSystem::double_gauss().focus_distance(focus_distance);
+    Camera::realistic(Point::ZERO, Point::NEG_Z, Direction::Y, lens, 43.3, WIDTH, HEIGHT)
+}
+
+/// Mean distance of rays towards `raster_xy` from their mean on the plane
+/// `depth` meters away from the film, and the mean weight of the rays
+fn ray_spread(camera: &Camera, raster_xy: Vec2, depth: Float) -> (Float, Float) {
+    let hits: Vec<(Vec2, Float)> = (0..NUM_SAMPLES)
+        .filter_map(|_| camera.generate_ray(raster_xy, rand_utils::unit_square(), 0.0))
+        .map(|(ro, weight)| {
+            let t = (-depth - ro.origin.z) / ro.dir.z;
+            (ro.at(t).truncate(), weight)
+        })
+        .collect();
+    let n = hits.len() as Float;
+    let mean = hits.iter().map(|(xy, _)| *xy).sum::<Vec2>() / n;
+    let spread = hits.iter().map(|(xy, _)| xy.distance(mean)).sum::<Float>() / n;
+    let weight = hits.iter().map(|(_, w)| *w).sum::<Float>() / NUM_SAMPLES as Float;
+    (spread, weight)
+}
+
+#[test]
+fn lens_table_parsed() {
+    assert!(LensSystem::from_table(DOUBLE_GAUSS_50MM).is_ok());
+    assert!(LensSystem::from_table("# comment\n\n10 2 1.5 8\n").is_ok());
+    assert!(LensSystem::from_table("10 2 1.5").is_err());
+    assert!(LensSystem::from_table("10 2 glass 8").is_err());
+    assert!(LensSystem::from_table("# empty").is_err());
+    assert!(LensSystem::double_gauss().try_aperture_diameter(8.0).is_ok());
+    assert!(LensSystem::double_gauss().try_aperture_diameter(30.0).is_err());
+}
+
+#[test]
+fn realistic_camera_focuses() {
+    let center = Vec2::new(WIDTH as Float, HEIGHT as Float) / 2.0;
+    for focus in [1.0, 3.0] {
+        let camera = realistic(focus);
+        let (in_focus, _) = ray_spread(&camera, center, focus);
+        let (near, _) = ray_spread(&camera, center, 0.5 * focus);
+        let (far, _) = ray_spread(&camera, center, 2.0 * focus);
+        assert!(in_focus < 0.2 * near && in_focus < 0.2 * far);
+    }
+}
+
+#[test]
+fn realistic_camera_vignettes() {
+    let camera = realistic(3.0);
+    let (_, center) = ray_spread(&camera, Vec2::new(WIDTH as Float, HEIGHT as Float) / 2.0, 3.0);
+    let (_, corner) = ray_spread(&camera, Vec2::new(1.0, 1.0), 3.0);
+    assert!(center > 0.5);
+    assert!(corner < 0.8 * center);
+    assert!(!camera.supports_light_tracing());
+}
+
+fn projections() -> Vec<(Projection, Float)> {
+    // projections with the solid angle they cover
+    vec![
+        (Projection::Equirectangular, 4.0 * crate::PI),
+        (Projection::CubeMap, 4.0 * crate::PI),
+        (Projection::Cylindrical(90.0), 4.0 * crate::PI * (45.0 as Float).to_radians().sin()),
+        (Projection::Fisheye(FisheyeMapping::Equidistant, 180.0), 2.0 * crate::PI),
+        (Projection::Fisheye(FisheyeMapping::Equisolid, 270.0),
+         2.0 * crate::PI * (1.0 - (135.0 as Float).to_radians().cos())),
+    ]
+}
+
+fn panoramic(projection: Projection) -> Camera {
+    Camera::panoramic(Point::ZERO, Point::NEG_Z, Direction::Y, projection, WIDTH, HEIGHT)
+}
+
+#[test]
+fn panoramic_rays_invertible() {
+    for (projection, _) in projections() {
+        let camera = panoramic(projection);
+        assert!(camera.supports_light_tracing());
+        for _ in 0..NUM_SAMPLES {
+            let raster_xy = rand_utils::unit_square() * Vec2::new(WIDTH as Float, HEIGHT as Float);
+            let ro = match camera.generate_ray(raster_xy, rand_utils::unit_square(), 0.0) {
+                Some((ro, _)) => ro,
+                None => continue,
+            };
+            let sample = camera.importance_sample(&ro);
+            assert!(sample.raster_xy.distance(raster_xy) < 1e-6,
+                    "{} != {}", sample.raster_xy, raster_xy);
+            assert!(sample.color.luminance() > 0.0);
+            assert!(camera.pdf_wi(ro.dir, 0.0) > 0.0);
+        }
+    }
+}
+
+#[test]
+fn panoramic_pdfs_cover_field_of_view() {
+    for (projection, solid_angle) in projections() {
+        let camera = panoramic(projection);
+        // expected inverse density over the screen is the covered solid angle
+        let integral = (0..NUM_SAMPLES)
+            .filter_map(|_| {
+                let raster_xy = rand_utils::unit_square()
+                    * Vec2::new(WIDTH as Float, HEIGHT as Float);
+                camera.generate_ray(raster_xy, Vec2::ZERO, 0.0)
+            })
+            .map(|(ro, _)| 1.0 / camera.pdf_wi(ro.dir, 0.0))
+            .sum::<Float>() / NUM_SAMPLES as Float;
+        assert!((integral / solid_angle - 1.0).abs() < 0.05, "{} != {}", integral, solid_angle);
+    }
+}
+
+#[test]
+fn orthographic_rays_invertible() {
+    for lens_radius in [0.0, 0.1] {
+        let camera = Camera::orthographic(
+            Point::ZERO, Point::NEG_Z, Direction::Y, 2.0, lens_radius, 3.0, WIDTH, HEIGHT
+        );
+        for _ in 0..1_000 {
+            let raster_xy = rand_utils::unit_square() * Vec2::new(WIDTH as Float, HEIGHT as Float);
+            let (ro, _) = camera.generate_ray(raster_xy, rand_utils::unit_square(), 0.0).unwrap();
+            let sample = camera.importance_sample(&ro);
+            assert!(sample.raster_xy.distance(raster_xy) < 1e-6);
+            assert!(sample.color.luminance() > 0.0);
+
+            // points in focus are seen at `raster_xy` through any point on the lens
+            let xi = ro.at(3.0 / ro.dir.dot(Direction::NEG_Z));
+            let ri = camera.sample_towards(xi, rand_utils::unit_square(), 0.0);
+            assert!(camera.sample_towards_pdf(&ri, xi) > 0.0);
+            assert!(camera.importance_sample(&ri).raster_xy.distance(raster_xy) < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn rolling_shutter_weights_unbiased() {
+    let shutter = Shutter::new(1.0, 3.0).rolling(0.5);
+    for v in [0.0, 0.3, 1.0] {
+        // mean weight over times of random rows is one for each row
+        let mean = (0..100_000)
+            .map(|_| {
+                let time = shutter.sample(rand_utils::rand_float(), rand_utils::rand_float());
+                assert!((1.0..=3.0).contains(&time));
+                shutter.weight(time, v)
+            })
+            .sum::<Float>() / 100_000.0;
+        assert!((mean - 1.0).abs() < 0.02, "{}", mean);
+    }
+}
+
+#[test]
+fn keyframed_camera_moves() {
+    let mut camera = Camera::default(WIDTH, HEIGHT);
+    camera.set_shutter(Shutter::new(0.0, 1.0));
+    camera.add_keyframe(0.0, Transform::IDENTITY);
+    camera.add_keyframe(1.0, Transform::from_translation(Vec3::X) * Transform::from_rotation_y(0.3));
+    let raster_xy = Vec2::new(1.5, 2.5);
+
+    let (r0, _) = camera.generate_ray(raster_xy, Vec2::ZERO, 0.0).unwrap();
+    let (r1, _) = camera.generate_ray(raster_xy, Vec2::ZERO, 1.0).unwrap();
+    assert!(r0.origin.distance(Point::ZERO) < 1e-6);
+    assert!(r1.origin.distance(Point::X) < 1e-6);
+    assert!((r1.time - 1.0).abs() < 1e-6);
+
+    for time in [0.0, 0.4, 1.0] {
+        let (ro, _) = camera.generate_ray(raster_xy, Vec2::ZERO, time).unwrap();
+        let sample = camera.importance_sample(&ro);
+        assert!(sample.raster_xy.distance(raster_xy) < 1e-6);
+    }
+}
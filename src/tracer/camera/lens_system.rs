@@ -0,0 +1,454 @@
+use crate::{Float, Vec2, Point, Direction, Normal, error::{self, Error}};
+use std::fs;
+
+/// Number of bins in the radial distance of film points with
+/// precomputed exit pupil bounds
+const PUPIL_BINS: usize = 64;
+/// Points on the rear element traced for each bin of the exit pupil
+const PUPIL_SAMPLES: usize = 64 * 64;
+/// Default distance of the plane in focus in meters
+const FOCUS_DISTANCE: Float = 10.0;
+
+/// Prescription of a double Gauss 50mm lens at f/2, scaled from
+/// US patent 2,673,491 in Modern Lens Design
+pub const DOUBLE_GAUSS_50MM: &str = "\
+# radius   thickness   ior     aperture
+29.475     3.76        1.67    25.2
+84.83      0.12        1       25.2
+19.275     4.025       1.67    23
+40.77      3.275       1.699   23
+12.75      5.705       1       18
+0          4.5         0       17.1
+-14.495    1.18        1.603   17
+40.77      6.065       1.658   20
+-20.385    0.19        1       20
+437.065    3.22        1.717   20
+-39.73     0           1       20
+";
+
+/// Spherical lens interface or the aperture stop of a lens system.
+/// Lengths in millimeters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LensElement {
+    /// Signed radius of curvature, positive if the center is towards
+    /// the film. Zero for the planar aperture stop.
+    pub curvature_radius: Float,
+    /// Distance along the optical axis to the next element
+    /// towards the film
+    pub thickness: Float,
+    /// Index of refraction of the medium behind the element,
+    /// one for air and zero for the aperture stop
+    pub ior: Float,
+    /// Diameter of the element
+    pub aperture: Float,
+}
+
+impl LensElement {
+    fn is_stop(&self) -> bool {
+        self.curvature_radius == 0.0
+    }
+}
+
+/// Ray in lens space. The film is at `z = 0` and the elements
+/// towards negative `z`.
+#[derive(Clone, Copy)]
+struct LensRay {
+    origin: Point,
+    dir: Direction,
+}
+
+impl LensRay {
+    fn at(&self, t: Float) -> Point {
+        self.origin + t * self.dir
+    }
+}
+
+/// Bounds of the exit pupil on the plane of the rear element
+#[derive(Clone, Copy)]
+struct PupilBounds {
+    min: Vec2,
+    max: Vec2,
+}
+
+impl PupilBounds {
+    fn area(&self) -> Float {
+        let d = (self.max - self.min).max(Vec2::ZERO);
+        d.x * d.y
+    }
+}
+
+/// System of spherical lens elements and an aperture stop that rays get
+/// traced through, as in a real camera lens. Produces the distortion, field
+/// curvature and vignetting of the lens. Described from the scene side
+/// towards the film by a prescription table, where each row has the radius
+/// of curvature, thickness, index of refraction and aperture diameter of an
+/// element in millimeters. See `DOUBLE_GAUSS_50MM`.
+#[derive(Clone)]
+pub struct LensSystem {
+    /// Elements in meters, from the scene side towards the film
+    elements: Vec<LensElement>,
+    focus_distance: Float,
+    /// Physical size of the film in meters
+    film_size: Vec2,
+    /// Exit pupil bounds of film points in bins of distance from
+    /// the center of the film
+    pupil_bounds: Vec<PupilBounds>,
+}
+
+impl LensSystem {
+    /// Lens system of `elements`, from the scene side towards the film.
+    /// Focused at 10 meters by default.
+    pub fn new(elements: Vec<LensElement>) -> Self {
+        error::unwrap(Self::try_new(elements))
+    }
+
+    /// Fallible `new`. Fails if there are no elements, the diameters
+    /// or thicknesses are not positive or indices of refraction negative.
+    pub fn try_new(elements: Vec<LensElement>) -> Result<Self, Error> {
+        error::check(!elements.is_empty(), || "lens system has no elements".to_string())?;
+        for (i, e) in elements.iter().enumerate() {
+            error::check(
+                e.curvature_radius.is_finite() && e.thickness >= 0.0
+                    && e.ior >= 0.0 && e.aperture > 0.0,
+                || format!("lens element {} is invalid: {:?}", i, e),
+            )?;
+        }
+
+        let elements = elements.iter()
+            .map(|e| LensElement {
+                curvature_radius: e.curvature_radius * 1e-3,
+                thickness: e.thickness * 1e-3,
+                ior: e.ior,
+                aperture: e.aperture * 1e-3,
+            })
+            .collect();
+
+        Ok(Self {
+            elements,
+            focus_distance: FOCUS_DISTANCE,
+            film_size: Vec2::ZERO,
+            pupil_bounds: vec![],
+        })
+    }
+
+    /// Lens system of a prescription table. Lines starting with `#` are comments.
+    pub fn from_table(table: &str) -> Result<Self, Error> {
+        let mut elements = vec![];
+        for (i, line) in table.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let values: Vec<Float> = line.split_whitespace()
+                .map(|v| v.parse::<Float>())
+                .collect::<Result<_, _>>()
+                .map_err(|err| Error::InvalidArgument(
+                    format!("line {} of lens table: {}", i + 1, err)
+                ))?;
+            error::check(values.len() == 4, || format!(
+                "line {} of lens table should have 4 values, got {}", i + 1, values.len()
+            ))?;
+            elements.push(LensElement {
+                curvature_radius: values[0],
+                thickness: values[1],
+                ior: values[2],
+                aperture: values[3],
+            });
+        }
+        Self::try_new(elements)
+    }
+
+    /// Lens system of the prescription table in the file at `path`
+    pub fn from_file(path: &str) -> Result<Self, Error> {
+        Self::from_table(&fs::read_to_string(path)?)
+    }
+
+    /// The 50mm double Gauss lens of `DOUBLE_GAUSS_50MM`
+    pub fn double_gauss() -> Self {
+        error::unwrap(Self::from_table(DOUBLE_GAUSS_50MM))
+    }
+
+    /// Sets the diameter of the aperture stop in millimeters. Can not be
+    /// larger than the diameter given in the prescription.
+    pub fn aperture_diameter(self, diameter: Float) -> Self {
+        error::unwrap(self.try_aperture_diameter(diameter))
+    }
+
+    /// Fallible `aperture_diameter`. Fails if the system has no
+    /// aperture stop or the diameter is not within the stop.
+    pub fn try_aperture_diameter(mut self, diameter: Float) -> Result<Self, Error> {
+        let stop = self.elements.iter_mut().find(|e| e.is_stop())
+            .ok_or_else(|| Error::InvalidArgument("lens system has no aperture stop".to_string()))?;
+        let max = stop.aperture * 1e3;
+        error::check(diameter > 0.0 && diameter <= max, || format!(
+            "aperture diameter must be in (0,{}] mm, got {}", max, diameter
+        ))?;
+        stop.aperture = diameter * 1e-3;
+        Ok(self)
+    }
+
+    /// Sets the distance to the plane in focus in meters
+    pub fn focus_distance(mut self, distance: Float) -> Self {
+        self.focus_distance = distance;
+        self
+    }
+
+    /// Focuses the lens and precomputes the exit pupil for a film with
+    /// `film_diagonal` millimeters and the aspect ratio of `resolution`
+    pub(super) fn build(mut self, film_diagonal: Float, resolution: Vec2) -> Result<Self, Error> {
+        error::check(film_diagonal > 0.0 && film_diagonal.is_finite(), || format!(
+            "film diagonal must be positive, got {}", film_diagonal
+        ))?;
+        error::check(self.focus_distance > 0.0 && self.focus_distance.is_finite(), || format!(
+            "focus distance must be positive, got {}", self.focus_distance
+        ))?;
+        let film_diagonal = film_diagonal * 1e-3;
+        self.film_size = film_diagonal * resolution / resolution.length();
+
+        let thickness = self.focus_thick_lens(self.focus_distance, film_diagonal)?;
+        self.elements.last_mut().unwrap().thickness = thickness;
+
+        let film_radius = 0.5 * film_diagonal;
+        self.pupil_bounds = (0..PUPIL_BINS)
+            .map(|i| {
+                let r0 = i as Float / PUPIL_BINS as Float * film_radius;
+                let r1 = (i + 1) as Float / PUPIL_BINS as Float * film_radius;
+                self.bound_exit_pupil(r0, r1)
+            })
+            .collect();
+
+        Ok(self)
+    }
+
+    /// Point on the film in lens space of screen space point `screen_xy`.
+    /// The lens flips the image on the film.
+    pub(super) fn film_point(&self, screen_xy: Vec2) -> Point {
+        (-0.5 * screen_xy * self.film_size).extend(0.0)
+    }
+
+    /// Generates a ray through the lens system from `film_xyz` using
+    /// `rand_sq` to choose the point on the exit pupil. Returns the ray
+    /// leaving the front element in camera space and its weight.
+    /// `None` if the ray gets blocked by the lens.
+    pub(super) fn generate_ray(
+        &self,
+        film_xyz: Point,
+        rand_sq: Vec2,
+    ) -> Option<(Point, Direction, Float)> {
+        let (rear_xyz, pupil_area) = self.sample_exit_pupil(film_xyz.truncate(), rand_sq);
+        let r = LensRay { origin: film_xyz, dir: (rear_xyz - film_xyz).normalize() };
+        let out = self.trace_from_film(r)?;
+
+        // irradiance falls off with the fourth power of the cosine,
+        // relative to the center of the film
+        let cos_theta = r.dir.z.abs();
+        let weight = cos_theta.powi(4) * pupil_area / self.pupil_bounds[0].area();
+
+        Some((Self::to_camera(out.origin), Self::to_camera(out.dir), weight))
+    }
+
+    /// Flips between lens space and camera space, where `z` points
+    /// towards the scene
+    fn to_camera(v: Point) -> Point {
+        Point::new(v.x, v.y, -v.z)
+    }
+
+    fn rear_z(&self) -> Float {
+        self.elements.last().unwrap().thickness
+    }
+
+    fn front_z(&self) -> Float {
+        self.elements.iter().map(|e| e.thickness).sum()
+    }
+
+    fn rear_radius(&self) -> Float {
+        0.5 * self.elements.last().unwrap().aperture
+    }
+
+    /// Intersects a spherical element of `radius` centered at `z_center`.
+    /// Returns the distance and the normal facing the ray.
+    fn intersect_element(radius: Float, z_center: Float, r: &LensRay) -> Option<(Float, Normal)> {
+        let o = r.origin - Point::new(0.0, 0.0, z_center);
+        let b = o.dot(r.dir);
+        let c = o.length_squared() - radius * radius;
+        let disc = b * b - c;
+        if disc < 0.0 {
+            return None;
+        }
+        let sqrt_disc = disc.sqrt();
+        let (t0, t1) = (-b - sqrt_disc, -b + sqrt_disc);
+        // choose the intersection on the side of the sphere facing the ray
+        let closer = (r.dir.z > 0.0) ^ (radius < 0.0);
+        let t = if closer { t0 } else { t1 };
+        if t < 0.0 {
+            return None;
+        }
+        let n = (o + t * r.dir).normalize();
+        let n = if n.dot(r.dir) > 0.0 { -n } else { n };
+        Some((t, n))
+    }
+
+    /// Refracts `wi` pointing away from the surface with normal `n`
+    /// on the same side, `eta` is the ratio of the indices of refraction
+    fn refract(wi: Direction, n: Normal, eta: Float) -> Option<Direction> {
+        let cos_i = n.dot(wi);
+        let sin2_i = (1.0 - cos_i * cos_i).max(0.0);
+        let sin2_t = eta * eta * sin2_i;
+        if sin2_t >= 1.0 {
+            return None;
+        }
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some((eta * -wi + (eta * cos_i - cos_t) * n).normalize())
+    }
+
+    /// Crosses the element `i` at `z` with `r`. `eta` is the ratio of the
+    /// indices of refraction, `None` at the aperture stop.
+    fn cross_element(&self, i: usize, z: Float, r: &mut LensRay, eta: Option<Float>) -> Option<()> {
+        let element = &self.elements[i];
+        let (t, n) = if element.is_stop() {
+            let t = (z - r.origin.z) / r.dir.z;
+            (t, Normal::Z)
+        } else {
+            let radius = element.curvature_radius;
+            Self::intersect_element(radius, z + radius, r)?
+        };
+        if t.is_nan() || t < 0.0 {
+            return None;
+        }
+
+        let hit = r.at(t);
+        if hit.truncate().length_squared() > 0.25 * element.aperture * element.aperture {
+            return None;
+        }
+        r.origin = hit;
+        if let (false, Some(eta)) = (element.is_stop(), eta) {
+            r.dir = Self::refract(-r.dir, n, eta)?;
+        }
+        Some(())
+    }
+
+    /// Index of refraction of the medium behind element `i`
+    fn ior(&self, i: usize) -> Float {
+        let ior = self.elements[i].ior;
+        if ior == 0.0 { 1.0 } else { ior }
+    }
+
+    /// Traces `r` from the film out of the front element
+    fn trace_from_film(&self, mut r: LensRay) -> Option<LensRay> {
+        let mut z = 0.0;
+        for i in (0..self.elements.len()).rev() {
+            z -= self.elements[i].thickness;
+            let eta_t = if i > 0 { self.ior(i - 1) } else { 1.0 };
+            self.cross_element(i, z, &mut r, Some(self.ior(i) / eta_t))?;
+        }
+        Some(r)
+    }
+
+    /// Traces `r` from the scene out of the rear element
+    fn trace_from_scene(&self, mut r: LensRay) -> Option<LensRay> {
+        let mut z = -self.front_z();
+        for i in 0..self.elements.len() {
+            let eta_i = if i > 0 { self.ior(i - 1) } else { 1.0 };
+            self.cross_element(i, z, &mut r, Some(eta_i / self.ior(i)))?;
+            z += self.elements[i].thickness;
+        }
+        Some(r)
+    }
+
+    /// Principal plane and focal point of the lens on the optical axis, in
+    /// lens space, from a ray entering parallel to the axis as `r_in` and
+    /// leaving as `r_out`
+    fn cardinal_points(r_in: &LensRay, r_out: &LensRay) -> (Float, Float) {
+        let tf = -r_out.origin.x / r_out.dir.x;
+        let tp = (r_in.origin.x - r_out.origin.x) / r_out.dir.x;
+        (r_out.at(tp).z, r_out.at(tf).z)
+    }
+
+    /// Thickness of the rear element that focuses the lens at
+    /// `focus_distance`. Approximates the system as a thick lens.
+    fn focus_thick_lens(&self, focus_distance: Float, film_diagonal: Float) -> Result<Float, Error> {
+        let x = 1e-3 * film_diagonal;
+        let failed = || Error::InvalidArgument(
+            "rays parallel to the optical axis do not pass through the lens system".to_string()
+        );
+
+        let r_scene = LensRay {
+            origin: Point::new(x, 0.0, -self.front_z() - 1.0),
+            dir: Direction::Z,
+        };
+        let r_film = self.trace_from_scene(r_scene).ok_or_else(failed)?;
+        let (pz0, fz0) = Self::cardinal_points(&r_scene, &r_film);
+
+        let r_film = LensRay {
+            origin: Point::new(x, 0.0, 1.0),
+            dir: Direction::NEG_Z,
+        };
+        let r_scene = self.trace_from_film(r_film).ok_or_else(failed)?;
+        let (pz1, _) = Self::cardinal_points(&r_film, &r_scene);
+
+        let f = fz0 - pz0;
+        let z = -focus_distance;
+        let c = (pz1 - z - pz0) * (pz1 - z - 4.0 * f - pz0);
+        error::check(c > 0.0, || format!(
+            "lens system can not focus at {} m", focus_distance
+        ))?;
+        let delta = 0.5 * (pz1 - z + pz0 - c.sqrt());
+        Ok(self.rear_z() + delta)
+    }
+
+    /// Bounds of the points on the rear element that film points from `r0`
+    /// to `r1` away from the center can see through the lens system
+    fn bound_exit_pupil(&self, r0: Float, r1: Float) -> PupilBounds {
+        let rear = 1.5 * self.rear_radius();
+        let n = (PUPIL_SAMPLES as Float).sqrt() as usize;
+        let mut min = Vec2::splat(Float::INFINITY);
+        let mut max = Vec2::splat(Float::NEG_INFINITY);
+
+        for i in 0..PUPIL_SAMPLES {
+            // golden ratio sequence decorrelates film points from the grid
+            let u = (i as Float * 0.618_033_988_749_895).fract();
+            let film = Point::new(r0 + u * (r1 - r0), 0.0, 0.0);
+            let grid = Vec2::new((i % n) as Float + 0.5, (i / n) as Float + 0.5) / n as Float;
+            let rear_xy = (2.0 * grid - 1.0) * rear;
+            let rear_xyz = rear_xy.extend(-self.rear_z());
+
+            let inside = rear_xy.cmpge(min).all() && rear_xy.cmple(max).all();
+            let r = LensRay { origin: film, dir: (rear_xyz - film).normalize() };
+            if inside || self.trace_from_film(r).is_some() {
+                min = min.min(rear_xy);
+                max = max.max(rear_xy);
+            }
+        }
+
+        if min.x > max.x {
+            return PupilBounds { min: Vec2::ZERO, max: Vec2::ZERO };
+        }
+        // expand by the spacing of the grid
+        let pad = 2.0 * rear / n as Float;
+        PupilBounds { min: min - pad, max: max + pad }
+    }
+
+    /// Samples a point on the exit pupil of `film_xy` with `rand_sq`.
+    /// Returns the point on the rear element and the area of the
+    /// sampled bounds.
+    fn sample_exit_pupil(&self, film_xy: Vec2, rand_sq: Vec2) -> (Point, Float) {
+        let r_film = film_xy.length();
+        let film_radius = 0.5 * self.film_size.length();
+        let idx = ((r_film / film_radius * PUPIL_BINS as Float) as usize).min(PUPIL_BINS - 1);
+        let bounds = self.pupil_bounds[idx];
+        let xy = bounds.min + rand_sq * (bounds.max - bounds.min);
+
+        // bounds are for film points on the x-axis, rotate to `film_xy`
+        let (sin, cos) = if r_film == 0.0 {
+            (0.0, 1.0)
+        } else {
+            (film_xy.y / r_film, film_xy.x / r_film)
+        };
+        let rear_xyz = Point::new(
+            cos * xy.x - sin * xy.y,
+            sin * xy.x + cos * xy.y,
+            -self.rear_z(),
+        );
+        (rear_xyz, bounds.area())
+    }
+}
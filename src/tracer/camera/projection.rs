@@ -0,0 +1,220 @@
+use crate::{Float, Vec2, Direction, PI, error::{self, Error}};
+
+/// Mapping of a fisheye lens from the angle between a direction and the
+/// optical axis to the distance from the center of the image circle
+#[derive(Clone, Copy)]
+pub enum FisheyeMapping {
+    /// Distance proportional to the angle
+    Equidistant,
+    /// Preserves solid angles, i.e. equal areas on the image
+    /// cover equal solid angles
+    Equisolid,
+}
+
+/// Projection of a panoramic camera from the screen `\[-1,1\]^2` to
+/// directions in camera space. Each projection is invertible, so the
+/// cameras support bidirectional path tracing.
+#[derive(Clone, Copy)]
+pub enum Projection {
+    /// Full sphere of directions with longitude along the width
+    /// and latitude along the height of the image
+    Equirectangular,
+    /// 360° around the up direction along the width and a
+    /// perspective projection along the height. Parameter is the
+    /// vertical field of view in degrees.
+    Cylindrical(Float),
+    /// Circular fisheye with the image circle inscribed in the image.
+    /// Parameters are the mapping of the lens and its field of view
+    /// in degrees, at most 360°.
+    Fisheye(FisheyeMapping, Float),
+    /// Six 90° perspective views in a 3 x 2 grid. The top row has the
+    /// right, left and up faces, the bottom row the down,
+    /// front and back faces.
+    CubeMap,
+}
+
+/// Forward, right and up directions of each face of the cube map
+/// in the order of the grid
+const CUBE_FACES: [(Direction, Direction, Direction); 6] = [
+    (Direction::X, Direction::NEG_Z, Direction::Y),
+    (Direction::NEG_X, Direction::Z, Direction::Y),
+    (Direction::Y, Direction::X, Direction::NEG_Z),
+    (Direction::NEG_Y, Direction::X, Direction::Z),
+    (Direction::Z, Direction::X, Direction::Y),
+    (Direction::NEG_Z, Direction::NEG_X, Direction::Y),
+];
+
+impl Projection {
+    /// Checks that the field of view is valid
+    pub(super) fn validate(&self) -> Result<(), Error> {
+        match self {
+            Self::Cylindrical(vfov) => error::check(*vfov > 0.0 && *vfov < 180.0, || format!(
+                "vertical field of view must be in (0,180), got {}", vfov
+            )),
+            Self::Fisheye(_, fov) => error::check(*fov > 0.0 && *fov <= 360.0, || format!(
+                "fisheye field of view must be in (0,360], got {}", fov
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Direction in camera space towards `screen_xy` on an image with the
+    /// aspect ratio `aspect`. `None` if outside the image circle of a fisheye.
+    pub(super) fn direction(&self, screen_xy: Vec2, aspect: Float) -> Option<Direction> {
+        match self {
+            Self::Equirectangular => {
+                let phi = PI * screen_xy.x;
+                let lat = 0.5 * PI * screen_xy.y;
+                Some(Direction::new(
+                    lat.cos() * phi.sin(),
+                    lat.sin(),
+                    lat.cos() * phi.cos(),
+                ))
+            }
+            Self::Cylindrical(vfov) => {
+                let phi = PI * screen_xy.x;
+                let h = screen_xy.y * (0.5 * vfov.to_radians()).tan();
+                Some(Direction::new(phi.sin(), h, phi.cos()).normalize())
+            }
+            Self::Fisheye(mapping, fov) => {
+                let xy = screen_xy * Self::circle_scale(aspect);
+                let r = xy.length();
+                if r > 1.0 {
+                    return None;
+                }
+                let half_fov = 0.5 * fov.to_radians();
+                let theta = match mapping {
+                    FisheyeMapping::Equidistant => r * half_fov,
+                    FisheyeMapping::Equisolid => {
+                        2.0 * (r * (0.5 * half_fov).sin()).min(1.0).asin()
+                    }
+                };
+                let xy = if r == 0.0 { Vec2::ZERO } else { xy / r };
+                Some((theta.sin() * xy).extend(theta.cos()))
+            }
+            Self::CubeMap => {
+                let cell = Vec2::new(
+                    (1.5 * (screen_xy.x + 1.0)).clamp(0.0, 3.0),
+                    (1.0 - screen_xy.y).clamp(0.0, 2.0),
+                );
+                let col = cell.x.floor().min(2.0);
+                let row = cell.y.floor().min(1.0);
+                let u = 2.0 * (cell.x - col) - 1.0;
+                let v = 1.0 - 2.0 * (cell.y - row);
+                let (forward, right, up) = CUBE_FACES[(3.0 * row + col) as usize];
+                Some((forward + u * right + v * up).normalize())
+            }
+        }
+    }
+
+    /// Point on the screen `\[-1,1\]^2` that sees the camera space direction
+    /// `wi_local`. `None` if the direction is outside the field of view.
+    pub(super) fn screen_xy(&self, wi_local: Direction, aspect: Float) -> Option<Vec2> {
+        let wi = wi_local.normalize();
+        match self {
+            Self::Equirectangular => {
+                let phi = wi.x.atan2(wi.z);
+                let lat = wi.y.clamp(-1.0, 1.0).asin();
+                Some(Vec2::new(phi / PI, lat / (0.5 * PI)))
+            }
+            Self::Cylindrical(vfov) => {
+                let phi = wi.x.atan2(wi.z);
+                let y = wi.y / wi.x.hypot(wi.z) / (0.5 * vfov.to_radians()).tan();
+                if y.abs() > 1.0 || y.is_nan() {
+                    None
+                } else {
+                    Some(Vec2::new(phi / PI, y))
+                }
+            }
+            Self::Fisheye(mapping, fov) => {
+                let theta = wi.z.clamp(-1.0, 1.0).acos();
+                let half_fov = 0.5 * fov.to_radians();
+                if theta > half_fov {
+                    return None;
+                }
+                let r = match mapping {
+                    FisheyeMapping::Equidistant => theta / half_fov,
+                    FisheyeMapping::Equisolid => (0.5 * theta).sin() / (0.5 * half_fov).sin(),
+                };
+                let xy = wi.truncate().try_normalize().unwrap_or(Vec2::ZERO);
+                Some(r * xy / Self::circle_scale(aspect))
+            }
+            Self::CubeMap => {
+                let face = (0..CUBE_FACES.len())
+                    .max_by(|a, b| {
+                        let cos = |i: &usize| wi.dot(CUBE_FACES[*i].0);
+                        cos(a).total_cmp(&cos(b))
+                    })
+                    .unwrap_or(0);
+                let (forward, right, up) = CUBE_FACES[face];
+                let wi = wi / wi.dot(forward);
+                let u = wi.dot(right).clamp(-1.0, 1.0);
+                let v = wi.dot(up).clamp(-1.0, 1.0);
+                let col = (face % 3) as Float;
+                let row = (face / 3) as Float;
+                Some(Vec2::new(
+                    2.0 * (col + 0.5 * (u + 1.0)) / 3.0 - 1.0,
+                    1.0 - (row + 0.5 * (1.0 - v)),
+                ))
+            }
+        }
+    }
+
+    /// Solid angle density of the directions of rays towards uniformly
+    /// sampled points on the screen
+    pub(super) fn pdf(&self, wi_local: Direction, aspect: Float) -> Float {
+        // screen has an area of 4
+        let jacobian = match self {
+            Self::Equirectangular => {
+                let cos_lat = wi_local.x.hypot(wi_local.z) / wi_local.length();
+                0.5 * PI * PI * cos_lat
+            }
+            Self::Cylindrical(vfov) => {
+                let h = wi_local.y / wi_local.x.hypot(wi_local.z);
+                let tan_vfov = (0.5 * vfov.to_radians()).tan();
+                PI * tan_vfov / (1.0 + h * h).powf(1.5)
+            }
+            Self::Fisheye(mapping, fov) => {
+                let theta = (wi_local.z / wi_local.length()).clamp(-1.0, 1.0).acos();
+                let half_fov = 0.5 * fov.to_radians();
+                if theta > half_fov {
+                    return 0.0;
+                }
+                let scale = Self::circle_scale(aspect);
+                let jacobian = match mapping {
+                    FisheyeMapping::Equidistant => if theta == 0.0 {
+                        half_fov * half_fov
+                    } else {
+                        half_fov * half_fov * theta.sin() / theta
+                    },
+                    FisheyeMapping::Equisolid => 4.0 * (0.5 * half_fov).sin().powi(2),
+                };
+                scale.x * scale.y * jacobian
+            }
+            Self::CubeMap => {
+                let wi = wi_local.normalize();
+                let cos = CUBE_FACES.iter()
+                    .map(|(forward, _, _)| wi.dot(*forward))
+                    .fold(0.0, Float::max);
+                // each face covers 1/6 of the screen
+                6.0 * cos * cos * cos
+            }
+        };
+
+        if jacobian > 0.0 && jacobian.is_finite() {
+            1.0 / (4.0 * jacobian)
+        } else {
+            0.0
+        }
+    }
+
+    /// Scale from the screen to the image circle of a fisheye,
+    /// which touches the shorter sides of the image
+    fn circle_scale(aspect: Float) -> Vec2 {
+        if aspect >= 1.0 {
+            Vec2::new(aspect, 1.0)
+        } else {
+            Vec2::new(1.0, 1.0 / aspect)
+        }
+    }
+}
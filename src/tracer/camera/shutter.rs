@@ -0,0 +1,88 @@
+use crate::{Float, error::{self, Error}};
+
+/// Interval of time the sensor of the camera gets exposed for, see
+/// `Camera::set_shutter`. Objects moving during the interval get
+/// motion blurred. Defaults to an instant at time zero.
+#[derive(Clone, Copy, Default)]
+pub struct Shutter {
+    /// Time the shutter opens at
+    pub open: Float,
+    /// Time the shutter closes at
+    pub close: Float,
+    /// Time it takes to read out the rows of a rolling shutter, from the top
+    /// to the bottom of the image. Each row gets exposed for
+    /// `close - open - readout`. Zero for a global shutter.
+    pub readout: Float,
+}
+
+impl Shutter {
+    /// Global shutter exposing every pixel from `open` to `close`
+    pub fn new(open: Float, close: Float) -> Self {
+        error::unwrap(Self::try_new(open, close))
+    }
+
+    /// Fallible `new`. Fails if `close` is before `open`.
+    pub fn try_new(open: Float, close: Float) -> Result<Self, Error> {
+        error::check(open.is_finite() && close.is_finite() && open <= close, || format!(
+            "shutter must close after it opens, got {} to {}", open, close
+        ))?;
+        Ok(Self { open, close, readout: 0.0 })
+    }
+
+    /// Rolling shutter that takes `readout` to read the rows of the image.
+    /// Fast moving objects get skewed.
+    pub fn rolling(self, readout: Float) -> Self {
+        error::unwrap(self.try_rolling(readout))
+    }
+
+    /// Fallible `rolling`. Fails if `readout` is negative or rows would
+    /// not get exposed, i.e. `readout` is not shorter than the interval.
+    pub fn try_rolling(mut self, readout: Float) -> Result<Self, Error> {
+        let duration = self.close - self.open;
+        error::check(readout == 0.0 || (readout > 0.0 && readout < duration), || format!(
+            "shutter readout must be in [0,{}), got {}", duration, readout
+        ))?;
+        self.readout = readout;
+        Ok(self)
+    }
+
+    /// Is the sensor exposed for an instant?
+    pub fn is_instant(&self) -> bool {
+        self.open == self.close
+    }
+
+    /// Start and end of the exposure of the row at `v` in `\[0,1\]`
+    /// from the top of the image
+    fn exposure(&self, v: Float) -> (Float, Float) {
+        let start = self.open + v * self.readout;
+        (start, start + self.close - self.open - self.readout)
+    }
+
+    /// Samples uniformly a time the row at `v` is exposed at using `rand_u`
+    pub(super) fn sample(&self, v: Float, rand_u: Float) -> Float {
+        let (start, end) = self.exposure(v.clamp(0.0, 1.0));
+        start + rand_u * (end - start)
+    }
+
+    /// Weight of light arriving at `time` on the row at `v`, when `time`
+    /// got sampled for a random row. Zero if the row is not exposed, otherwise
+    /// the inverse of the fraction of rows exposed at `time`.
+    pub(super) fn weight(&self, time: Float, v: Float) -> Float {
+        if self.readout == 0.0 {
+            return if self.is_instant() || (self.open..=self.close).contains(&time) {
+                1.0
+            } else {
+                0.0
+            };
+        }
+        let (start, end) = self.exposure(v);
+        if time < start || time > end {
+            return 0.0;
+        }
+        // rows with exposures containing `time`
+        let duration = self.close - self.open - self.readout;
+        let v_min = ((time - self.open - duration) / self.readout).max(0.0);
+        let v_max = ((time - self.open) / self.readout).min(1.0);
+        if v_max > v_min { 1.0 / (v_max - v_min) } else { 0.0 }
+    }
+}
@@ -4,6 +4,15 @@ use std::ops::{
     Mul, MulAssign, Div, DivAssign
 };
 
+pub use color_space::{ColorSpace, Transfer};
+pub(crate) use color_space::D50;
+
+#[cfg(test)]
+mod color_tests;
+
+/// RGB color spaces and transfer functions
+mod color_space;
+
 #[derive(Clone, Copy)]
 /// Abstraction for color using linear RGB values
 pub struct Color {
@@ -17,15 +26,42 @@ impl Color {
     /// White color
     pub const WHITE: Self = Self { rgb: Vec3::ONE };
 
-    /// Decodes 8-bit sRGB encoded `r`, `g`, and `b` channels to linear RGB.
+    /// Decodes 8-bit sRGB encoded `r`, `g`, and `b` channels to linear RGB
     pub fn new(r: u8, g: u8, b: u8) -> Self {
+        let rgb = Vec3::new(r as Float, g as Float, b as Float) / 255.0;
+        Self::from_encoded(rgb, ColorSpace::Srgb, Transfer::Srgb)
+    }
+
+    /// Decodes `rgb` encoded with `transfer` in `space` to linear sRGB
+    pub fn from_encoded(rgb: Vec3, space: ColorSpace, transfer: Transfer) -> Self {
         let rgb = Vec3::new(
-            (r as Float / 255.0).powf(2.2),
-            (g as Float / 255.0).powf(2.2),
-            (b as Float / 255.0).powf(2.2),
+            transfer.decode(rgb.x),
+            transfer.decode(rgb.y),
+            transfer.decode(rgb.z),
         );
+        Self::from_linear(rgb, space)
+    }
 
-        Self { rgb }
+    /// Converts linear `rgb` in `space` to linear sRGB
+    pub fn from_linear(rgb: Vec3, space: ColorSpace) -> Self {
+        let conversion = ColorSpace::conversion(space, ColorSpace::Srgb);
+        Self { rgb: conversion * rgb }
+    }
+
+    /// Linear RGB value of the color in `space`
+    pub fn to_linear(&self, space: ColorSpace) -> Vec3 {
+        ColorSpace::conversion(ColorSpace::Srgb, space) * self.rgb
+    }
+
+    /// Encodes the color to `space` with `transfer`. Values get clamped
+    /// to `\[0,1\]` before encoding.
+    pub fn encode(&self, space: ColorSpace, transfer: Transfer) -> Vec3 {
+        let rgb = self.to_linear(space).clamp(Vec3::ZERO, Vec3::ONE);
+        Vec3::new(
+            transfer.encode(rgb.x),
+            transfer.encode(rgb.y),
+            transfer.encode(rgb.z),
+        )
     }
 
     /// Splats `value` to each RGB channel
@@ -38,16 +74,21 @@ impl Color {
         self.rgb.dot(Vec3::new(0.2126, 0.7152, 0.0722))
     }
 
+    /// Maps linear RGB value in `space` to luminance
+    pub fn luminance_in(&self, space: ColorSpace) -> Float {
+        self.rgb.dot(space.rgb_to_xyz().row(1))
+    }
+
     /// LERP `self` with `other` using `c` as the coefficient
     pub fn lerp(&self, other: Self, c: Float) -> Self {
         Self { rgb: self.rgb.lerp(other.rgb, c) }
     }
 
-    /// Gamma encodes self
+    /// Encodes self to 8-bit sRGB
     pub fn gamma_enc(&self) -> (u8, u8, u8) {
-        let enc = self.rgb.powf(1.0 / 2.2) * 255.0;
+        let enc = self.encode(ColorSpace::Srgb, Transfer::Srgb) * 255.0;
 
-        (enc.x as u8, enc.y as u8, enc.z as u8)
+        (enc.x.round() as u8, enc.y.round() as u8, enc.z.round() as u8)
     }
 
     /// Clamps RGB channels between `lb` and `ub`
@@ -0,0 +1,148 @@
+use crate::{Float, Mat3, Vec2, Vec3};
+
+/// Bradford chromatic adaptation matrix, rows of the matrix
+const BRADFORD: [Float; 9] = [
+    0.8951, 0.2664, -0.1614,
+    -0.7502, 1.7135, 0.0367,
+    0.0389, -0.0685, 1.0296,
+];
+
+/// RGB color spaces defined by their primaries and white point. Colors are
+/// stored as linear sRGB and converted to the working space of the renderer
+/// while rendering.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorSpace {
+    /// sRGB and Rec. 709 primaries with D65 white point
+    Srgb,
+    /// ACES AP1 primaries with the ACES white point, close to D60
+    AcesCg,
+    /// Rec. 2020 primaries with D65 white point
+    Rec2020,
+}
+
+impl ColorSpace {
+    /// All of the color spaces
+    pub const ALL: [ColorSpace; 3] = [Self::Srgb, Self::AcesCg, Self::Rec2020];
+
+    /// CIE xy chromaticities of the red, green and blue primaries
+    /// followed by the white point
+    pub fn chromaticities(&self) -> [Vec2; 4] {
+        match self {
+            Self::Srgb => [
+                Vec2::new(0.64, 0.33),
+                Vec2::new(0.30, 0.60),
+                Vec2::new(0.15, 0.06),
+                Vec2::new(0.3127, 0.3290),
+            ],
+            Self::AcesCg => [
+                Vec2::new(0.713, 0.293),
+                Vec2::new(0.165, 0.830),
+                Vec2::new(0.128, 0.044),
+                Vec2::new(0.32168, 0.33767),
+            ],
+            Self::Rec2020 => [
+                Vec2::new(0.708, 0.292),
+                Vec2::new(0.170, 0.797),
+                Vec2::new(0.131, 0.046),
+                Vec2::new(0.3127, 0.3290),
+            ],
+        }
+    }
+
+    /// Matrix from linear RGB of the space to CIE XYZ
+    pub fn rgb_to_xyz(&self) -> Mat3 {
+        let [r, g, b, w] = self.chromaticities();
+        let primaries = Mat3::from_cols(xy_to_xyz(r), xy_to_xyz(g), xy_to_xyz(b));
+        let scale = primaries.inverse() * xy_to_xyz(w);
+
+        primaries * Mat3::from_diagonal(scale)
+    }
+
+    /// Matrix that converts linear RGB values from `from` to `to`. White
+    /// points get adapted with the Bradford transform.
+    pub fn conversion(from: ColorSpace, to: ColorSpace) -> Mat3 {
+        if from == to {
+            return Mat3::IDENTITY;
+        }
+
+        let adapt = adaptation(
+            xy_to_xyz(from.chromaticities()[3]),
+            xy_to_xyz(to.chromaticities()[3]),
+        );
+
+        to.rgb_to_xyz().inverse() * adapt * from.rgb_to_xyz()
+    }
+
+    /// Matrix from linear RGB of the space to CIE XYZ adapted to the D50
+    /// white point of ICC profiles
+    pub fn rgb_to_xyz_d50(&self) -> Mat3 {
+        let white = xy_to_xyz(self.chromaticities()[3]);
+        adaptation(white, D50) * self.rgb_to_xyz()
+    }
+}
+
+/// CIE XYZ of the D50 white point
+pub(crate) const D50: Vec3 = Vec3::new(0.9642, 1.0, 0.8249);
+
+/// Bradford transform adapting CIE XYZ from white point `from` to `to`
+fn adaptation(from: Vec3, to: Vec3) -> Mat3 {
+    let bradford = Mat3::from_cols_array(&BRADFORD).transpose();
+    bradford.inverse()
+        * Mat3::from_diagonal((bradford * to) / (bradford * from))
+        * bradford
+}
+
+/// CIE XYZ with unit luminance of chromaticity `xy`
+fn xy_to_xyz(xy: Vec2) -> Vec3 {
+    Vec3::new(xy.x / xy.y, 1.0, (1.0 - xy.x - xy.y) / xy.y)
+}
+
+/// Transfer functions that map linear values to the encoded values
+/// stored in images
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Transfer {
+    /// Values stored as is
+    Linear,
+    /// Piecewise sRGB curve with a linear segment near black
+    Srgb,
+    /// Pure power curve with the given gamma, e.g. `2.2`
+    Gamma(Float),
+}
+
+impl Transfer {
+    /// Encodes linear value `x` in `\[0,1\]`
+    pub fn encode(&self, x: Float) -> Float {
+        match self {
+            Self::Linear => x,
+            Self::Srgb => if x <= 0.0031308 {
+                12.92 * x
+            } else {
+                1.055 * x.powf(1.0 / 2.4) - 0.055
+            },
+            Self::Gamma(gamma) => x.powf(1.0 / gamma),
+        }
+    }
+
+    /// Decodes encoded value `x` in `\[0,1\]` to linear
+    pub fn decode(&self, x: Float) -> Float {
+        match self {
+            Self::Linear => x,
+            Self::Srgb => if x <= 0.04045 {
+                x / 12.92
+            } else {
+                ((x + 0.055) / 1.055).powf(2.4)
+            },
+            Self::Gamma(gamma) => x.powf(*gamma),
+        }
+    }
+
+    /// Gamma approximating the transfer function. Stored in the gAMA chunk
+    /// of .png files.
+    pub fn gamma(&self) -> Float {
+        match self {
+            Self::Linear => 1.0,
+            Self::Srgb => 2.2,
+            Self::Gamma(gamma) => *gamma,
+        }
+    }
+}
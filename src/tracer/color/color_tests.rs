@@ -0,0 +1,73 @@
+use super::*;
+
+fn assert_close(a: Vec3, b: Vec3, eps: Float) {
+    assert!((a - b).abs().max_element() < eps, "{} != {}", a, b);
+}
+
+#[test]
+fn srgb_transfer_roundtrip() {
+    for i in 0..=100 {
+        let x = i as Float / 100.0;
+        let enc = Transfer::Srgb.encode(x);
+        assert!((Transfer::Srgb.decode(enc) - x).abs() < 1e-10);
+    }
+}
+
+#[test]
+fn srgb_transfer_values() {
+    assert!((Transfer::Srgb.encode(0.5) - 0.735357).abs() < 1e-5);
+    assert!((Transfer::Srgb.decode(0.5) - 0.214041).abs() < 1e-5);
+    // linear segment near black
+    assert!((Transfer::Srgb.encode(0.001) - 0.01292).abs() < 1e-10);
+    // continuous at the break point
+    let below = Transfer::Srgb.encode(0.0031308 - 1e-9);
+    let above = Transfer::Srgb.encode(0.0031308 + 1e-9);
+    assert!((below - above).abs() < 1e-6);
+}
+
+#[test]
+fn decodes_8bit_srgb() {
+    assert_close(Color::new(255, 255, 255).rgb, Vec3::ONE, 1e-12);
+    assert_close(Color::new(0, 0, 0).rgb, Vec3::ZERO, 1e-12);
+    let mid = Color::new(128, 128, 128).rgb;
+    assert_close(mid, Vec3::splat(0.215861), 1e-5);
+    assert!(Color::from(mid).gamma_enc() == (128, 128, 128));
+}
+
+#[test]
+fn srgb_to_xyz_matrix() {
+    let m = ColorSpace::Srgb.rgb_to_xyz();
+    assert_close(m.row(0), Vec3::new(0.4124, 0.3576, 0.1805), 1e-3);
+    assert_close(m.row(1), Vec3::new(0.2126, 0.7152, 0.0722), 1e-3);
+    assert_close(m.row(2), Vec3::new(0.0193, 0.1192, 0.9505), 1e-3);
+}
+
+#[test]
+fn conversions_roundtrip() {
+    let rgb = Vec3::new(0.2, 0.5, 0.9);
+    for from in ColorSpace::ALL {
+        for to in ColorSpace::ALL {
+            let there = ColorSpace::conversion(from, to) * rgb;
+            let back = ColorSpace::conversion(to, from) * there;
+            assert_close(back, rgb, 1e-9);
+        }
+    }
+}
+
+#[test]
+fn white_stays_white() {
+    for from in ColorSpace::ALL {
+        for to in ColorSpace::ALL {
+            let white = ColorSpace::conversion(from, to) * Vec3::ONE;
+            assert_close(white, Vec3::ONE, 1e-3);
+        }
+    }
+}
+
+#[test]
+fn srgb_red_in_wider_spaces() {
+    let red = ColorSpace::conversion(ColorSpace::Srgb, ColorSpace::Rec2020) * Vec3::X;
+    assert_close(red, Vec3::new(0.6274, 0.0691, 0.0164), 1e-3);
+    let red = ColorSpace::conversion(ColorSpace::Srgb, ColorSpace::AcesCg) * Vec3::X;
+    assert_close(red, Vec3::new(0.6131, 0.0702, 0.0206), 2e-3);
+}
@@ -1,8 +1,49 @@
-use crate::tracer::{filter::Filter, Color};
-use crate::{Float, Vec2};
+use crate::tracer::{filter::Filter, Color, ColorSpace, SampledWavelengths, Transfer};
+use crate::{Float, Vec2, Vec3, PostProcess, HeatMap, RenderStats, logging::log_info};
+use crate::error::{self, Error};
 use glam::IVec2;
-use png::{BitDepth, ColorType, Encoder, EncodingError};
-use std::{fs::File, io::BufWriter, path::Path, ops::AddAssign};
+use png::{
+    BitDepth, ColorType, Encoder, EncodingError, Info,
+    ScaledFloat, SourceChromaticities, SrgbRenderingIntent
+};
+use std::{borrow::Cow, fs::File, io::{self, BufWriter}, path::Path, ops::AddAssign};
+
+pub use aov::Aov;
+pub use denoise::Denoiser;
+pub use exr::ExrPrecision;
+use exr::ExrChannel;
+
+#[cfg(test)]
+mod film_tests;
+
+/// Arbitrary output variables
+mod aov;
+/// Saving and loading the accumulated state of a film
+mod checkpoint;
+/// Feature guided denoising of the resolved image
+mod denoise;
+/// Scanline OpenEXR writer
+mod exr;
+/// ICC profiles of the .png output encoding
+mod icc;
+/// Portable float map writer
+mod pfm;
+
+/// Smallest luminance used when computing relative errors of pixels. Avoids
+/// dark pixels getting sampled forever.
+const MIN_ERROR_LUMINANCE: Float = 1e-3;
+/// Pixels with a smaller sum of filter weights resolve to black. Negative
+/// lobes of filters can cancel out the weights of the samples.
+const MIN_FILTER_WEIGHT: Float = 1e-6;
+
+/// Bit depth of the channels in .png output
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PngDepth {
+    /// 8 bits per channel
+    Eight,
+    /// 16 bits per channel, avoids banding in dark gradients
+    Sixteen,
+}
 
 /// Sample for the film
 pub struct FilmSample {
@@ -12,6 +53,11 @@ pub struct FilmSample {
     pub color: Color,
     /// "Splat" sample i.e. from sampling camera
     pub splat: bool,
+    /// Values of arbitrary output variables written by the integrator
+    pub aovs: Vec<(Aov, Color)>,
+    /// Coverage of the sample. One if the camera ray hit something,
+    /// zero if it escaped the scene.
+    pub alpha: Float,
 }
 
 impl Default for FilmSample {
@@ -20,6 +66,8 @@ impl Default for FilmSample {
             raster_xy: Vec2::NEG_ONE,
             color: Color::BLACK,
             splat: true,
+            aovs: vec![],
+            alpha: 0.0,
         }
     }
 }
@@ -28,9 +76,35 @@ impl FilmSample {
     /// Creates a sample of `color` at raster `(x,y)`
     pub fn new(color: Color, raster_xy: Vec2, splat: bool) -> Self {
         Self {
-            raster_xy, color, splat,
+            raster_xy, color, splat, aovs: vec![], alpha: 0.0,
         }
     }
+
+    /// Sets the value of `aov` to `value`
+    pub fn set_aov(&mut self, aov: Aov, value: Color) {
+        match self.aovs.iter_mut().find(|(a, _)| *a == aov) {
+            Some((_, v)) => *v = value,
+            None => self.aovs.push((aov, value)),
+        }
+    }
+
+    /// Converts the spectral values of the sample at the wavelengths
+    /// `lambda` to RGB in `space`. AOVs other than radiance are already RGB.
+    pub fn spectral_to_rgb(&mut self, lambda: &SampledWavelengths, space: ColorSpace) {
+        self.color = lambda.to_rgb(self.color, space);
+        for (aov, value) in self.aovs.iter_mut() {
+            if matches!(aov, Aov::Direct | Aov::Indirect) {
+                *value = lambda.to_rgb(*value, space);
+            }
+        }
+    }
+
+    /// Value of `aov` in the sample, black if not written
+    pub fn aov(&self, aov: Aov) -> Color {
+        self.aovs.iter()
+            .find(|(a, _)| *a == aov)
+            .map_or(Color::BLACK, |(_, v)| *v)
+    }
 }
 
 // TODO: some memory saving available by making separate tile pixel (w.o. splat)
@@ -39,11 +113,63 @@ struct Pixel {
     pub color: Color,
     pub splat: Color,
     pub filter_weight_sum: Float,
+    /// Filtered sum of the coverage of the samples
+    pub alpha: Float,
+    /// Filtered sums of the enabled AOVs
+    pub aovs: Vec<Color>,
+    /// Number of camera samples taken in the pixel
+    pub samples: u32,
+    /// Sum of the sample luminances
+    pub lum_sum: Float,
+    /// Sum of the squared sample luminances
+    pub lum_sq_sum: Float,
+    /// Cost of rendering the pixel, see `HeatMap`
+    pub cost: Float,
 }
 
-impl Default for Pixel {
-    fn default() -> Self {
-        Pixel { color: Color::BLACK, splat: Color::BLACK, filter_weight_sum: 0.0 }
+impl Pixel {
+    fn new(num_aovs: usize) -> Self {
+        Pixel {
+            color: Color::BLACK,
+            splat: Color::BLACK,
+            filter_weight_sum: 0.0,
+            alpha: 0.0,
+            aovs: vec![Color::BLACK; num_aovs],
+            samples: 0,
+            lum_sum: 0.0,
+            lum_sq_sum: 0.0,
+            cost: 0.0,
+        }
+    }
+
+    /// Do the filter weights of the samples in the pixel sum up high
+    /// enough to normalize by?
+    fn has_filter_weight(&self) -> bool {
+        self.filter_weight_sum > MIN_FILTER_WEIGHT
+    }
+
+    /// Estimated variance of the mean luminance of the pixel.
+    /// Infinite if less than two samples taken.
+    fn variance(&self) -> Float {
+        if self.samples < 2 {
+            return crate::INF;
+        }
+        let n = self.samples as Float;
+        let mean = self.lum_sum / n;
+        let variance = ((self.lum_sq_sum - self.lum_sum * mean) / (n - 1.0)).max(0.0);
+
+        variance / n
+    }
+
+    /// Estimated relative standard error of the mean luminance of the pixel.
+    /// Infinite if less than two samples taken.
+    fn relative_error(&self) -> Float {
+        if self.samples < 2 {
+            return crate::INF;
+        }
+        let mean = self.lum_sum / self.samples as Float;
+
+        self.variance().sqrt() / mean.abs().max(MIN_ERROR_LUMINANCE)
     }
 }
 
@@ -52,10 +178,20 @@ impl AddAssign<&Pixel> for Pixel {
         self.color += rhs.color;
         self.splat += rhs.splat;
         self.filter_weight_sum += rhs.filter_weight_sum;
+        self.alpha += rhs.alpha;
+        self.aovs.iter_mut()
+            .zip(&rhs.aovs)
+            .for_each(|(a, b)| *a += *b);
+        self.samples += rhs.samples;
+        self.lum_sum += rhs.lum_sum;
+        self.lum_sq_sum += rhs.lum_sq_sum;
+        self.cost += rhs.cost;
     }
 }
 
-/// FilmTile given to a thread to avoid synchronization issues
+/// FilmTile given to a thread to avoid synchronization issues. Filters
+/// wider than a pixel spread samples to a border around the tile, which
+/// gets merged with the neighbouring tiles in `Film::add_tile`.
 pub struct FilmTile {
     /// Minimum coordinates of tile in raster space
     pub px_min: IVec2,
@@ -63,132 +199,766 @@ pub struct FilmTile {
     pub px_max: IVec2,
     /// Width of the tile
     pub width: i32,
+    /// Minimum coordinates of the tile and its border in raster space
+    border_min: IVec2,
+    /// Maximum coordinates of the tile and its border in raster space
+    border_max: IVec2,
     pixels: Vec<Pixel>,
     splats: Vec<FilmSample>,
     filter: Filter,
+    aovs: Vec<Aov>,
 }
 
 impl FilmTile {
-    /// Creates a new tile `px_min` x `px_max` with `filter`
-    pub fn new(px_min: IVec2, px_max: IVec2, filter: Filter) -> Self {
-        let pxs = px_max - px_min;
-        let width = pxs.x;
+    /// Creates a new tile `px_min` x `px_max` with `filter` that stores `aovs`
+    pub fn new(px_min: IVec2, px_max: IVec2, filter: Filter, aovs: &[Aov]) -> Self {
+        let border = IVec2::splat((filter.radius() - 0.5).ceil().max(0.0) as i32);
+        let border_min = px_min - border;
+        let border_max = px_max + border;
+        let pxs = border_max - border_min;
         Self {
             px_min,
             px_max,
+            width: px_max.x - px_min.x,
+            border_min,
+            border_max,
             filter,
-            width,
-            pixels: vec![Pixel::default(); (pxs.x * pxs.y) as usize],
+            pixels: vec![Pixel::new(aovs.len()); (pxs.x * pxs.y) as usize],
             splats: vec![],
+            aovs: aovs.to_vec(),
         }
     }
 
-    /// Adds a sample to the tile
+    /// Is `raster` inside the tile, border excluded
+    fn contains(&self, raster: IVec2) -> bool {
+        raster.cmpge(self.px_min).all() && raster.cmplt(self.px_max).all()
+    }
+
+    /// Index of the pixel at `raster` in the tile including the border
+    fn pixel_index(&self, raster: IVec2) -> usize {
+        let raster = raster - self.border_min;
+        let width = self.border_max.x - self.border_min.x;
+        (raster.x + width * raster.y) as usize
+    }
+
+    /// Adds a sample to the tile. The sample gets splatted to all pixels
+    /// within the radius of the filter.
     pub fn add_sample(&mut self, sample: FilmSample) {
         if sample.splat {
             return self.splats.push(sample);
         }
 
         let raster = sample.raster_xy.floor().as_ivec2();
-        if !(self.px_min.x..self.px_max.x).contains(&raster.x) {
+        if !self.contains(raster) {
             return;
         }
-        if !(self.px_min.y..self.px_max.y).contains(&raster.y) {
+
+        let (fp_min, fp_max) = footprint(sample.raster_xy, self.filter.radius());
+        for y in fp_min.y..fp_max.y {
+            for x in fp_min.x..fp_max.x {
+                let mid = Vec2::new(x as Float, y as Float) + 0.5;
+                let weight = self.filter.eval(mid - sample.raster_xy);
+                if weight != 0.0 {
+                    self.accumulate(IVec2::new(x, y), &sample, weight);
+                }
+            }
+        }
+
+        self.add_statistics(raster, &sample);
+    }
+
+    /// Adds a sample with `weight` only to the pixel containing it.
+    /// Used with filter importance sampling.
+    pub fn add_weighted_sample(&mut self, sample: FilmSample, weight: Float) {
+        if sample.splat {
+            return self.splats.push(sample);
+        }
+
+        let raster = sample.raster_xy.floor().as_ivec2();
+        if !self.contains(raster) {
             return;
         }
 
-        let mid = Vec2::new(raster.x as Float, raster.y as Float) + 0.5;
-        let offset = mid - sample.raster_xy;
-        let weight = self.filter.eval(2.0 * offset);
+        self.accumulate(raster, &sample, weight);
+        self.add_statistics(raster, &sample);
+    }
+
+    /// Adds `cost` to the heat map of the pixel at `raster`
+    pub fn add_cost(&mut self, raster: IVec2, cost: Float) {
+        if self.contains(raster) {
+            let idx = self.pixel_index(raster);
+            self.pixels[idx].cost += cost;
+        }
+    }
+
+    /// Adds `sample` with filter `weight` to the pixel at `raster`
+    fn accumulate(&mut self, raster: IVec2, sample: &FilmSample, weight: Float) {
+        let idx = self.pixel_index(raster);
+        let px = &mut self.pixels[idx];
+        px.filter_weight_sum += weight;
+        px.color += sample.color * weight;
+        px.alpha += sample.alpha * weight;
+        for (i, aov) in self.aovs.iter().enumerate() {
+            px.aovs[i] += sample.aov(*aov) * weight;
+        }
+    }
 
-        let raster = raster - self.px_min;
-        let idx = (raster.x + self.width * raster.y) as usize;
-        self.pixels[idx].filter_weight_sum += weight;
-        self.pixels[idx].color += sample.color * weight;
+    /// Records `sample` to the statistics of the pixel at `raster`
+    fn add_statistics(&mut self, raster: IVec2, sample: &FilmSample) {
+        let idx = self.pixel_index(raster);
+        let px = &mut self.pixels[idx];
+        let lum = sample.color.luminance();
+        px.samples += 1;
+        px.lum_sum += lum;
+        px.lum_sq_sum += lum * lum;
     }
 }
 
+/// Pixels from minimum to maximum, exclusive, with their center within
+/// `radius` of `raster_xy`. Samples exactly at a pixel border go to the
+/// pixel after the border.
+fn footprint(raster_xy: Vec2, radius: Float) -> (IVec2, IVec2) {
+    let fp_min = (raster_xy - 0.5 - radius).floor().as_ivec2() + 1;
+    let fp_max = (raster_xy - 0.5 + radius).floor().as_ivec2() + 1;
+    (fp_min, fp_max)
+}
+
 /// Film that contains the image being rendered
 pub struct Film {
     pixels: Vec<Pixel>,
     /// Image resolution
     pub resolution: IVec2,
-    splat_scale: Float,
+    /// Samples per pixel taken so far
+    samples: i32,
+    /// Minimum corner of the rendered region in raster space
+    crop_min: IVec2,
+    /// Maximum corner, exclusive, of the rendered region in raster space
+    crop_max: IVec2,
+    aovs: Vec<Aov>,
+    /// Color space of the accumulated values
+    working_space: ColorSpace,
+    /// Color space of .png output
+    output_space: ColorSpace,
+    /// Transfer function of .png output
+    output_transfer: Transfer,
+    /// Bit depth of .png output
+    png_depth: PngDepth,
+    /// Write the coverage of camera rays as alpha
+    transparent_background: bool,
+    /// Statistics of the render
+    stats: RenderStats,
+    /// Cost recorded to the pixels
+    heat_map: Option<HeatMap>,
 }
 
 impl Film {
-    /// Creates a new empty film
-    pub fn new(width: i32, height: i32, samples: i32) -> Self {
+    /// Creates a new empty film that stores also `aovs`
+    pub fn new(width: i32, height: i32, aovs: &[Aov]) -> Self {
         let n = width * height;
         let resolution = IVec2::new(width, height);
         Self {
-            pixels: vec![Pixel::default(); n as usize],
-            splat_scale: 1.0 / samples as Float,
+            pixels: vec![Pixel::new(aovs.len()); n as usize],
             resolution,
+            samples: 0,
+            crop_min: IVec2::ZERO,
+            crop_max: resolution,
+            aovs: aovs.to_vec(),
+            working_space: ColorSpace::Srgb,
+            output_space: ColorSpace::Srgb,
+            output_transfer: Transfer::Srgb,
+            png_depth: PngDepth::Eight,
+            transparent_background: false,
+            stats: RenderStats::default(),
+            heat_map: None,
+        }
+    }
+
+    /// Samples per pixel taken so far. With adaptive sampling the number
+    /// of samples taken in the least converged pixels.
+    pub fn samples(&self) -> i32 {
+        self.samples
+    }
+
+    /// Records that `samples` more samples per pixel have been taken
+    pub fn add_samples(&mut self, samples: i32) {
+        self.samples += samples;
+    }
+
+    /// Restricts the rendered region to raster coordinates from `px_min` to
+    /// `px_max`, exclusive. Pixels outside the region resolve to black.
+    pub fn set_crop_window(&mut self, px_min: IVec2, px_max: IVec2) {
+        error::unwrap(self.try_set_crop_window(px_min, px_max))
+    }
+
+    /// Fallible `set_crop_window`. Fails if the region is empty or
+    /// not within the film.
+    pub fn try_set_crop_window(&mut self, px_min: IVec2, px_max: IVec2) -> Result<(), Error> {
+        Self::check_crop_window(px_min, px_max, self.resolution)?;
+        self.crop_min = px_min;
+        self.crop_max = px_max;
+        Ok(())
+    }
+
+    /// Checks that the crop window from `min` to `max` is non-empty and
+    /// within an image of `resolution`
+    pub(crate) fn check_crop_window(
+        min: IVec2,
+        max: IVec2,
+        resolution: IVec2,
+    ) -> Result<(), Error> {
+        let valid = min.cmpge(IVec2::ZERO).all()
+            && min.cmplt(max).all()
+            && max.cmple(resolution).all();
+        error::check(valid, || format!(
+            "crop window ({}, {}) - ({}, {}) must be non-empty and within the {} x {} image",
+            min.x, min.y, max.x, max.y, resolution.x, resolution.y,
+        ))
+    }
+
+    /// Minimum and maximum, exclusive, raster coordinates of the rendered region
+    pub fn crop_window(&self) -> (IVec2, IVec2) {
+        (self.crop_min, self.crop_max)
+    }
+
+    /// Film of just the rendered region
+    pub fn cropped(&self) -> Film {
+        let size = self.crop_max - self.crop_min;
+        let mut film = Film::new(size.x, size.y, &self.aovs);
+        film.samples = self.samples;
+        film.working_space = self.working_space;
+        film.output_space = self.output_space;
+        film.output_transfer = self.output_transfer;
+        film.png_depth = self.png_depth;
+        film.transparent_background = self.transparent_background;
+        film.stats = self.stats;
+        film.heat_map = self.heat_map;
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let raster = self.crop_min + IVec2::new(x, y);
+                let idx = (raster.x + raster.y * self.resolution.x) as usize;
+                film.pixels[(x + y * size.x) as usize] = self.pixels[idx].clone();
+            }
+        }
+        // the light paths of the samples splatted over the whole film
+        let splat_scale = film.splat_scale();
+        if splat_scale > 0.0 {
+            let rescale = self.splat_scale() / splat_scale;
+            film.pixels.iter_mut().for_each(|px| px.splat = px.splat * rescale);
+        }
+        film
+    }
+
+    /// Saves the accumulated state of the film to a checkpoint at `fname`.
+    /// Rendering can be continued from the checkpoint with `Renderer::resume`.
+    pub fn save_checkpoint(&self, fname: &str) -> io::Result<()> {
+        checkpoint::write(fname, self)
+    }
+
+    /// Loads a film from the checkpoint at `fname`
+    pub fn load_checkpoint(fname: &str) -> io::Result<Self> {
+        checkpoint::read(fname)
+    }
+
+    /// Merges the samples of `other` to `self`, e.g. films rendered on
+    /// different machines with different seeds. Filtered sums, splats and
+    /// sample counts get added together, so each film is weighted by
+    /// the number of samples it has. The rendered region grows to
+    /// contain the regions of both films.
+    pub fn merge(&mut self, other: &Film) -> io::Result<()> {
+        if self.resolution != other.resolution
+            || self.aovs != other.aovs
+            || self.working_space != other.working_space {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Can only merge films of same resolution, AOVs and working space",
+            ));
+        }
+
+        self.pixels.iter_mut()
+            .zip(&other.pixels)
+            .for_each(|(a, b)| *a += b);
+        self.samples += other.samples;
+        self.stats += &other.stats;
+        self.crop_min = self.crop_min.min(other.crop_min);
+        self.crop_max = self.crop_max.max(other.crop_max);
+        Ok(())
+    }
+
+    /// Loads the checkpoints at `fnames` and merges them to one film
+    pub fn merge_checkpoints(fnames: &[&str]) -> io::Result<Self> {
+        let (first, rest) = fnames.split_first().ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No checkpoints to merge",
+        ))?;
+
+        let mut film = Self::load_checkpoint(first)?;
+        for fname in rest {
+            film.merge(&Self::load_checkpoint(fname)?)?;
+        }
+        Ok(film)
+    }
+
+    /// Checks that the per pixel sample counts agree with the samples
+    /// per pixel taken. Jittered sampling may take less samples than asked.
+    fn samples_consistent(&self) -> bool {
+        self.pixels.iter().all(|px| px.samples as i64 <= self.samples as i64)
+    }
+
+    /// Scale of splat samples. Each camera sample traces a light path that
+    /// may splat anywhere on the film, hence we divide by the number of
+    /// light paths per pixel of the film. With a crop window or adaptive
+    /// sampling only some pixels take samples, but the splats still spread
+    /// over the whole film.
+    fn splat_scale(&self) -> Float {
+        let samples: u64 = self.pixels.iter().map(|px| px.samples as u64).sum();
+
+        if samples == 0 { 0.0 } else { self.pixels.len() as Float / samples as Float }
+    }
+
+    /// Number of camera samples taken in each pixel
+    pub fn sample_counts(&self) -> Vec<u32> {
+        self.pixels.iter().map(|px| px.samples).collect()
+    }
+
+    /// Estimated relative error of the luminance of each pixel. Infinite for
+    /// pixels with less than two samples.
+    pub fn pixel_errors(&self) -> Vec<Float> {
+        self.pixels.iter().map(|px| px.relative_error()).collect()
+    }
+
+    /// Mean relative error of the pixels with at least two samples.
+    /// `None` if there are no such pixels.
+    pub fn noise(&self) -> Option<Float> {
+        let (pixels, sum) = self.pixels.iter()
+            .filter(|px| px.samples >= 2)
+            .fold((0, 0.0), |(p, s), px| (p + 1, s + px.relative_error()));
+
+        if pixels == 0 { None } else { Some(sum / pixels as Float) }
+    }
+
+    /// AOVs stored in the film
+    pub fn aovs(&self) -> &[Aov] {
+        &self.aovs
+    }
+
+    /// Sets the color space of the values accumulated to the film. Set by
+    /// the renderer to its working space.
+    pub fn set_working_space(&mut self, space: ColorSpace) {
+        self.working_space = space;
+    }
+
+    /// Color space of the values accumulated to the film
+    pub fn working_space(&self) -> ColorSpace {
+        self.working_space
+    }
+
+    /// Sets the color space and transfer function of images saved as .png.
+    /// Defaults to sRGB. The matching color chunks, or an ICC profile for
+    /// other spaces, get written to the file. High dynamic range formats
+    /// store linear values in the working space.
+    pub fn set_output_encoding(&mut self, space: ColorSpace, transfer: Transfer) {
+        self.output_space = space;
+        self.output_transfer = transfer;
+    }
+
+    /// Color space and transfer function of images saved as .png
+    pub fn output_encoding(&self) -> (ColorSpace, Transfer) {
+        (self.output_space, self.output_transfer)
+    }
+
+    /// Sets the bit depth of the channels in .png output. Defaults to 8 bits.
+    pub fn set_png_depth(&mut self, depth: PngDepth) {
+        self.png_depth = depth;
+    }
+
+    /// Bit depth of the channels in .png output
+    pub fn png_depth(&self) -> PngDepth {
+        self.png_depth
+    }
+
+    /// Makes the background transparent in saved images. Images get an
+    /// alpha channel from the fraction of camera rays that hit something.
+    /// Colors of .png files get divided by alpha as .png stores straight
+    /// alpha, while .exr files store alpha premultiplied.
+    pub fn set_transparent_background(&mut self, transparent: bool) {
+        self.transparent_background = transparent;
+    }
+
+    /// Is the background transparent in saved images?
+    pub fn transparent_background(&self) -> bool {
+        self.transparent_background
+    }
+
+    /// Resolves the coverage of camera rays in `\[0,1\]` row by row
+    /// starting from the top left corner
+    pub fn resolve_alpha(&self) -> Vec<Float> {
+        self.pixels.iter()
+            .map(|px| if px.samples == 0 || !px.has_filter_weight() {
+                0.0
+            } else {
+                // negative lobes of filters can go outside [0,1]
+                (px.alpha / px.filter_weight_sum).clamp(0.0, 1.0)
+            })
+            .collect()
+    }
+
+    /// Alpha of saved images, `None` if the background is not transparent
+    fn alpha(&self) -> Option<Vec<Float>> {
+        if self.transparent_background {
+            Some(self.resolve_alpha())
+        } else {
+            None
         }
     }
 
-    /// Add samples from `tile` to self
+    /// Resolves the film and applies `post_process` to it. Tone mapped
+    /// RGB values row by row starting from the top left corner.
+    pub fn resolve_processed(&self, post_process: &PostProcess) -> Vec<Color> {
+        post_process.apply(self.resolution, &self.resolve(), self.working_space)
+    }
+
+    /// Resolves the film and denoises it with `denoiser`. Guided by the
+    /// albedo, normal and depth AOVs, if stored in the film. Linear RGB
+    /// values row by row starting from the top left corner.
+    pub fn denoise(&self, denoiser: Denoiser) -> Vec<Color> {
+        denoise::denoise(self, denoiser)
+    }
+
+    /// Add samples from `tile` to self. The border of the tile gets added
+    /// to the neighbouring pixels and splats spread with the filter of
+    /// the tile.
     pub fn add_tile(&mut self, tile: FilmTile) {
-        let px_offset = tile.px_max - tile.px_min;
-        for y in 0..px_offset.y {
-            for x in 0..px_offset.x {
-                let px = IVec2::new(x, y);
-                let idx_tile = (px.x + px.y * tile.width) as usize;
-                let raster = px + tile.px_min;
+        let px_min = tile.border_min.max(IVec2::ZERO);
+        let px_max = tile.border_max.min(self.resolution);
+        for y in px_min.y..px_max.y {
+            for x in px_min.x..px_max.x {
+                let raster = IVec2::new(x, y);
                 let idx_film = (raster.x + raster.y * self.resolution.x) as usize;
-                self.pixels[idx_film] += &tile.pixels[idx_tile];
+                self.pixels[idx_film] += &tile.pixels[tile.pixel_index(raster)];
             }
         }
 
+        let radius = tile.filter.radius();
+        let integral = tile.filter.integral();
         for splat in tile.splats {
-            let raster = splat.raster_xy.floor().as_ivec2();
-            if !(0..self.resolution.x).contains(&raster.x) {
-                continue;
-            }
-            if !(0..self.resolution.y).contains(&raster.y) {
-                continue;
+            let (fp_min, fp_max) = footprint(splat.raster_xy, radius);
+            let fp_min = fp_min.max(IVec2::ZERO);
+            let fp_max = fp_max.min(self.resolution);
+            for y in fp_min.y..fp_max.y {
+                for x in fp_min.x..fp_max.x {
+                    let mid = Vec2::new(x as Float, y as Float) + 0.5;
+                    let weight = tile.filter.eval(mid - splat.raster_xy) / integral;
+                    let idx = (x + y * self.resolution.x) as usize;
+                    self.pixels[idx].splat += splat.color * weight;
+                }
             }
+        }
+    }
 
-            let idx = (raster.x + raster.y * self.resolution.x) as usize;
-            self.pixels[idx].splat += splat.color;
+    /// Resolved linear RGB value of the pixel at `idx`
+    fn pixel_color(&self, idx: usize, splat_scale: Float) -> Color {
+        let px = &self.pixels[idx];
+        // splats of light paths might land outside the rendered region
+        if px.samples == 0 {
+            return Color::BLACK;
         }
+
+        let color = if !px.has_filter_weight() {
+            Color::BLACK
+        } else {
+            // negative lobes of filters can make the color negative
+            (px.color / px.filter_weight_sum).clamp(0.0, crate::INF)
+        };
+
+        color + px.splat * splat_scale
     }
 
-    fn rgb_image(&self) -> Vec<u8> {
-        let mut img = Vec::new();
+    /// Resolves the film to linear RGB values row by row starting from
+    /// the top left corner
+    pub fn resolve(&self) -> Vec<Color> {
+        let splat_scale = self.splat_scale();
+        (0..self.pixels.len()).map(|idx| self.pixel_color(idx, splat_scale)).collect()
+    }
 
-        for y in 0..self.resolution.y {
-            for x in 0..self.resolution.x {
-                let idx = (x + y * self.resolution.x) as usize;
-                let col = self.pixels[idx].splat * self.splat_scale +
-                    self.pixels[idx].color / self.pixels[idx].filter_weight_sum;
+    /// Resolves `aov` row by row starting from the top left corner.
+    /// `None` if the AOV is not stored in the film.
+    pub fn resolve_aov(&self, aov: Aov) -> Option<Vec<Color>> {
+        let i = self.aovs.iter().position(|a| *a == aov)?;
+        let values = self.pixels.iter()
+            .map(|px| if !px.has_filter_weight() {
+                Color::BLACK
+            } else {
+                px.aovs[i] / px.filter_weight_sum
+            })
+            .collect();
 
-                let (r, g, b) = col.gamma_enc();
-                img.push(r);
-                img.push(g);
-                img.push(b);
+        Some(values)
+    }
+
+    /// Encodes `pixels` in the working space to values of the output
+    /// encoding at the bit depth of .png output. Linear `alpha`
+    /// gets interleaved if given.
+    fn png_image(&self, pixels: &[Color], alpha: Option<&[Float]>) -> Vec<u8> {
+        let conversion = ColorSpace::conversion(self.working_space, self.output_space);
+        let mut img = Vec::new();
+        let mut push = |v: Float| match self.png_depth {
+            PngDepth::Eight => img.push((v * 255.0).round() as u8),
+            // big endian as per the specification
+            PngDepth::Sixteen => img.extend_from_slice(
+                &((v * 65535.0).round() as u16).to_be_bytes()
+            ),
+        };
+
+        for (i, col) in pixels.iter().enumerate() {
+            let rgb = (conversion * col.rgb).clamp(Vec3::ZERO, Vec3::ONE);
+            for v in rgb.to_array() {
+                push(self.output_transfer.encode(v));
+            }
+            if let Some(alpha) = alpha {
+                push(alpha[i]);
             }
         }
 
         img
     }
 
-    /// Saves the film to a .png file
+    /// Saves the linear radiance of the film to a .pfm file
+    pub fn save_pfm(&self, fname: &str) -> io::Result<()> {
+        log_info!("Saving to \"{}\"", fname);
+        pfm::write(fname, self.resolution, &self.resolve())
+    }
+
+    /// Saves the linear radiance of the film to an uncompressed scanline
+    /// OpenEXR file with channel values stored in `precision`. Stored AOVs
+    /// are written as layers of the same file, e.g. `albedo.R`.
+    pub fn save_exr(&self, fname: &str, precision: ExrPrecision) -> io::Result<()> {
+        log_info!("Saving to \"{}\"", fname);
+        let mut channels = Self::exr_channels("", &["R", "G", "B"], &self.resolve());
+        if let Some(alpha) = self.alpha() {
+            channels.push(ExrChannel::new("A", alpha));
+        }
+        for aov in &self.aovs {
+            if let Some(values) = self.resolve_aov(*aov) {
+                let layer = format!("{}.", aov);
+                channels.extend(Self::exr_channels(&layer, aov.channels(), &values));
+            }
+        }
+        exr::write(fname, self.resolution, channels, precision, self.working_space)
+    }
+
+    /// Splits `values` to channels named `names` prefixed with `layer`
+    fn exr_channels(layer: &str, names: &[&str], values: &[Color]) -> Vec<ExrChannel> {
+        names.iter().enumerate()
+            .map(|(i, name)| ExrChannel::new(
+                &format!("{}{}", layer, name),
+                values.iter().map(|c| c.rgb[i]).collect(),
+            ))
+            .collect()
+    }
+
+    /// Saves `aov` to its own image. Format chosen based on the extension
+    /// of `fname`, one of .png, .pfm or .exr. Values in .png files are mapped
+    /// for viewing, e.g. normals from `\[-1,1\]` to `\[0,1\]`.
+    pub fn save_aov(&self, aov: Aov, fname: &str) -> io::Result<()> {
+        let values = self.resolve_aov(aov).ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("AOV {} not stored in the film", aov),
+        ))?;
+
+        log_info!("Saving {} to \"{}\"", aov, fname);
+        self.write_values(fname, aov.channels(), &values, &aov.display(&values), None)
+    }
+
+    /// Saves the image denoised with `denoiser`. Format chosen based on
+    /// the extension of `fname`, one of .png, .pfm or .exr. Images saved
+    /// as .png get `post_process` applied.
+    pub fn save_denoised(
+        &self,
+        fname: &str,
+        denoiser: Denoiser,
+        post_process: &PostProcess,
+    ) -> io::Result<()> {
+        log_info!("Denoising with {}", denoiser);
+        let values = self.denoise(denoiser);
+        let alpha = self.alpha();
+        let display = self.display(&values, alpha.as_deref(), post_process);
+        log_info!("Saving denoised image to \"{}\"", fname);
+        self.write_values(fname, &["R", "G", "B"], &values, &display, alpha.as_deref())
+    }
+
+    /// Saves the number of samples taken in each pixel to an image. Useful
+    /// to inspect adaptive sampling. Format chosen like in `save_aov`,
+    /// in .png files the counts get normalized by the maximum count.
+    pub fn save_sample_counts(&self, fname: &str) -> io::Result<()> {
+        let counts = self.sample_counts();
+        let max = counts.iter().copied().max().unwrap_or(0).max(1) as Float;
+        let values: Vec<Color> = counts.iter()
+            .map(|c| Color::splat(*c as Float))
+            .collect();
+        let display: Vec<Color> = values.iter().map(|v| *v / max).collect();
+
+        log_info!("Saving sample counts to \"{}\"", fname);
+        self.write_values(fname, &["Y"], &values, &display, None)
+    }
+
+    /// Statistics of the render. Only cover the rendering done since
+    /// the film was created or loaded from a checkpoint.
+    pub fn stats(&self) -> &RenderStats {
+        &self.stats
+    }
+
+    /// Adds `stats` to the statistics of the render
+    pub(crate) fn add_stats(&mut self, stats: &RenderStats) {
+        self.stats += stats;
+    }
+
+    /// Records the cost of rendering each pixel as `heat_map`
+    pub fn set_heat_map(&mut self, heat_map: HeatMap) {
+        self.heat_map = Some(heat_map);
+    }
+
+    /// Cost recorded to the pixels, if any
+    pub fn heat_map(&self) -> Option<HeatMap> {
+        self.heat_map
+    }
+
+    /// Saves the cost of rendering each pixel to an image. Format chosen
+    /// like in `save_aov`, in .png files the costs are normalized by
+    /// the maximum cost and colored from black through red to yellow.
+    pub fn save_heat_map(&self, fname: &str) -> io::Result<()> {
+        let heat_map = self.heat_map.ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No heat map recorded to the film",
+        ))?;
+
+        let max = self.pixels.iter().map(|px| px.cost).fold(0.0, Float::max);
+        let max = if max > 0.0 { max } else { 1.0 };
+        let values: Vec<Color> = self.pixels.iter()
+            .map(|px| Color::splat(px.cost))
+            .collect();
+        let display: Vec<Color> = self.pixels.iter()
+            .map(|px| {
+                let t = 3.0 * px.cost / max;
+                Color::from(Vec3::new(t, t - 1.0, t - 2.0).clamp(Vec3::ZERO, Vec3::ONE))
+            })
+            .collect();
+
+        log_info!("Saving {:?} heat map to \"{}\"", heat_map, fname);
+        self.write_values(fname, &["Y"], &values, &display, None)
+    }
+
+    /// Writes `values` to `fname` based on its extension. `names` are the EXR
+    /// channels of `values` and `display` the values written to .png files.
+    /// `alpha` gets written to .png and .exr files if given.
+    fn write_values(
+        &self,
+        fname: &str,
+        names: &[&str],
+        values: &[Color],
+        display: &[Color],
+        alpha: Option<&[Float]>,
+    ) -> io::Result<()> {
+        if fname.ends_with(".pfm") {
+            pfm::write(fname, self.resolution, values)
+        } else if fname.ends_with(".exr") {
+            let mut channels = Self::exr_channels("", names, values);
+            if let Some(alpha) = alpha {
+                channels.push(ExrChannel::new("A", alpha.to_vec()));
+            }
+            let space = self.working_space;
+            exr::write(fname, self.resolution, channels, ExrPrecision::Full, space)
+        } else if fname.ends_with(".png") {
+            self.write_png(fname, display, alpha)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Can only save as .png, .pfm or .exr",
+            ))
+        }
+    }
+
+    /// Saves the film to a .png file without post processing. Use
+    /// `Renderer::save` to apply the tone mapping and post effects of
+    /// the renderer.
     pub fn save(&self, fname: &str) -> Result<(), EncodingError> {
-        println!("Saving to \"{}\"", fname);
+        self.save_processed(fname, &PostProcess::default())
+    }
+
+    /// Saves the film to a .png file with `post_process` applied
+    pub fn save_processed(
+        &self,
+        fname: &str,
+        post_process: &PostProcess,
+    ) -> Result<(), EncodingError> {
+        log_info!("Saving to \"{}\"", fname);
+        self.save_snapshot(fname, post_process)
+    }
+
+    /// Silent `save_processed` for snapshots of progressive renders
+    pub(crate) fn save_snapshot(
+        &self,
+        fname: &str,
+        post_process: &PostProcess,
+    ) -> Result<(), EncodingError> {
+        let alpha = self.alpha();
+        let display = self.display(&self.resolve(), alpha.as_deref(), post_process);
+        self.write_png(fname, &display, alpha.as_deref())
+    }
+
+    /// `values` with `post_process` applied for .png files. Divided by
+    /// `alpha` if given, as edges of objects are blended with the black
+    /// background.
+    fn display(
+        &self,
+        values: &[Color],
+        alpha: Option<&[Float]>,
+        post_process: &PostProcess,
+    ) -> Vec<Color> {
+        match alpha {
+            None => post_process.apply(self.resolution, values, self.working_space),
+            Some(alpha) => {
+                let straight: Vec<Color> = values.iter()
+                    .zip(alpha)
+                    .map(|(c, a)| if *a > 0.0 { *c / *a } else { *c })
+                    .collect();
+                post_process.apply(self.resolution, &straight, self.working_space)
+            }
+        }
+    }
+
+    fn write_png(
+        &self,
+        fname: &str,
+        pixels: &[Color],
+        alpha: Option<&[Float]>,
+    ) -> Result<(), EncodingError> {
         let path = Path::new(fname);
 
         let mut binding = BufWriter::new(File::create(path)?);
-        let mut encoder = Encoder::new(
-            &mut binding,
-            self.resolution.x as u32,
-            self.resolution.y as u32,
-        );
-        encoder.set_color(ColorType::Rgb);
-        encoder.set_depth(BitDepth::Eight);
-
-        encoder.write_header()?.write_image_data(&self.rgb_image())?;
+        let mut info = Info::with_size(self.resolution.x as u32, self.resolution.y as u32);
+        let srgb = (self.output_space, self.output_transfer) == (ColorSpace::Srgb, Transfer::Srgb);
+        if !srgb {
+            let profile = icc::profile(self.output_space, self.output_transfer);
+            info.icc_profile = Some(Cow::Owned(profile));
+        }
+        let mut encoder = Encoder::with_info(&mut binding, info)?;
+        encoder.set_color(if alpha.is_some() { ColorType::Rgba } else { ColorType::Rgb });
+        encoder.set_depth(match self.png_depth {
+            PngDepth::Eight => BitDepth::Eight,
+            PngDepth::Sixteen => BitDepth::Sixteen,
+        });
+        if srgb {
+            encoder.set_source_srgb(SrgbRenderingIntent::Perceptual);
+        } else {
+            let [r, g, b, w] = self.output_space.chromaticities()
+                .map(|xy| (xy.x as f32, xy.y as f32));
+            encoder.set_source_chromaticities(SourceChromaticities::new(w, r, g, b));
+            let gamma = 1.0 / self.output_transfer.gamma();
+            encoder.set_source_gamma(ScaledFloat::new(gamma as f32));
+        }
+
+        encoder.write_header()?.write_image_data(&self.png_image(pixels, alpha))?;
         Ok(())
     }
 }
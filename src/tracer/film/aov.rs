@@ -0,0 +1,85 @@
+use crate::{rand_utils, Float, Vec3};
+use crate::tracer::Color;
+use std::fmt;
+
+/// Arbitrary output variables that can be rendered alongside the image
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Aov {
+    /// Albedo of the first hit
+    Albedo,
+    /// Shading normal of the first hit in world space
+    Normal,
+    /// Camera space depth of the first hit
+    Depth,
+    /// World space position of the first hit
+    Position,
+    /// Index of the first hit object in the scene, starting from one.
+    /// Zero if nothing was hit.
+    ObjectId,
+    /// Light emitted towards the camera or scattered once from the first hit
+    Direct,
+    /// Light that has scattered more than once before reaching the camera
+    Indirect,
+}
+
+impl fmt::Display for Aov {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Albedo => write!(f, "albedo"),
+            Self::Normal => write!(f, "normal"),
+            Self::Depth => write!(f, "depth"),
+            Self::Position => write!(f, "position"),
+            Self::ObjectId => write!(f, "objectId"),
+            Self::Direct => write!(f, "direct"),
+            Self::Indirect => write!(f, "indirect"),
+        }
+    }
+}
+
+impl Aov {
+    /// All of the AOVs
+    pub const ALL: [Aov; 7] = [
+        Self::Albedo, Self::Normal, Self::Depth, Self::Position,
+        Self::ObjectId, Self::Direct, Self::Indirect,
+    ];
+
+    /// Names of the channels of the AOV in multichannel files
+    pub fn channels(&self) -> &'static [&'static str] {
+        match self {
+            Self::Albedo | Self::Direct | Self::Indirect => &["R", "G", "B"],
+            Self::Normal | Self::Position => &["X", "Y", "Z"],
+            Self::Depth => &["Z"],
+            Self::ObjectId => &["id"],
+        }
+    }
+
+    /// Maps the resolved `values` of the AOV to colors that can be viewed
+    /// in 8-bit images
+    pub fn display(&self, values: &[Color]) -> Vec<Color> {
+        match self {
+            Self::Normal => values.iter()
+                .map(|v| Color::from(0.5 * v.rgb + 0.5))
+                .collect(),
+            Self::Depth => {
+                let max = values.iter().fold(0.0, |m: Float, v| m.max(v.rgb.x));
+                let max = if max == 0.0 { 1.0 } else { max };
+                values.iter().map(|v| *v / max).collect()
+            }
+            Self::ObjectId => values.iter()
+                .map(|v| Self::id_color(v.rgb.x.round() as u64))
+                .collect(),
+            _ => values.to_vec(),
+        }
+    }
+
+    /// Pseudorandom color for object `id`. Black for zero.
+    fn id_color(id: u64) -> Color {
+        if id == 0 {
+            return Color::BLACK;
+        }
+        let h = rand_utils::hash(id);
+
+        let channel = |shift: u64| ((h >> shift) & 0xff) as Float / 255.0;
+        Color::from(Vec3::new(channel(0), channel(8), channel(16)))
+    }
+}
@@ -0,0 +1,160 @@
+use super::{Aov, Film, Pixel};
+use crate::{Float, Vec3};
+use crate::tracer::{Color, ColorSpace};
+use glam::IVec2;
+use std::{fs::{self, File}, io::{self, BufReader, BufWriter, Read, Write}};
+
+const MAGIC: &[u8; 8] = b"LUMOCKPT";
+const VERSION: u32 = 3;
+
+/// Writes the accumulated state of `film` to `fname`. The file is first
+/// written next to `fname` and then renamed over it, so an interrupted write
+/// never destroys the previous checkpoint.
+pub fn write(fname: &str, film: &Film) -> io::Result<()> {
+    let tmp = format!("{}.tmp", fname);
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&film.resolution.x.to_le_bytes())?;
+    writer.write_all(&film.resolution.y.to_le_bytes())?;
+    writer.write_all(&film.samples.to_le_bytes())?;
+    for v in [film.crop_min, film.crop_max] {
+        writer.write_all(&v.x.to_le_bytes())?;
+        writer.write_all(&v.y.to_le_bytes())?;
+    }
+    let space = ColorSpace::ALL.iter().position(|s| *s == film.working_space).unwrap() as u32;
+    writer.write_all(&space.to_le_bytes())?;
+    writer.write_all(&(film.aovs.len() as u32).to_le_bytes())?;
+    for aov in &film.aovs {
+        let id = Aov::ALL.iter().position(|a| a == aov).unwrap() as u32;
+        writer.write_all(&id.to_le_bytes())?;
+    }
+
+    for px in &film.pixels {
+        write_color(&mut writer, px.color)?;
+        write_color(&mut writer, px.splat)?;
+        writer.write_all(&px.filter_weight_sum.to_le_bytes())?;
+        writer.write_all(&px.alpha.to_le_bytes())?;
+        writer.write_all(&px.samples.to_le_bytes())?;
+        writer.write_all(&px.lum_sum.to_le_bytes())?;
+        writer.write_all(&px.lum_sq_sum.to_le_bytes())?;
+        for aov in &px.aovs {
+            write_color(&mut writer, *aov)?;
+        }
+    }
+
+    writer.flush()?;
+    drop(writer);
+    fs::rename(tmp, fname)
+}
+
+/// Reads a film written by `write` from `fname`
+pub fn read(fname: &str) -> io::Result<Film> {
+    let mut reader = BufReader::new(File::open(fname)?);
+
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("Not a lumo checkpoint"));
+    }
+    if read_u32(&mut reader)? != VERSION {
+        return Err(invalid_data("Unsupported checkpoint version"));
+    }
+
+    let width = read_i32(&mut reader)?;
+    let height = read_i32(&mut reader)?;
+    let samples = read_i32(&mut reader)?;
+    if width <= 0 || height <= 0 || samples < 0 || width.checked_mul(height).is_none() {
+        return Err(invalid_data("Invalid checkpoint header"));
+    }
+    let crop_min = IVec2::new(read_i32(&mut reader)?, read_i32(&mut reader)?);
+    let crop_max = IVec2::new(read_i32(&mut reader)?, read_i32(&mut reader)?);
+    let resolution = IVec2::new(width, height);
+    if crop_min.cmplt(IVec2::ZERO).any()
+        || crop_min.cmpge(crop_max).any()
+        || crop_max.cmpgt(resolution).any() {
+        return Err(invalid_data("Invalid crop window in checkpoint"));
+    }
+
+    let working_space = ColorSpace::ALL.get(read_u32(&mut reader)? as usize)
+        .copied()
+        .ok_or_else(|| invalid_data("Unknown working space in checkpoint"))?;
+
+    let num_aovs = read_u32(&mut reader)? as usize;
+    if num_aovs > Aov::ALL.len() {
+        return Err(invalid_data("Invalid number of AOVs in checkpoint"));
+    }
+    let aovs = (0..num_aovs)
+        .map(|_| {
+            let id = read_u32(&mut reader)? as usize;
+            Aov::ALL.get(id).copied()
+                .ok_or_else(|| invalid_data("Unknown AOV in checkpoint"))
+        })
+        .collect::<io::Result<Vec<Aov>>>()?;
+
+    let mut film = Film::new(width, height, &aovs);
+    film.samples = samples;
+    film.working_space = working_space;
+    film.set_crop_window(crop_min, crop_max);
+    for px in film.pixels.iter_mut() {
+        *px = Pixel {
+            color: read_color(&mut reader)?,
+            splat: read_color(&mut reader)?,
+            filter_weight_sum: read_float(&mut reader)?,
+            alpha: read_float(&mut reader)?,
+            samples: read_u32(&mut reader)?,
+            lum_sum: read_float(&mut reader)?,
+            lum_sq_sum: read_float(&mut reader)?,
+            // costs of the heat map are only kept for the current render
+            cost: 0.0,
+            aovs: (0..num_aovs)
+                .map(|_| read_color(&mut reader))
+                .collect::<io::Result<Vec<Color>>>()?,
+        };
+    }
+
+    if reader.read(&mut [0u8; 1])? != 0 {
+        return Err(invalid_data("Trailing data in checkpoint"));
+    }
+    if !film.samples_consistent() {
+        return Err(invalid_data("Pixel sample counts do not match samples taken"));
+    }
+
+    Ok(film)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_color<W: Write>(writer: &mut W, color: Color) -> io::Result<()> {
+    writer.write_all(&color.rgb.x.to_le_bytes())?;
+    writer.write_all(&color.rgb.y.to_le_bytes())?;
+    writer.write_all(&color.rgb.z.to_le_bytes())
+}
+
+fn read_color<R: Read>(reader: &mut R) -> io::Result<Color> {
+    let r = read_float(reader)?;
+    let g = read_float(reader)?;
+    let b = read_float(reader)?;
+    Ok(Color::from(Vec3::new(r, g, b)))
+}
+
+fn read_float<R: Read>(reader: &mut R) -> io::Result<Float> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(Float::from_le_bytes(bytes))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> io::Result<i32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
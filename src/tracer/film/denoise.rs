@@ -0,0 +1,216 @@
+use super::{Aov, Film};
+use crate::tracer::Color;
+use crate::Float;
+use rayon::prelude::*;
+use std::fmt;
+
+/// Radius of the patches compared in the non-local means modes
+const PATCH_RADIUS: i32 = 1;
+/// Width of the range kernel of relative color differences
+const SIGMA_COLOR: Float = 0.5;
+/// Filtering strength of non-local means
+const NLM_STRENGTH: Float = 0.4;
+/// Sensitivity to variance in the variance guided mode
+const VARIANCE_SENSITIVITY: Float = 0.45;
+/// Width of the range kernel of albedo differences
+const SIGMA_ALBEDO: Float = 0.1;
+/// Width of the range kernel of normal differences
+const SIGMA_NORMAL: Float = 0.3;
+/// Width of the range kernel of relative depth differences
+const SIGMA_DEPTH: Float = 0.05;
+/// Avoids divisions by zero for black pixels
+const EPSILON: Float = 1e-4;
+
+/// Denoising filters applied to the resolved image. Each is guided by the
+/// albedo, normal and depth AOVs of the film, if stored. Parameter is the
+/// radius of the search window in pixels.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Denoiser {
+    /// Cross-bilateral filter with a Gaussian spatial kernel and range
+    /// kernels on the color and features
+    CrossBilateral(i32),
+    /// Non-local means that compares small patches of color
+    /// around the pixels, weighted by features
+    NonLocalMeans(i32),
+    /// Non-local means with the patch distances normalized by the
+    /// estimated variance of the pixels. Filters noisy pixels more
+    /// aggressively and leaves converged pixels alone.
+    VarianceGuided(i32),
+}
+
+impl fmt::Display for Denoiser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CrossBilateral(r) => write!(f, "cross-bilateral (radius {})", r),
+            Self::NonLocalMeans(r) => write!(f, "non-local means (radius {})", r),
+            Self::VarianceGuided(r) => write!(f, "variance guided (radius {})", r),
+        }
+    }
+}
+
+impl Denoiser {
+    /// AOVs that guide the denoiser
+    pub const FEATURES: [Aov; 3] = [Aov::Albedo, Aov::Normal, Aov::Depth];
+
+    fn radius(&self) -> i32 {
+        match self {
+            Self::CrossBilateral(r)
+                | Self::NonLocalMeans(r)
+                | Self::VarianceGuided(r) => *r,
+        }
+    }
+}
+
+/// Resolved buffers of the film that the denoiser reads
+struct Buffers {
+    width: i32,
+    height: i32,
+    color: Vec<Color>,
+    /// Variance of the mean luminance of each pixel
+    variance: Vec<Float>,
+    sampled: Vec<bool>,
+    albedo: Option<Vec<Color>>,
+    normal: Option<Vec<Color>>,
+    depth: Option<Vec<Color>>,
+}
+
+impl Buffers {
+    fn new(film: &Film) -> Self {
+        Self {
+            width: film.resolution.x,
+            height: film.resolution.y,
+            color: film.resolve(),
+            variance: film.pixels.iter().map(|px| px.variance()).collect(),
+            sampled: film.pixels.iter().map(|px| px.samples > 0).collect(),
+            albedo: film.resolve_aov(Aov::Albedo),
+            normal: film.resolve_aov(Aov::Normal),
+            depth: film.resolve_aov(Aov::Depth),
+        }
+    }
+
+    fn idx(&self, x: i32, y: i32) -> usize {
+        (x + y * self.width) as usize
+    }
+
+    /// Squared color distance of pixels `p` and `q` relative to their brightness
+    fn relative_distance(&self, p: usize, q: usize) -> Float {
+        let (cp, cq) = (self.color[p].rgb, self.color[q].rgb);
+        (cp - cq).length_squared() / (EPSILON + cp.length_squared() + cq.length_squared())
+    }
+
+    /// Distance of pixels `p` and `q` normalized by their variances
+    /// as in Rousselle et al. 2012. Negative values mean the difference
+    /// is explained by noise.
+    fn variance_distance(&self, p: usize, q: usize) -> Float {
+        let (vp, vq) = (self.variance[p], self.variance[q]);
+        let (vp, vq) = (vp.min(1e10), vq.min(1e10));
+        let diff = self.color[p].rgb - self.color[q].rgb;
+        let d2 = diff.length_squared() / 3.0 - (vp + vp.min(vq));
+        d2 / (EPSILON + VARIANCE_SENSITIVITY * VARIANCE_SENSITIVITY * (vp + vq))
+    }
+
+    /// Mean of `distance` over the patches centered at `(px, py)` and `(qx, qy)`
+    fn patch_distance(
+        &self,
+        (px, py): (i32, i32),
+        (qx, qy): (i32, i32),
+        distance: impl Fn(&Self, usize, usize) -> Float,
+    ) -> Float {
+        let mut sum = 0.0;
+        let mut n = 0;
+        for dy in -PATCH_RADIUS..=PATCH_RADIUS {
+            for dx in -PATCH_RADIUS..=PATCH_RADIUS {
+                let (x0, y0) = (px + dx, py + dy);
+                let (x1, y1) = (qx + dx, qy + dy);
+                if !self.contains(x0, y0) || !self.contains(x1, y1) {
+                    continue;
+                }
+                sum += distance(self, self.idx(x0, y0), self.idx(x1, y1));
+                n += 1;
+            }
+        }
+
+        if n == 0 { 0.0 } else { sum / n as Float }
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        (0..self.width).contains(&x) && (0..self.height).contains(&y)
+    }
+
+    /// Product of the range kernels of the features of pixels `p` and `q`
+    fn feature_weight(&self, p: usize, q: usize) -> Float {
+        let mut exponent = 0.0;
+        if let Some(albedo) = &self.albedo {
+            let d2 = (albedo[p].rgb - albedo[q].rgb).length_squared();
+            exponent += d2 / (SIGMA_ALBEDO * SIGMA_ALBEDO);
+        }
+        if let Some(normal) = &self.normal {
+            let d2 = (normal[p].rgb - normal[q].rgb).length_squared();
+            exponent += d2 / (SIGMA_NORMAL * SIGMA_NORMAL);
+        }
+        if let Some(depth) = &self.depth {
+            let (dp, dq) = (depth[p].rgb.x, depth[q].rgb.x);
+            let d2 = (dp - dq) * (dp - dq) / (EPSILON + dp * dp);
+            exponent += d2 / (SIGMA_DEPTH * SIGMA_DEPTH);
+        }
+
+        (-0.5 * exponent).exp()
+    }
+
+    /// Denoised color of the pixel at `(x, y)`
+    fn denoise_pixel(&self, denoiser: Denoiser, x: i32, y: i32) -> Color {
+        let p = self.idx(x, y);
+        if !self.sampled[p] {
+            return self.color[p];
+        }
+
+        let radius = denoiser.radius();
+        let sigma_spatial = (radius as Float / 2.0).max(0.5);
+        let mut color_sum = Color::BLACK;
+        let mut weight_sum = 0.0;
+
+        for qy in (y - radius).max(0)..=(y + radius).min(self.height - 1) {
+            for qx in (x - radius).max(0)..=(x + radius).min(self.width - 1) {
+                let q = self.idx(qx, qy);
+                if !self.sampled[q] {
+                    continue;
+                }
+
+                let range = match denoiser {
+                    Denoiser::CrossBilateral(_) => {
+                        let d2 = ((qx - x).pow(2) + (qy - y).pow(2)) as Float;
+                        let spatial = d2 / (sigma_spatial * sigma_spatial);
+                        let color = self.relative_distance(p, q) / (SIGMA_COLOR * SIGMA_COLOR);
+                        (-0.5 * (spatial + color)).exp()
+                    }
+                    Denoiser::NonLocalMeans(_) => {
+                        let d2 = self.patch_distance((x, y), (qx, qy), Self::relative_distance);
+                        (-d2 / (NLM_STRENGTH * NLM_STRENGTH)).exp()
+                    }
+                    Denoiser::VarianceGuided(_) => {
+                        let d2 = self.patch_distance((x, y), (qx, qy), Self::variance_distance);
+                        (-d2.max(0.0)).exp()
+                    }
+                };
+
+                let weight = range * self.feature_weight(p, q);
+                color_sum += self.color[q] * weight;
+                weight_sum += weight;
+            }
+        }
+
+        if weight_sum == 0.0 { self.color[p] } else { color_sum / weight_sum }
+    }
+}
+
+/// Denoises the resolved image of `film` with `denoiser`
+pub fn denoise(film: &Film, denoiser: Denoiser) -> Vec<Color> {
+    let buffers = Buffers::new(film);
+
+    (0..buffers.height).into_par_iter()
+        .flat_map_iter(|y| {
+            let buffers = &buffers;
+            (0..buffers.width).map(move |x| buffers.denoise_pixel(denoiser, x, y))
+        })
+        .collect()
+}
@@ -0,0 +1,186 @@
+use crate::Float;
+use crate::tracer::ColorSpace;
+use glam::IVec2;
+use std::{fs::File, io::{self, BufWriter, Write}};
+
+/// Precision of the channels in OpenEXR output
+#[derive(Clone, Copy)]
+pub enum ExrPrecision {
+    /// 16-bit half precision floats
+    Half,
+    /// 32-bit single precision floats
+    Full,
+}
+
+impl ExrPrecision {
+    /// Pixel type as defined in the OpenEXR specification
+    fn pixel_type(&self) -> i32 {
+        match self {
+            Self::Half => 1,
+            Self::Full => 2,
+        }
+    }
+
+    /// Size of one value in bytes
+    fn size(&self) -> usize {
+        match self {
+            Self::Half => 2,
+            Self::Full => 4,
+        }
+    }
+}
+
+/// Named channel of an OpenEXR image
+pub struct ExrChannel {
+    /// Name of the channel, e.g. `R` or `albedo.R`
+    pub name: String,
+    /// Values of the channel row by row starting from the top left corner
+    pub values: Vec<Float>,
+}
+
+impl ExrChannel {
+    /// Creates channel `name` with `values`
+    pub fn new(name: &str, values: Vec<Float>) -> Self {
+        Self { name: name.to_string(), values }
+    }
+}
+
+/// Converts `v` to IEEE 754 half precision float with round to nearest even
+pub fn f32_to_f16(v: f32) -> u16 {
+    let bits = v.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x007f_ffff;
+
+    // infinity or NaN
+    if exp == 0xff {
+        let nan = if mantissa == 0 { 0 } else { 0x0200 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let exp = exp - 127 + 15;
+    if exp >= 0x1f {
+        // overflow to infinity
+        sign | 0x7c00
+    } else if exp <= 0 {
+        // subnormal or underflow to zero
+        if exp < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x0080_0000;
+        let shift = (14 - exp) as u32;
+        let half = 1 << (shift - 1);
+        let rest = mantissa & ((1 << shift) - 1);
+        let mut value = mantissa >> shift;
+        if rest > half || (rest == half && value & 1 == 1) {
+            value += 1;
+        }
+        sign | value as u16
+    } else {
+        let half = 0x0000_1000;
+        let rest = mantissa & 0x0000_1fff;
+        let mut value = ((exp as u32) << 10) | (mantissa >> 13);
+        // rounding can carry over to the exponent, which is what we want
+        if rest > half || (rest == half && value & 1 == 1) {
+            value += 1;
+        }
+        sign | value as u16
+    }
+}
+
+fn write_attribute<W: Write>(
+    writer: &mut W,
+    name: &str,
+    kind: &str,
+    value: &[u8]
+) -> io::Result<()> {
+    writer.write_all(name.as_bytes())?;
+    writer.write_all(&[0])?;
+    writer.write_all(kind.as_bytes())?;
+    writer.write_all(&[0])?;
+    writer.write_all(&(value.len() as i32).to_le_bytes())?;
+    writer.write_all(value)
+}
+
+/// Writes uncompressed scanline OpenEXR image of `channels` to `fname`.
+/// Color values are linear RGB in `space`.
+pub fn write(
+    fname: &str,
+    resolution: IVec2,
+    mut channels: Vec<ExrChannel>,
+    precision: ExrPrecision,
+    space: ColorSpace,
+) -> io::Result<()> {
+    // specification requires channels in alphabetical order
+    channels.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // magic number and version 2 with single part scanline flags
+    let mut header = vec![0x76, 0x2f, 0x31, 0x01];
+    header.extend_from_slice(&2_i32.to_le_bytes());
+
+    let mut chlist = Vec::new();
+    for channel in &channels {
+        chlist.extend_from_slice(channel.name.as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&precision.pixel_type().to_le_bytes());
+        // pLinear and reserved
+        chlist.extend_from_slice(&[0, 0, 0, 0]);
+        // x and y sampling
+        chlist.extend_from_slice(&1_i32.to_le_bytes());
+        chlist.extend_from_slice(&1_i32.to_le_bytes());
+    }
+    chlist.push(0);
+    write_attribute(&mut header, "channels", "chlist", &chlist)?;
+
+    let mut chromaticities = Vec::new();
+    for xy in space.chromaticities() {
+        chromaticities.extend_from_slice(&(xy.x as f32).to_le_bytes());
+        chromaticities.extend_from_slice(&(xy.y as f32).to_le_bytes());
+    }
+    write_attribute(&mut header, "chromaticities", "chromaticities", &chromaticities)?;
+    write_attribute(&mut header, "compression", "compression", &[0])?;
+
+    let mut window = Vec::new();
+    for v in [0, 0, resolution.x - 1, resolution.y - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+    write_attribute(&mut header, "dataWindow", "box2i", &window)?;
+    write_attribute(&mut header, "displayWindow", "box2i", &window)?;
+    write_attribute(&mut header, "lineOrder", "lineOrder", &[0])?;
+    write_attribute(&mut header, "pixelAspectRatio", "float", &1_f32.to_le_bytes())?;
+    write_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8])?;
+    write_attribute(&mut header, "screenWindowWidth", "float", &1_f32.to_le_bytes())?;
+    header.push(0);
+
+    let mut writer = BufWriter::new(File::create(fname)?);
+    writer.write_all(&header)?;
+
+    let line_size = resolution.x as usize * channels.len() * precision.size();
+    let block_size = 8 + line_size;
+    let table_size = 8 * resolution.y as usize;
+    for y in 0..resolution.y as usize {
+        let offset = (header.len() + table_size + y * block_size) as u64;
+        writer.write_all(&offset.to_le_bytes())?;
+    }
+
+    for y in 0..resolution.y {
+        writer.write_all(&y.to_le_bytes())?;
+        writer.write_all(&(line_size as i32).to_le_bytes())?;
+        for channel in &channels {
+            let start = (y * resolution.x) as usize;
+            let end = start + resolution.x as usize;
+            for v in &channel.values[start..end] {
+                match precision {
+                    ExrPrecision::Half => {
+                        writer.write_all(&f32_to_f16(*v as f32).to_le_bytes())?
+                    }
+                    ExrPrecision::Full => {
+                        writer.write_all(&(*v as f32).to_le_bytes())?
+                    }
+                }
+            }
+        }
+    }
+
+    writer.flush()
+}
@@ -0,0 +1,603 @@
+use super::*;
+use crate::Vec3;
+use std::io::Read;
+
+fn read_bytes(file: &tempfile::NamedTempFile) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    File::open(file.path()).unwrap().read_to_end(&mut bytes).unwrap();
+    bytes
+}
+
+fn film_with(color: Color) -> Film {
+    let aovs = [Aov::Albedo, Aov::Depth];
+    let mut film = Film::new(4, 3, &aovs);
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(0.5), &aovs);
+    for y in 0..film.resolution.y {
+        for x in 0..film.resolution.x {
+            let xy = Vec2::new(x as Float, y as Float) + 0.5;
+            let mut sample = FilmSample::new(color, xy, false);
+            sample.set_aov(Aov::Albedo, Color::splat(0.25));
+            sample.set_aov(Aov::Depth, Color::splat(x as Float));
+            sample.set_aov(Aov::Normal, Color::WHITE);
+            // camera rays of the top row miss the scene
+            sample.alpha = if y == 0 { 0.0 } else { 1.0 };
+            tile.add_sample(sample);
+        }
+    }
+    film.add_tile(tile);
+    film
+}
+
+fn film_with_samples(lums: &[Float]) -> Film {
+    let mut film = Film::new(1, 1, &[]);
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(0.5), &[]);
+    for lum in lums {
+        tile.add_sample(FilmSample::new(Color::splat(*lum), Vec2::splat(0.5), false));
+    }
+    film.add_tile(tile);
+    film
+}
+
+#[test]
+fn half_floats() {
+    assert!(exr::f32_to_f16(0.0) == 0x0000);
+    assert!(exr::f32_to_f16(-0.0) == 0x8000);
+    assert!(exr::f32_to_f16(1.0) == 0x3c00);
+    assert!(exr::f32_to_f16(-2.0) == 0xc000);
+    assert!(exr::f32_to_f16(65504.0) == 0x7bff);
+    assert!(exr::f32_to_f16(1e6) == 0x7c00);
+    assert!(exr::f32_to_f16(f32::NAN) & 0x7c00 == 0x7c00);
+    // smallest subnormal
+    assert!(exr::f32_to_f16(5.960_464_5e-8) == 0x0001);
+    // rounds to nearest
+    assert!(exr::f32_to_f16(1.0 + 1.0 / 1024.0) == 0x3c01);
+    assert!(exr::f32_to_f16(1.0 + 1.0 / 4096.0) == 0x3c00);
+}
+
+#[test]
+fn resolve_keeps_high_dynamic_range() {
+    let film = film_with(Color::splat(12.5));
+    assert!(film.resolve().iter().all(|c| c.rgb == Vec3::splat(12.5)));
+}
+
+#[test]
+fn resolve_empty_pixels_black() {
+    let film = Film::new(2, 2, &[]);
+    assert!(film.resolve().iter().all(|c| c.is_black()));
+}
+
+#[test]
+fn splats_scaled_by_samples() {
+    let mut film = Film::new(2, 2, &[]);
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(0.5), &[]);
+    tile.add_sample(FilmSample::new(Color::splat(4.0), Vec2::splat(0.5), true));
+    for _ in 0..2 {
+        tile.add_sample(FilmSample::new(Color::BLACK, Vec2::splat(0.5), false));
+    }
+    film.add_tile(tile);
+    // two light paths splat over a film of four pixels
+    assert!(film.resolve()[0].rgb == Vec3::splat(8.0));
+
+    // uneven sample counts, splats scaled by the pixels per light path
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(0.5), &[]);
+    for _ in 0..6 {
+        tile.add_sample(FilmSample::new(Color::BLACK, Vec2::splat(1.5), false));
+    }
+    film.add_tile(tile);
+    assert!(film.resolve()[0].rgb == Vec3::splat(2.0));
+    assert!(film.resolve()[1].is_black());
+}
+
+#[test]
+fn sample_counts_tracked() {
+    let film = film_with(Color::WHITE);
+    assert!(film.sample_counts().iter().all(|c| *c == 1));
+    assert!(film.noise().is_none());
+
+    let film = film_with_samples(&[1.0; 5]);
+    assert!(film.sample_counts() == vec![5]);
+    assert!(film.pixel_errors()[0] == 0.0);
+    assert!(film.noise() == Some(0.0));
+}
+
+#[test]
+fn relative_error_of_pixel() {
+    assert!(film_with_samples(&[1.0]).pixel_errors()[0] == crate::INF);
+
+    // mean 2, sample variance 2, standard error 1
+    let film = film_with_samples(&[1.0, 3.0]);
+    let err = film.pixel_errors()[0];
+    assert!((err - 0.5).abs() < 1e-10);
+
+    // more samples of the same distribution, smaller error
+    let film = film_with_samples(&[1.0, 3.0, 1.0, 3.0, 1.0, 3.0, 1.0, 3.0]);
+    assert!(film.pixel_errors()[0] < err);
+}
+
+#[test]
+fn sample_counts_saved() {
+    let film = film_with_samples(&[1.0; 3]);
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let fname = format!("{}.pfm", file.path().to_str().unwrap());
+    film.save_sample_counts(&fname).unwrap();
+
+    let bytes = std::fs::read(&fname).unwrap();
+    std::fs::remove_file(&fname).unwrap();
+    let header = b"PF\n1 1\n-1.0\n";
+    let v = f32::from_le_bytes(bytes[header.len()..header.len() + 4].try_into().unwrap());
+    assert!(v == 3.0);
+}
+
+#[test]
+fn aovs_resolved() {
+    let film = film_with(Color::WHITE);
+    let albedo = film.resolve_aov(Aov::Albedo).unwrap();
+    assert!(albedo.iter().all(|c| c.rgb == Vec3::splat(0.25)));
+
+    let depth = film.resolve_aov(Aov::Depth).unwrap();
+    assert!(depth[5].rgb.x == 1.0);
+    assert!(depth[7].rgb.x == 3.0);
+
+    assert!(film.resolve_aov(Aov::Normal).is_none());
+}
+
+#[test]
+fn aov_not_stored() {
+    let film = film_with(Color::WHITE);
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let fname = format!("{}.png", file.path().to_str().unwrap());
+    assert!(film.save_aov(Aov::Normal, &fname).is_err());
+    assert!(film.save_aov(Aov::Albedo, "albedo.jpg").is_err());
+}
+
+#[test]
+fn sample_aovs() {
+    let mut sample = FilmSample::default();
+    assert!(sample.aov(Aov::Direct).is_black());
+    sample.set_aov(Aov::Direct, Color::WHITE);
+    sample.set_aov(Aov::Direct, Color::splat(2.0));
+    assert!(sample.aovs.len() == 1);
+    assert!(sample.aov(Aov::Direct).rgb == Vec3::splat(2.0));
+}
+
+#[test]
+fn pfm_layout() {
+    let film = film_with(Color::splat(3.0));
+    let file = tempfile::NamedTempFile::new().unwrap();
+    film.save_pfm(file.path().to_str().unwrap()).unwrap();
+
+    let bytes = read_bytes(&file);
+    let header = b"PF\n4 3\n-1.0\n";
+    assert!(bytes.starts_with(header));
+    assert!(bytes.len() == header.len() + 4 * 3 * 3 * 4);
+    let v = f32::from_le_bytes(bytes[header.len()..header.len() + 4].try_into().unwrap());
+    assert!(v == 3.0);
+}
+
+#[test]
+fn exr_layout() {
+    let film = film_with(Color::splat(0.5));
+    // rgb, albedo and depth
+    let num_channels = 3 + 3 + 1;
+    for (precision, size) in [(ExrPrecision::Half, 2), (ExrPrecision::Full, 4)] {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        film.save_exr(file.path().to_str().unwrap(), precision).unwrap();
+
+        let bytes = read_bytes(&file);
+        assert!(bytes[0..4] == [0x76, 0x2f, 0x31, 0x01]);
+
+        let line_size = 4 * num_channels * size;
+        let block_size = 8 + line_size;
+        let data_size = 3 * block_size;
+        let table_start = bytes.len() - data_size - 3 * 8;
+
+        // offset table points to the scanline blocks
+        for y in 0..3 {
+            let idx = table_start + 8 * y;
+            let offset = u64::from_le_bytes(bytes[idx..idx + 8].try_into().unwrap());
+            let offset = offset as usize;
+            assert!(offset == table_start + 3 * 8 + y * block_size);
+            let line = i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+            assert!(line == y as i32);
+        }
+    }
+}
+
+#[test]
+fn checkpoint_roundtrip() {
+    let mut film = film_with(Color::splat(0.75));
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(0.5), &[]);
+    tile.add_sample(FilmSample::new(Color::splat(2.0), Vec2::splat(1.5), true));
+    film.add_tile(tile);
+    film.add_samples(1);
+    film.set_working_space(ColorSpace::AcesCg);
+
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let fname = file.path().to_str().unwrap();
+    film.save_checkpoint(fname).unwrap();
+    let loaded = Film::load_checkpoint(fname).unwrap();
+
+    assert!(loaded.resolution == film.resolution);
+    assert!(loaded.samples() == 1);
+    assert!(loaded.aovs() == film.aovs());
+    assert!(loaded.working_space() == ColorSpace::AcesCg);
+    assert!(loaded.sample_counts() == film.sample_counts());
+    assert!(loaded.resolve_alpha() == film.resolve_alpha());
+    let same = |a: Vec<Color>, b: Vec<Color>| a.iter().zip(&b).all(|(a, b)| a.rgb == b.rgb);
+    assert!(same(loaded.resolve(), film.resolve()));
+    assert!(same(
+        loaded.resolve_aov(Aov::Depth).unwrap(),
+        film.resolve_aov(Aov::Depth).unwrap(),
+    ));
+}
+
+#[test]
+fn checkpoint_validated() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let fname = file.path().to_str().unwrap();
+
+    std::fs::write(fname, b"not a checkpoint").unwrap();
+    assert!(Film::load_checkpoint(fname).is_err());
+
+    // pixels have samples, but the film claims none were taken
+    film_with(Color::WHITE).save_checkpoint(fname).unwrap();
+    assert!(Film::load_checkpoint(fname).is_err());
+
+    let mut film = film_with(Color::WHITE);
+    film.add_samples(1);
+    film.save_checkpoint(fname).unwrap();
+    let mut bytes = std::fs::read(fname).unwrap();
+    bytes.pop();
+    std::fs::write(fname, &bytes).unwrap();
+    assert!(Film::load_checkpoint(fname).is_err());
+}
+
+#[test]
+fn merged_films_weighted_by_samples() {
+    let partial = |color: Color, splat: Option<Color>| {
+        let mut film = Film::new(2, 1, &[]);
+        let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(0.5), &[]);
+        for x in 0..2 {
+            let xy = Vec2::new(x as Float + 0.5, 0.5);
+            tile.add_sample(FilmSample::new(color, xy, false));
+        }
+        if let Some(splat) = splat {
+            tile.add_sample(FilmSample::new(splat, Vec2::splat(0.5), true));
+        }
+        film.add_tile(tile);
+        film.add_samples(1);
+        film
+    };
+
+    let mut film = partial(Color::WHITE, Some(Color::splat(4.0)));
+    film.merge(&partial(Color::splat(3.0), None)).unwrap();
+    assert!(film.samples() == 2);
+    assert!(film.sample_counts() == vec![2, 2]);
+
+    let pixels = film.resolve();
+    // mean of the filtered colors and splat divided by two samples
+    assert!(pixels[0].rgb == Vec3::splat(4.0));
+    assert!(pixels[1].rgb == Vec3::splat(2.0));
+
+    assert!(film.merge(&Film::new(1, 2, &[])).is_err());
+    assert!(film.merge(&Film::new(2, 1, &[Aov::Depth])).is_err());
+    let mut aces = Film::new(2, 1, &[]);
+    aces.set_working_space(ColorSpace::AcesCg);
+    assert!(film.merge(&aces).is_err());
+}
+
+#[test]
+fn merge_checkpoints() {
+    let mut film = film_with(Color::WHITE);
+    film.add_samples(1);
+    let files: Vec<_> = (0..3).map(|_| tempfile::NamedTempFile::new().unwrap()).collect();
+    let fnames: Vec<&str> = files.iter().map(|f| f.path().to_str().unwrap()).collect();
+    for fname in &fnames {
+        film.save_checkpoint(fname).unwrap();
+    }
+
+    let merged = Film::merge_checkpoints(&fnames).unwrap();
+    assert!(merged.samples() == 3);
+    assert!(merged.sample_counts().iter().all(|c| *c == 3));
+    assert!(Film::merge_checkpoints(&[]).is_err());
+}
+
+#[test]
+fn crop_window() {
+    let mut film = Film::new(4, 3, &[]);
+    assert!(film.try_set_crop_window(IVec2::new(1, 1), IVec2::new(1, 3)).is_err());
+    assert!(film.try_set_crop_window(IVec2::new(1, 1), IVec2::new(5, 3)).is_err());
+    film.set_crop_window(IVec2::new(1, 1), IVec2::new(3, 3));
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(0.5), &[]);
+    for y in 1..3 {
+        for x in 1..3 {
+            let xy = Vec2::new(x as Float, y as Float) + 0.5;
+            tile.add_sample(FilmSample::new(Color::splat((x + 2 * y) as Float), xy, false));
+        }
+    }
+    // splat outside of the crop window
+    tile.add_sample(FilmSample::new(Color::WHITE, Vec2::splat(0.5), true));
+    film.add_tile(tile);
+    film.add_samples(1);
+
+    let full = film.resolve();
+    assert!(full[0].is_black());
+    assert!(full[5].rgb == Vec3::splat(3.0));
+
+    let cropped = film.cropped();
+    assert!(cropped.resolution == IVec2::new(2, 2));
+    assert!(cropped.crop_window() == (IVec2::ZERO, IVec2::new(2, 2)));
+    let pixels = cropped.resolve();
+    assert!(pixels[0].rgb == Vec3::splat(3.0));
+    assert!(pixels[3].rgb == Vec3::splat(6.0));
+
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let fname = file.path().to_str().unwrap();
+    film.save_checkpoint(fname).unwrap();
+    let loaded = Film::load_checkpoint(fname).unwrap();
+    assert!(loaded.crop_window() == film.crop_window());
+
+    let mut other = Film::new(4, 3, &[]);
+    other.set_crop_window(IVec2::ZERO, IVec2::new(2, 1));
+    film.merge(&other).unwrap();
+    assert!(film.crop_window() == (IVec2::ZERO, IVec2::new(3, 3)));
+}
+
+#[test]
+fn wide_filter_splats_across_tiles() {
+    let mut film = Film::new(4, 1, &[]);
+    let filter = Filter::Triangle(1.5);
+    let mut left = FilmTile::new(IVec2::ZERO, IVec2::new(2, 1), filter, &[]);
+    let mut right = FilmTile::new(IVec2::new(2, 0), IVec2::new(4, 1), filter, &[]);
+    left.add_sample(FilmSample::new(Color::WHITE, Vec2::new(1.5, 0.5), false));
+    // outside of the tile, belongs to the right tile
+    left.add_sample(FilmSample::new(Color::WHITE, Vec2::new(2.5, 0.5), false));
+    right.add_sample(FilmSample::new(Color::splat(3.0), Vec2::new(3.5, 0.5), false));
+    film.add_tile(left);
+    film.add_tile(right);
+
+    assert_eq!(film.sample_counts(), vec![0, 1, 0, 1]);
+    let weights: Vec<Float> = film.pixels.iter().map(|px| px.filter_weight_sum).collect();
+    // pixel 2 gets weight from samples in both tiles
+    assert!((weights[0] - 0.5 * 1.5).abs() < 1e-10);
+    assert!((weights[2] - 2.0 * 0.5 * 1.5).abs() < 1e-10);
+    assert!((film.pixels[2].color.rgb.x - (0.5 + 1.5) * 1.5).abs() < 1e-10);
+}
+
+#[test]
+fn negative_filter_weights_resolve_to_non_negative() {
+    let mut film = Film::new(3, 1, &[]);
+    let filter = Filter::Mitchell(2.0, 1.0 / 3.0, 1.0 / 3.0);
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, filter, &[]);
+    // one sample in a negative lobe
+    tile.add_weighted_sample(FilmSample::new(Color::WHITE, Vec2::new(0.5, 0.5), false), -0.5);
+    // negative lobe outweighs the color of the other sample
+    tile.add_weighted_sample(FilmSample::new(Color::BLACK, Vec2::new(1.5, 0.5), false), 1.0);
+    tile.add_weighted_sample(FilmSample::new(Color::WHITE, Vec2::new(1.5, 0.5), false), -0.5);
+    // weights cancel out
+    tile.add_weighted_sample(FilmSample::new(Color::WHITE, Vec2::new(2.5, 0.5), false), 1.0);
+    tile.add_weighted_sample(FilmSample::new(Color::WHITE, Vec2::new(2.5, 0.5), false), -1.0);
+    film.add_tile(tile);
+
+    assert!(film.resolve().iter().all(|px| px.is_black()));
+    assert!(film.resolve_alpha().iter().all(|a| *a == 0.0));
+}
+
+#[test]
+fn splats_spread_with_filter() {
+    let mut film = Film::new(3, 3, &[]);
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(1.5), &[]);
+    tile.add_sample(FilmSample::new(Color::splat(9.0), Vec2::splat(1.5), true));
+    film.add_tile(tile);
+
+    let total: Float = film.pixels.iter().map(|px| px.splat.rgb.x).sum();
+    assert!((total - 9.0).abs() < 1e-6);
+    assert!((film.pixels[0].splat.rgb.x - 1.0).abs() < 1e-6);
+}
+
+/// 16x16 film with 4 noisy samples per pixel. Left half has `left` color
+/// and albedo, right half `right`.
+fn noisy_film(left: Float, right: Float) -> Film {
+    let aovs = Denoiser::FEATURES;
+    let mut film = Film::new(16, 16, &aovs);
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(0.5), &aovs);
+    for y in 0..16 {
+        for x in 0..16 {
+            let base = if x < 8 { left } else { right };
+            for i in 0..4 {
+                let h = crate::rand_utils::hash((x + 16 * y) as u64 * 4 + i);
+                let noise = (h % 1000) as Float / 1000.0 - 0.5;
+                let xy = Vec2::new(x as Float, y as Float) + 0.5;
+                let mut sample = FilmSample::new(Color::splat(base * (1.0 + noise)), xy, false);
+                sample.set_aov(Aov::Albedo, Color::splat(base));
+                sample.set_aov(Aov::Normal, Color::from(Vec3::Z));
+                sample.set_aov(Aov::Depth, Color::splat(2.0));
+                tile.add_sample(sample);
+            }
+        }
+    }
+    film.add_tile(tile);
+    film.add_samples(4);
+    film
+}
+
+fn mean_and_variance(colors: &[Color]) -> (Float, Float) {
+    let n = colors.len() as Float;
+    let mean = colors.iter().map(|c| c.rgb.x).sum::<Float>() / n;
+    let var = colors.iter().map(|c| (c.rgb.x - mean).powi(2)).sum::<Float>() / n;
+    (mean, var)
+}
+
+#[test]
+fn denoisers_reduce_noise() {
+    let film = noisy_film(0.5, 0.5);
+    let (mean, var) = mean_and_variance(&film.resolve());
+    for denoiser in [
+        Denoiser::CrossBilateral(3),
+        Denoiser::NonLocalMeans(3),
+        Denoiser::VarianceGuided(3),
+    ] {
+        let (d_mean, d_var) = mean_and_variance(&film.denoise(denoiser));
+        assert!((d_mean - mean).abs() < 0.01);
+        assert!(d_var < 0.5 * var);
+    }
+}
+
+#[test]
+fn denoisers_keep_feature_edges() {
+    let film = noisy_film(0.1, 0.9);
+    for denoiser in [
+        Denoiser::CrossBilateral(3),
+        Denoiser::NonLocalMeans(3),
+        Denoiser::VarianceGuided(3),
+    ] {
+        let denoised = film.denoise(denoiser);
+        for y in 0..16 {
+            assert!((denoised[7 + 16 * y].rgb.x - 0.1).abs() < 0.05);
+            assert!((denoised[8 + 16 * y].rgb.x - 0.9).abs() < 0.1);
+        }
+    }
+}
+
+#[test]
+fn save_denoised_pfm() {
+    let film = noisy_film(0.5, 0.5);
+    let file = tempfile::Builder::new().suffix(".pfm").tempfile().unwrap();
+    let fname = file.path().to_str().unwrap();
+    film.save_denoised(fname, Denoiser::VarianceGuided(2), &PostProcess::default()).unwrap();
+    assert_eq!(read_bytes(&file).len(), 16 * 16 * 12 + "PF\n16 16\n-1.0\n".len());
+}
+
+#[test]
+fn png_color_chunks() {
+    let mut film = film_with(Color::splat(0.5));
+    let file = tempfile::Builder::new().suffix(".png").tempfile().unwrap();
+    let fname = file.path().to_str().unwrap();
+
+    film.save(fname).unwrap();
+    let reader = png::Decoder::new(File::open(fname).unwrap()).read_info().unwrap();
+    assert!(reader.info().srgb.is_some());
+    assert!(reader.info().icc_profile.is_none());
+
+    film.set_output_encoding(ColorSpace::Rec2020, Transfer::Linear);
+    film.save(fname).unwrap();
+    let reader = png::Decoder::new(File::open(fname).unwrap()).read_info().unwrap();
+    let info = reader.info();
+    assert!(info.srgb.is_none());
+    assert!((info.gama_chunk.unwrap().into_value() - 1.0).abs() < 1e-5);
+    assert!((info.chrm_chunk.unwrap().red.0.into_value() - 0.708).abs() < 1e-5);
+
+    let profile = info.icc_profile.as_ref().unwrap();
+    assert!(u32::from_be_bytes(profile[0..4].try_into().unwrap()) as usize == profile.len());
+    assert!(&profile[36..40] == b"acsp");
+    // red primary of Rec. 2020 adapted to D50
+    let tags = u32::from_be_bytes(profile[128..132].try_into().unwrap()) as usize;
+    let red = (0..tags)
+        .map(|i| &profile[132 + 12 * i..144 + 12 * i])
+        .find(|entry| &entry[0..4] == b"rXYZ")
+        .map(|entry| u32::from_be_bytes(entry[4..8].try_into().unwrap()) as usize)
+        .unwrap();
+    let fixed = |i: usize| {
+        let bytes = profile[red + 8 + 4 * i..red + 12 + 4 * i].try_into().unwrap();
+        i32::from_be_bytes(bytes) as Float / 65536.0
+    };
+    let expected = ColorSpace::Rec2020.rgb_to_xyz_d50().x_axis;
+    assert!((Vec3::new(fixed(0), fixed(1), fixed(2)) - expected).abs().max_element() < 1e-4);
+}
+
+#[test]
+fn png_roundtrip_through_texture() {
+    let color = Color::from(Vec3::new(0.1, 0.4, 0.8));
+    let file = tempfile::Builder::new().suffix(".png").tempfile().unwrap();
+    let fname = file.path().to_str().unwrap();
+
+    for (space, transfer) in [
+        (ColorSpace::Srgb, Transfer::Srgb),
+        (ColorSpace::Rec2020, Transfer::Gamma(2.4)),
+        (ColorSpace::AcesCg, Transfer::Srgb),
+    ] {
+        let mut film = film_with(color);
+        film.set_output_encoding(space, transfer);
+        film.save(fname).unwrap();
+        let img = crate::Image::from_path(fname).unwrap();
+        assert!(img.buffer.iter().all(|c| (c.rgb - color.rgb).abs().max_element() < 0.01));
+    }
+}
+
+#[test]
+fn png_sixteen_bit_depth() {
+    let color = Color::splat(0.002);
+    let mut film = film_with(color);
+    film.set_png_depth(PngDepth::Sixteen);
+    let file = tempfile::Builder::new().suffix(".png").tempfile().unwrap();
+    let fname = file.path().to_str().unwrap();
+
+    film.save(fname).unwrap();
+    let reader = png::Decoder::new(File::open(fname).unwrap()).read_info().unwrap();
+    assert!(reader.info().bit_depth == png::BitDepth::Sixteen);
+
+    // finer than the steps of 8-bit values in the dark
+    let img = crate::Image::from_path(fname).unwrap();
+    assert!(img.buffer.iter().all(|c| (c.rgb - color.rgb).abs().max_element() < 1e-5));
+}
+
+/// Film of two pixels where camera rays hit something in
+/// all of the left and half of the right pixel
+fn film_with_coverage() -> Film {
+    let mut film = Film::new(2, 1, &[]);
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(0.5), &[]);
+    for (x, alpha) in [(0.5, 1.0), (0.5, 1.0), (1.5, 1.0), (1.5, 0.0)] {
+        let mut sample = FilmSample::new(Color::splat(0.5) * alpha, Vec2::new(x, 0.5), false);
+        sample.alpha = alpha;
+        tile.add_sample(sample);
+    }
+    film.add_tile(tile);
+    film
+}
+
+#[test]
+fn alpha_from_coverage() {
+    let film = film_with_coverage();
+    assert!(film.resolve_alpha() == vec![1.0, 0.5]);
+}
+
+#[test]
+fn png_transparent_background() {
+    let mut film = film_with_coverage();
+    let file = tempfile::Builder::new().suffix(".png").tempfile().unwrap();
+    let fname = file.path().to_str().unwrap();
+
+    film.save(fname).unwrap();
+    let reader = png::Decoder::new(File::open(fname).unwrap()).read_info().unwrap();
+    assert!(reader.info().color_type == png::ColorType::Rgb);
+
+    film.set_transparent_background(true);
+    film.save(fname).unwrap();
+    let mut reader = png::Decoder::new(File::open(fname).unwrap()).read_info().unwrap();
+    assert!(reader.info().color_type == png::ColorType::Rgba);
+    let mut bytes = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut bytes).unwrap();
+    // straight alpha, the half covered pixel keeps the color of the object
+    assert!(bytes[..8] == [188, 188, 188, 255, 188, 188, 188, 128]);
+}
+
+#[test]
+fn heat_map_saved() {
+    let mut film = Film::new(2, 1, &[]);
+    let file = tempfile::Builder::new().suffix(".pfm").tempfile().unwrap();
+    let fname = file.path().to_str().unwrap();
+    assert!(film.save_heat_map(fname).is_err());
+
+    film.set_heat_map(HeatMap::Traversal);
+    let mut tile = FilmTile::new(IVec2::ZERO, film.resolution, Filter::Box(0.5), &[]);
+    tile.add_cost(IVec2::new(1, 0), 3.0);
+    tile.add_cost(IVec2::new(1, 0), 5.0);
+    film.add_tile(tile);
+    film.save_heat_map(fname).unwrap();
+
+    let bytes = read_bytes(&file);
+    let floats: Vec<f32> = bytes[bytes.len() - 24..].chunks(4)
+        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+        .collect();
+    assert!(floats == [0.0, 0.0, 0.0, 8.0, 8.0, 8.0]);
+}
@@ -0,0 +1,122 @@
+use crate::{Float, Vec3};
+use crate::tracer::{color::D50, ColorSpace, Transfer};
+
+/// Entries in tabulated tone curves
+const CURVE_ENTRIES: usize = 1024;
+/// Size of the profile header in bytes
+const HEADER_SIZE: usize = 128;
+
+/// ICC v2 display profile of RGB values in `space` encoded with `transfer`.
+/// Embedded in .png files encoded other than sRGB, as many viewers ignore
+/// the cHRM and gAMA chunks.
+pub fn profile(space: ColorSpace, transfer: Transfer) -> Vec<u8> {
+    let rgb_to_xyz = space.rgb_to_xyz_d50();
+    let trc = curve(transfer);
+    let tags = [
+        (b"desc", description(&format!("lumo {:?}", space))),
+        (b"cprt", text("No copyright, use freely")),
+        (b"wtpt", xyz(D50)),
+        (b"rXYZ", xyz(rgb_to_xyz.x_axis)),
+        (b"gXYZ", xyz(rgb_to_xyz.y_axis)),
+        (b"bXYZ", xyz(rgb_to_xyz.z_axis)),
+        (b"rTRC", trc.clone()),
+        (b"gTRC", trc.clone()),
+        (b"bTRC", trc),
+    ];
+
+    let mut table = Vec::new();
+    let mut data = Vec::new();
+    let data_start = HEADER_SIZE + 4 + 12 * tags.len();
+    table.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    for (signature, bytes) in &tags {
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&((data_start + data.len()) as u32).to_be_bytes());
+        table.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        data.extend_from_slice(bytes);
+        // tag data starts at four byte boundaries
+        data.resize((data.len() + 3) & !3, 0);
+    }
+
+    let size = data_start + data.len();
+    let mut profile = header(size as u32);
+    profile.extend(table);
+    profile.extend(data);
+    profile
+}
+
+/// Profile header of a display profile with RGB data and CIE XYZ as the
+/// profile connection space
+fn header(size: u32) -> Vec<u8> {
+    let mut header = vec![0; HEADER_SIZE];
+    header[0..4].copy_from_slice(&size.to_be_bytes());
+    // version 2.1
+    header[8..12].copy_from_slice(&0x0210_0000_u32.to_be_bytes());
+    header[12..16].copy_from_slice(b"mntr");
+    header[16..20].copy_from_slice(b"RGB ");
+    header[20..24].copy_from_slice(b"XYZ ");
+    header[36..40].copy_from_slice(b"acsp");
+    header[68..80].copy_from_slice(&xyz_number(D50));
+    header
+}
+
+/// `s15Fixed16Number` encoding of `v`
+fn s15_fixed16(v: Float) -> [u8; 4] {
+    ((v * 65536.0).round() as i32).to_be_bytes()
+}
+
+/// `XYZNumber` encoding of `v`
+fn xyz_number(v: Vec3) -> Vec<u8> {
+    v.to_array().iter().flat_map(|c| s15_fixed16(*c)).collect()
+}
+
+/// `XYZType` tag of `v`
+fn xyz(v: Vec3) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    tag.extend(xyz_number(v));
+    tag
+}
+
+/// `curveType` tag that decodes values encoded with `transfer`
+fn curve(transfer: Transfer) -> Vec<u8> {
+    let mut tag = b"curv\0\0\0\0".to_vec();
+    match transfer {
+        // empty curve is the identity
+        Transfer::Linear => tag.extend_from_slice(&0_u32.to_be_bytes()),
+        Transfer::Gamma(gamma) => {
+            tag.extend_from_slice(&1_u32.to_be_bytes());
+            // u8Fixed8Number
+            tag.extend_from_slice(&((gamma * 256.0).round() as u16).to_be_bytes());
+        }
+        Transfer::Srgb => {
+            tag.extend_from_slice(&(CURVE_ENTRIES as u32).to_be_bytes());
+            for i in 0..CURVE_ENTRIES {
+                let x = i as Float / (CURVE_ENTRIES - 1) as Float;
+                let y = (transfer.decode(x) * 65535.0).round() as u16;
+                tag.extend_from_slice(&y.to_be_bytes());
+            }
+        }
+    }
+    tag
+}
+
+/// `textType` tag of ASCII `s`
+fn text(s: &str) -> Vec<u8> {
+    let mut tag = b"text\0\0\0\0".to_vec();
+    tag.extend_from_slice(s.as_bytes());
+    tag.push(0);
+    tag
+}
+
+/// `textDescriptionType` tag of ASCII `s` without the Unicode and
+/// ScriptCode descriptions
+fn description(s: &str) -> Vec<u8> {
+    let mut tag = b"desc\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(s.len() as u32 + 1).to_be_bytes());
+    tag.extend_from_slice(s.as_bytes());
+    tag.push(0);
+    // Unicode language code and length
+    tag.extend_from_slice(&[0; 8]);
+    // ScriptCode code, length and the fixed 67 byte description
+    tag.extend_from_slice(&[0; 3 + 67]);
+    tag
+}
@@ -0,0 +1,23 @@
+use crate::tracer::Color;
+use glam::IVec2;
+use std::{fs::File, io::{self, BufWriter, Write}};
+
+/// Writes `pixels`, given row by row starting from the top left corner,
+/// to a little endian RGB .pfm file at `fname`
+pub fn write(fname: &str, resolution: IVec2, pixels: &[Color]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(fname)?);
+    // negative scale means little endian
+    write!(writer, "PF\n{} {}\n-1.0\n", resolution.x, resolution.y)?;
+
+    // .pfm stores scanlines from bottom to top
+    for y in (0..resolution.y).rev() {
+        for x in 0..resolution.x {
+            let rgb = pixels[(x + y * resolution.x) as usize].rgb;
+            writer.write_all(&(rgb.x as f32).to_le_bytes())?;
+            writer.write_all(&(rgb.y as f32).to_le_bytes())?;
+            writer.write_all(&(rgb.z as f32).to_le_bytes())?;
+        }
+    }
+
+    writer.flush()
+}
@@ -1,33 +1,184 @@
 use crate::{Vec2, Float};
 
-/// Filters used to construct a pixel from samples
+#[cfg(test)]
+mod filter_tests;
+
+/// Number of bins per axis in the tabulated distribution of filter samplers
+const SAMPLER_BINS: usize = 64;
+/// Number of steps in the numerical integration of the filters
+const INTEGRATION_STEPS: usize = 256;
+
+/// Filters used to construct a pixel from samples. Each filter has a radius
+/// in pixels and samples get splatted to all pixels with their center
+/// inside the radius. All of the filters are separable.
 #[derive(Clone, Copy)]
 pub enum Filter {
-    /// Constant box filter
-    Box,
-    /// Triangle filter
-    Triangle,
-    /// Gaussian filter with `alpha`
-    Gaussian(Float),
+    /// Constant box filter with `radius`. Radius of `0.5` covers one pixel.
+    Box(Float),
+    /// Triangle filter with `radius`
+    Triangle(Float),
+    /// Gaussian filter with `radius` and falloff `alpha`
+    Gaussian(Float, Float),
+    /// Mitchell-Netravali filter with `radius` and parameters `b` and `c`.
+    /// `b = c = 1/3` recommended.
+    Mitchell(Float, Float, Float),
+    /// Lanczos windowed sinc filter with `radius` and window width `tau`
+    LanczosSinc(Float, Float),
+    /// Blackman-Harris window with `radius`
+    BlackmanHarris(Float),
 }
 
 impl Filter {
-    /// Evaluate the filter at `px` which is in `[0.0, 1.0] x [0.0, 1.0]`
+    /// Box filter covering one pixel
+    pub fn box_filter() -> Self {
+        Self::Box(0.5)
+    }
+
+    /// Triangle filter covering one pixel
+    pub fn triangle() -> Self {
+        Self::Triangle(0.5)
+    }
+
+    /// Gaussian filter covering one pixel with falloff `alpha`
+    pub fn gaussian(alpha: Float) -> Self {
+        Self::Gaussian(0.5, alpha)
+    }
+
+    /// Radius of the filter in pixels
+    pub fn radius(&self) -> Float {
+        match self {
+            Self::Box(r)
+                | Self::Triangle(r)
+                | Self::Gaussian(r, _)
+                | Self::Mitchell(r, _, _)
+                | Self::LanczosSinc(r, _)
+                | Self::BlackmanHarris(r) => *r,
+        }
+    }
+
+    /// Evaluate the filter at offset `px` from the center of the pixel.
+    /// Zero outside of the radius.
     pub fn eval(&self, px: Vec2) -> Float {
+        self.eval_1d(px.x) * self.eval_1d(px.y)
+    }
+
+    /// Evaluate one dimension of the filter at offset `x`
+    fn eval_1d(&self, x: Float) -> Float {
+        let radius = self.radius();
+        let x = x.abs();
+        if x > radius {
+            return 0.0;
+        }
+
         match self {
-            Filter::Box => 1.0,
-            Filter::Triangle => {
-                let offset = (1.0 - px.abs()).max(Vec2::ZERO);
-                offset.x * offset.y
+            Self::Box(_) => 1.0,
+            Self::Triangle(_) => radius - x,
+            Self::Gaussian(_, alpha) => {
+                let t = x / radius;
+                ((-alpha * t * t).exp() - (-alpha).exp()).max(0.0)
             }
-            Filter::Gaussian(alpha) => {
-                let exp = (-alpha).exp();
-                let gauss = |x: Float| -> Float {
-                    ((-alpha * x * x).exp() - exp).max(0.0)
+            Self::Mitchell(_, b, c) => {
+                // defined in [0,2]
+                let x = 2.0 * x / radius;
+                let (b, c) = (*b, *c);
+                let poly = if x > 1.0 {
+                    (-b - 6.0 * c) * x * x * x
+                        + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c)
+                } else {
+                    (12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b)
                 };
-
-                gauss(px.x) * gauss(px.y)
+                poly / 6.0
             }
+            Self::LanczosSinc(_, tau) => sinc(x) * sinc(x / tau),
+            Self::BlackmanHarris(_) => {
+                let t = 2.0 * crate::PI * (x / (2.0 * radius) + 0.5);
+                0.35875 - 0.48829 * t.cos()
+                    + 0.14128 * (2.0 * t).cos()
+                    - 0.01168 * (3.0 * t).cos()
+            }
+        }
+    }
+
+    /// Integral of the filter over its support
+    pub fn integral(&self) -> Float {
+        let radius = self.radius();
+        let dx = 2.0 * radius / INTEGRATION_STEPS as Float;
+        let integral_1d = (0..INTEGRATION_STEPS)
+            .map(|i| self.eval_1d(-radius + (i as Float + 0.5) * dx))
+            .sum::<Float>() * dx;
+
+        integral_1d * integral_1d
+    }
+}
+
+/// Normalized sinc function
+fn sinc(x: Float) -> Float {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        let pix = crate::PI * x;
+        pix.sin() / pix
+    }
+}
+
+/// Importance samples offsets from the absolute value of a filter using
+/// a tabulated distribution. Samples get weighted by the value of the
+/// filter divided by the PDF.
+pub struct FilterSampler {
+    filter: Filter,
+    /// Cumulative distribution of the bins of one dimension
+    cdf: Vec<Float>,
+}
+
+impl FilterSampler {
+    /// Tabulates the distribution of `filter`
+    pub fn new(filter: Filter) -> Self {
+        let radius = filter.radius();
+        let dx = 2.0 * radius / SAMPLER_BINS as Float;
+        let mut cdf = vec![0.0; SAMPLER_BINS + 1];
+        for i in 0..SAMPLER_BINS {
+            // average of the bin, so that bins with non-zero
+            // values near the edges get sampled
+            let value = (0..8)
+                .map(|j| {
+                    let x = -radius + (i as Float + (j as Float + 0.5) / 8.0) * dx;
+                    filter.eval_1d(x).abs()
+                })
+                .sum::<Float>() / 8.0;
+            cdf[i + 1] = cdf[i] + value.max(1e-5);
         }
+
+        let total = cdf[SAMPLER_BINS];
+        cdf.iter_mut().for_each(|c| *c /= total);
+
+        Self { filter, cdf }
+    }
+
+    /// Samples a point and its PDF in one dimension
+    fn sample_1d(&self, u: Float) -> (Float, Float) {
+        let radius = self.filter.radius();
+        let bin = self.cdf.partition_point(|c| *c <= u)
+            .clamp(1, SAMPLER_BINS) - 1;
+        let width = self.cdf[bin + 1] - self.cdf[bin];
+        let t = (u - self.cdf[bin]) / width;
+        let dx = 2.0 * radius / SAMPLER_BINS as Float;
+
+        let x = -radius + (bin as Float + t) * dx;
+        let pdf = width / dx;
+        (x, pdf)
+    }
+
+    /// Samples an offset from the center of the pixel using `rand_sq`.
+    /// Returns the offset and the weight of the sample.
+    pub fn sample(&self, rand_sq: Vec2) -> (Vec2, Float) {
+        let (x, pdf_x) = self.sample_1d(rand_sq.x);
+        let (y, pdf_y) = self.sample_1d(rand_sq.y);
+        let offset = Vec2::new(x, y);
+
+        (offset, self.filter.eval(offset) / (pdf_x * pdf_y))
     }
 }
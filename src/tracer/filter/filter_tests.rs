@@ -0,0 +1,63 @@
+use super::*;
+
+const FILTERS: [Filter; 6] = [
+    Filter::Box(0.5),
+    Filter::Triangle(1.5),
+    Filter::Gaussian(1.5, 2.0),
+    Filter::Mitchell(2.0, 1.0 / 3.0, 1.0 / 3.0),
+    Filter::LanczosSinc(2.0, 2.0),
+    Filter::BlackmanHarris(1.5),
+];
+
+#[test]
+fn zero_outside_radius() {
+    for filter in FILTERS {
+        let r = filter.radius();
+        assert!(filter.eval(Vec2::ZERO) > 0.0);
+        assert!(filter.eval(Vec2::new(r + 1e-3, 0.0)) == 0.0);
+        assert!(filter.eval(Vec2::new(0.0, -r - 1e-3)) == 0.0);
+    }
+}
+
+#[test]
+fn symmetric() {
+    for filter in FILTERS {
+        let px = Vec2::new(0.3, 0.7);
+        assert!((filter.eval(px) - filter.eval(-px)).abs() < 1e-10);
+    }
+}
+
+#[test]
+fn box_and_triangle_integrals() {
+    assert!((Filter::Box(1.0).integral() - 4.0).abs() < 1e-6);
+    assert!((Filter::Triangle(1.0).integral() - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn sampler_weights_average_to_integral() {
+    let n = 512;
+    for filter in FILTERS {
+        let sampler = FilterSampler::new(filter);
+        let mut sum = 0.0;
+        for i in 0..n {
+            for j in 0..n {
+                let u = Vec2::new(i as Float + 0.5, j as Float + 0.5) / n as Float;
+                let (offset, weight) = sampler.sample(u);
+                assert!(offset.abs().max_element() <= filter.radius());
+                sum += weight;
+            }
+        }
+        let mean = sum / (n * n) as Float;
+        let integral = filter.integral();
+        assert!((mean - integral).abs() < 1e-2 * integral.abs());
+    }
+}
+
+#[test]
+fn constructors_cover_one_pixel() {
+    for filter in [Filter::box_filter(), Filter::triangle(), Filter::gaussian(2.0)] {
+        assert!(filter.radius() == 0.5);
+        assert!(filter.eval(Vec2::ZERO) > 0.0);
+        assert!(filter.eval(Vec2::new(0.6, 0.0)) == 0.0);
+    }
+}
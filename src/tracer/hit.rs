@@ -1,5 +1,7 @@
 use crate::{ Point, Float, Direction, Normal, efloat, Vec2, Vec3 };
-use crate::tracer::{ material::Material, object::Sampleable, ray::Ray };
+use crate::tracer::{
+    material::Material, object::Sampleable, ray::Ray, ColorSpace, SampledWavelengths
+};
 
 /// Stores information about a hit between a ray and an object
 pub struct Hit<'a> {
@@ -21,6 +23,14 @@ pub struct Hit<'a> {
     pub uv: Vec2,
     /// Are we on the backface?
     pub backface: bool,
+    /// Index of the object in the scene that got hit. Set by the scene.
+    pub object_id: Option<usize>,
+    /// Wavelengths of the path in spectral mode. Set by the scene.
+    pub lambda: Option<SampledWavelengths>,
+    /// Working space of the path in RGB mode. Set by the scene.
+    pub space: ColorSpace,
+    /// Time of the path within the shutter interval. Set by the scene.
+    pub time: Float,
 }
 
 impl<'a> Hit<'a> {
@@ -56,6 +66,10 @@ impl<'a> Hit<'a> {
             ns,
             ng,
             uv,
+            object_id: None,
+            lambda: None,
+            space: ColorSpace::Srgb,
+            time: 0.0,
         })
     }
 
@@ -88,10 +102,14 @@ impl<'a> Hit<'a> {
             move_double(xi.z, offset.z),
         );
 
-        Ray::new(
+        let mut ray = Ray::new(
             xi,
             wi
-        )
+        );
+        ray.lambda = self.lambda;
+        ray.space = self.space;
+        ray.time = self.time;
+        ray
     }
 
     /// Did we hit a medium?
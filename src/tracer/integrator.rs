@@ -1,11 +1,12 @@
 use crate::{
-    Transport, rand_utils, Vec2, Float,
-    Normal, Point, Direction, Vec3
+    Transport, Vec2, Float,
+    Normal, Point, Direction, Vec3,
+    samplers::Sampler, stats::{self, Counter}
 };
 use crate::tracer::{
-    camera::Camera, film::FilmSample, hit::Hit,
+    camera::Camera, film::{Aov, FilmSample}, hit::Hit,
     object::Sampleable,
-    ray::Ray, scene::Scene, Color
+    ray::Ray, scene::Scene, Color, ColorSpace, SampledWavelengths
 };
 use std::fmt;
 
@@ -13,6 +14,9 @@ mod bd_path_trace;
 mod direct_light;
 mod path_trace;
 
+#[cfg(test)]
+mod integrator_tests;
+
 /// Enum to choose which integrator to use
 pub enum Integrator {
     /// Implements the path tracing algorithm with
@@ -37,38 +41,69 @@ impl fmt::Display for Integrator {
 }
 
 impl Integrator {
-    /// Calls the corresponding integration function
-    pub fn integrate(&self, s: &Scene, c: &Camera, raster_xy: Vec2, r: Ray) -> Vec<FilmSample> {
+    /// Calls the corresponding integration function. Random values
+    /// get drawn from the dimensions of `sampler`. AOVs get written to
+    /// the samples only if `aovs` is set.
+    pub fn integrate(
+        &self,
+        s: &Scene,
+        c: &Camera,
+        raster_xy: Vec2,
+        r: Ray,
+        aovs: bool,
+        sampler: &mut dyn Sampler,
+    ) -> Vec<FilmSample> {
         match self {
-            Self::PathTrace => vec![path_trace::integrate(s, r, raster_xy)],
-            Self::DirectLight => vec![direct_light::integrate(s, r, raster_xy)],
-            Self::BDPathTrace => bd_path_trace::integrate(s, c, r, raster_xy),
+            Self::PathTrace => vec![path_trace::integrate(s, c, r, raster_xy, aovs, sampler)],
+            Self::DirectLight => vec![direct_light::integrate(s, c, r, raster_xy, aovs, sampler)],
+            Self::BDPathTrace => bd_path_trace::integrate(s, c, r, raster_xy, aovs, sampler),
         }
     }
 }
 
+/// Marks `sample` as covered and writes the AOVs describing the first
+/// hit `ho` of a camera ray to it if `aovs` is set
+fn add_first_hit_aovs(sample: &mut FilmSample, camera: &Camera, ho: &Hit, aovs: bool) {
+    sample.alpha = 1.0;
+    if !aovs {
+        return;
+    }
+
+    let id = ho.object_id.map_or(0.0, |i| (i + 1) as Float);
+
+    sample.set_aov(Aov::Albedo, ho.material.albedo_at(ho));
+    sample.set_aov(Aov::Normal, Color::from(ho.ns));
+    sample.set_aov(Aov::Depth, Color::splat(camera.depth(ho.p, ho.time)));
+    sample.set_aov(Aov::Position, Color::from(ho.p));
+    sample.set_aov(Aov::ObjectId, Color::splat(id));
+}
+
 /// Shoots a shadow ray towards random light from `ho`. MIS with `pdf_scatter`.
 fn shadow_ray(
     scene: &Scene,
     ro: &Ray,
     ho: &Hit,
-    rand_sq0: Vec2,
-    rand_sq1: Vec2,
+    sampler: &mut dyn Sampler,
 ) -> Color {
     let material = ho.material;
     let xo = ho.p;
     let wo = ro.dir;
     let ns = ho.ns;
 
-    let light = scene.uniform_random_light();
+    let light = scene.uniform_random_light(sampler.get_1d());
+    let rand_sq0 = sampler.get_2d();
+    let rand_sq1 = sampler.get_2d();
+    let rand_u = sampler.get_1d();
 
     let mut radiance = Color::BLACK;
 
     // refactor these to separate function?
     // sample light first
     radiance += {
-        let wi = light.sample_towards(xo, rand_sq0);
-        let ri = ho.generate_ray(wi);
+        let ri = ho.generate_ray(light.sample_towards(xo, rand_sq0, ho.time));
+        // normalized
+        let wi = ri.dir;
+        stats::count(Counter::ShadowRays, 1);
         match scene.hit_light(&ri, light) {
             None => Color::BLACK,
             Some(hi) => {
@@ -89,7 +124,7 @@ fn shadow_ray(
                 };
 
                 bsdf
-                    * scene.transmittance(hi.t)
+                    * scene.transmittance(hi.t, hi.lambda, hi.space)
                     * hi.material.emit(&hi)
                     * material.shading_cosine(wi, ns)
                     * weight
@@ -99,10 +134,11 @@ fn shadow_ray(
     };
 
     // then sample BSDF
-    radiance += match material.bsdf_sample(wo, ho, rand_sq1) {
+    radiance += match material.bsdf_sample(wo, ho, rand_u, rand_sq1) {
         None => Color::BLACK,
         Some(wi) => {
             let ri = ho.generate_ray(wi);
+            stats::count(Counter::ShadowRays, 1);
             match scene.hit_light(&ri, light) {
                 None => Color::BLACK,
                 Some(hi) => {
@@ -123,7 +159,7 @@ fn shadow_ray(
                     };
 
                     bsdf
-                        * scene.transmittance(hi.t)
+                        * scene.transmittance(hi.t, hi.lambda, hi.space)
                         * hi.material.emit(&hi)
                         * material.shading_cosine(wi, ns)
                         * weight
@@ -17,30 +17,62 @@ mod mis;
 /// Helpers to sample objects
 mod pdf;
 
-pub fn integrate(scene: &Scene, camera: &Camera, r: Ray, raster_xy: Vec2) -> Vec<FilmSample> {
-    let light_path = path_gen::light_path(scene);
-    let camera_path = path_gen::camera_path(scene, camera, r);
+pub fn integrate(
+    scene: &Scene,
+    camera: &Camera,
+    r: Ray,
+    raster_xy: Vec2,
+    aovs: bool,
+    sampler: &mut dyn Sampler,
+) -> Vec<FilmSample> {
+    let lambda = r.lambda;
+    let space = r.space;
+    let time = r.time;
+    let camera_path = path_gen::camera_path(scene, camera, r, sampler);
+    let light_path = path_gen::light_path(scene, lambda, space, time, sampler);
+    stats::count(Counter::PathVertices, camera_path.len().saturating_sub(1) as u64);
 
-    let mut radiance = Color::BLACK;
+    let mut direct = Color::BLACK;
+    let mut indirect = Color::BLACK;
     let mut samples = vec![];
 
-    for s in 2..=light_path.len() {
-        if let Some(sample) = connect_light_path(scene, camera, &camera_path, &light_path, s) {
+    let light_tracing = if camera.supports_light_tracing() { light_path.len() } else { 1 };
+    for s in 2..=light_tracing {
+        stats::count(Counter::Connections, 1);
+        if let Some(sample) = connect_light_path(
+            scene, camera, &camera_path, &light_path, s, sampler
+        ) {
             samples.push(sample);
         }
     }
 
     for t in 2..=camera_path.len() {
         for s in 0..=light_path.len() {
-            radiance += connect_paths(
+            stats::count(Counter::Connections, 1);
+            let radiance = connect_paths(
                 scene, camera,
                 &light_path, s,
                 &camera_path, t,
+                sampler,
             );
+            // camera, first hit and light
+            if s + t <= 3 {
+                direct += radiance;
+            } else {
+                indirect += radiance;
+            }
         }
     }
 
-    samples.push(FilmSample::new(radiance, raster_xy, false));
+    let mut sample = FilmSample::new(direct + indirect, raster_xy, false);
+    if let Some(first_hit) = camera_path.get(1) {
+        add_first_hit_aovs(&mut sample, camera, &first_hit.h, aovs);
+    }
+    if aovs {
+        sample.set_aov(Aov::Direct, direct);
+        sample.set_aov(Aov::Indirect, indirect);
+    }
+    samples.push(sample);
     samples
 }
 
@@ -50,14 +82,20 @@ fn connect_light_path(
     camera: &Camera,
     camera_path: &[Vertex],
     light_path: &[Vertex],
-    s: usize
+    s: usize,
+    sampler: &mut dyn Sampler,
 ) -> Option<FilmSample> {
     // assert!(s >= 2);
+    let rand_sq = sampler.get_2d();
 
     // sample direction
     let light_last = &light_path[s - 1];
+    // delta materials scatter towards the camera with zero probability
+    if light_last.is_delta() {
+        return None;
+    }
     let xi = light_last.h.p;
-    let ro = camera.sample_towards(xi, rand_utils::unit_square());
+    let ro = camera.sample_towards(xi, rand_sq, light_last.h.time);
     let pdf = camera.sample_towards_pdf(&ro, xi);
     if pdf == 0.0 {
         return None;
@@ -68,7 +106,7 @@ fn connect_light_path(
     let v = -ro.dir;
     let vr = light_last.h.generate_ray(v);
     let t2 = xo.distance_squared(xi);
-    if scene.hit(&vr).is_some_and(|h: Hit| h.t * h.t < t2 - crate::EPSILON) {
+    if scene.hit(&vr, sampler).is_some_and(|h: Hit| h.t * h.t < t2 - crate::EPSILON) {
         return None;
     }
 
@@ -87,12 +125,16 @@ fn connect_light_path(
         let wi = (xn - xi).normalize();
         let ns = light_last.h.ns;
         let ng = light_last.h.ng;
-        wi.dot(ng).abs() * light_last.shading_cosine(v, ns)
-            / v.dot(ng).abs()
+        v.dot(ng).abs() * light_last.shading_cosine(wi, ns)
+            / wi.dot(ng).abs()
     };
+    // grazing connections carry no light
+    if shading_cosine == 0.0 {
+        return None;
+    }
 
     sample.color *= light_last.gathered
-        * scene.transmittance(t2.sqrt())
+        * scene.transmittance(t2.sqrt(), light_last.h.lambda, light_last.h.space)
         * shading_cosine
         * light_last.bsdf(camera_last, Transport::Importance)
         * mis::mis_weight(camera, light_path, s, camera_path, 1, sampled_vertex);
@@ -102,7 +144,7 @@ fn connect_light_path(
 
 /// Connects a light subpath and a camera subpath.
 /// Camera sampling not implemented i.e. camera paths of length 0 or 1 discarded.
-/// Special logic if light path length 0 or 1.
+/// Special logic if light path length 0 or 1, `sampler` used to sample the light.
 fn connect_paths(
     scene: &Scene,
     camera: &Camera,
@@ -110,8 +152,10 @@ fn connect_paths(
     s: usize,
     camera_path: &[Vertex],
     t: usize,
+    sampler: &mut dyn Sampler,
 ) -> Color {
     // assert!(t >= 2);
+    let rand_sq = sampler.get_2d();
 
     // camera path ends on a light, but light path not empty
     if s != 0 && camera_path[t - 1].is_light() {
@@ -141,16 +185,18 @@ fn connect_paths(
             let light = light_path[0].h.light.unwrap();
 
             let xo = camera_last.h.p;
-            let pdf_light = ObjectPdf::new(light, xo);
+            let pdf_light = ObjectPdf::new(light, xo, camera_last.h.time);
 
-            match pdf_light.sample_direction(rand_utils::unit_square()) {
+            match pdf_light.sample_direction(rand_sq) {
                 None => Color::BLACK,
                 Some(wi) => {
                     let ri = camera_last.h.generate_ray(wi);
+                    // normalized
+                    let wi = ri.dir;
                     match scene.hit_light(&ri, light) {
                         None => Color::BLACK,
                         Some(hi) => {
-                            let ns = hi.ns;
+                            let ns = camera_last.h.ns;
                             let emittance = hi.material.emit(&hi)
                                 / pdf_light.value_for(&ri, false);
                             sampled_vertex = Some(Vertex::light(
@@ -169,7 +215,11 @@ fn connect_paths(
                                 * bsdf
                                 * light_last.gathered
                                 * camera_last.shading_cosine(wi, ns)
-                                * scene.transmittance(light_last.h.t)
+                                * scene.transmittance(
+                                    light_last.h.t,
+                                    light_last.h.lambda,
+                                    light_last.h.space,
+                                )
                         }
                     }
                 }
@@ -184,7 +234,7 @@ fn connect_paths(
 
         if camera_last.is_delta()
             || light_last.is_delta()
-            || !visible(scene, &light_last.h, &camera_last.h) {
+            || !visible(scene, &light_last.h, &camera_last.h, sampler) {
                 Color::BLACK
             } else {
                 let light_bsdf = light_last.bsdf(
@@ -196,12 +246,19 @@ fn connect_paths(
                     Transport::Radiance,
                 );
 
-                light_last.gathered
+                // transmittance baked in to G
+                let radiance = light_last.gathered
                     * light_bsdf
                     * camera_bsdf
                     * camera_last.gathered
-                    * light_last.g(camera_last, scene)
-                    // transmittance baked in to G
+                    * light_last.g(camera_last, scene);
+
+                match (&light_last.h.lambda, &camera_last.h.lambda) {
+                    (Some(light_lambda), Some(camera_lambda)) => {
+                        light_lambda.join(camera_lambda, radiance)
+                    }
+                    _ => radiance,
+                }
         }
     };
 
@@ -214,8 +271,8 @@ fn connect_paths(
     radiance * weight
 }
 
-/// Is `h1` visible from `h2`?
-fn visible(s: &Scene, h1: &Hit, h2: &Hit) -> bool {
+/// Is `h1` visible from `h2`? Mediums get sampled with `sampler`.
+fn visible(s: &Scene, h1: &Hit, h2: &Hit, sampler: &mut dyn Sampler) -> bool {
     let xo = h1.p;
     let xi = h2.p;
     let r = h1.generate_ray(xi - xo);
@@ -225,7 +282,7 @@ fn visible(s: &Scene, h1: &Hit, h2: &Hit) -> bool {
         return false;
     }
 
-    match s.hit(&r) {
+    match s.hit(&r, sampler) {
         None => false,
         Some(h) => h.p.distance_squared(xi) < crate::EPSILON,
     }
@@ -30,6 +30,11 @@ pub fn mis_weight(
         (&light_path[s.max(1) - 1], &camera_path[t - 1])
     };
 
+    // strategies with `t_new` camera vertices. The camera can not be hit
+    // by rays, so there is no strategy without camera vertices, and light
+    // tracing needs support from the camera.
+    let valid = |t_new: usize| t_new > 1 || (t_new == 1 && camera.supports_light_tracing());
+
     let mut sum_ri = 0.0;
     let mut ri = 1.0;
 
@@ -44,7 +49,9 @@ pub fn mis_weight(
         };
 
         ri *= map0(pdf_prev) / map0(ct.pdf_fwd);
-        sum_ri += ri;
+        if valid(t - 1) && !ct.is_delta() && !camera_path[t - 2].is_delta() {
+            sum_ri += ri;
+        }
     }
 
     // applies the updated PDF for camera t - 2 using the connection
@@ -56,7 +63,7 @@ pub fn mis_weight(
             ct.pdf_area(ct_m, Transport::Importance)
         };
         ri *= map0(pdf_prev) / map0(ct_m.pdf_fwd);
-        if !ct_m.is_delta() {
+        if valid(t - 2) && !ct_m.is_delta() && !camera_path[t - 3].is_delta() {
             sum_ri += ri;
         }
     }
@@ -64,7 +71,7 @@ pub fn mis_weight(
     // vertices in camera path
     for i in (1..t.max(2) - 2).rev() {
         ri *= map0(camera_path[i].pdf_bck) / map0(camera_path[i].pdf_fwd);
-        if !camera_path[i].is_delta() && !camera_path[i - 1].is_delta() {
+        if !camera_path[i].is_delta() && !camera_path[i - 1].is_delta() && valid(i) {
             sum_ri += ri;
         }
     }
@@ -74,16 +81,15 @@ pub fn mis_weight(
     // applies the updated PDF at light_last using the connection
     if s > 0 {
         let pdf_prev = if t == 1 {
-            // move this to vertex
-            let xo = ct.h.p;
-            let xi = ls.h.p;
-            let wi = (xi - xo).normalize();
-            camera.pdf_wi(wi)
+            let ni = ls.is_surface().then_some(ls.h.ng);
+            camera.pdf_xi(ct.h.p, ls.h.p, ni, ls.h.time)
         } else {
             ct.pdf_area(ls, Transport::Radiance)
         };
         ri *= map0(pdf_prev) / map0(ls.pdf_fwd);
-        sum_ri += ri;
+        if !ls.is_delta() && (s < 2 || !light_path[s - 2].is_delta()) {
+            sum_ri += ri;
+        }
     }
 
     // applies the updated PDF at light_last using the connection
@@ -92,7 +98,7 @@ pub fn mis_weight(
         let pdf_prev = ls.pdf_area(ls_m, Transport::Radiance);
 
         ri *= map0(pdf_prev) / map0(ls_m.pdf_fwd);
-        if !ls_m.is_delta() {
+        if !ls_m.is_delta() && (s < 3 || !light_path[s - 3].is_delta()) {
             sum_ri += ri;
         }
     }
@@ -108,7 +114,7 @@ pub fn mis_weight(
     let weight = 1.0 / (1.0 + sum_ri);
     #[cfg(debug_assertions)]
     if weight < 0.0 {
-        println!("negative weight in BDPT MIS");
+        crate::logging::log_warn!("negative weight in BDPT MIS");
     }
     weight
 }
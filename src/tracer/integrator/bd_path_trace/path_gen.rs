@@ -1,23 +1,55 @@
 use super::*;
 
 /// Generates a ray path starting from the camera
-pub fn camera_path<'a>(scene: &'a Scene, camera: &'a Camera, r: Ray) -> Vec<Vertex<'a>> {
+pub fn camera_path<'a>(
+    scene: &'a Scene,
+    camera: &'a Camera,
+    r: Ray,
+    sampler: &mut dyn Sampler,
+) -> Vec<Vertex<'a>> {
     let gathered = Color::WHITE;
-    let root = Vertex::camera(r.origin, gathered);
+    let xo = r.origin;
+    let root = Vertex::camera(xo, gathered);
     let wi = r.dir;
-    let pdf_fwd = camera.pdf_wi(wi);
+    let time = r.time;
+    let pdf_fwd = camera.pdf_wi(wi, time);
+
+    let mut path = walk(scene, r, root, gathered, pdf_fwd, Transport::Radiance, sampler);
+    // orthographic cameras sample the first vertex by its position,
+    // so let the camera give its density directly
+    if let Some(first) = path.get_mut(1) {
+        if !first.is_delta() {
+            let ni = first.is_surface().then_some(first.h.ng);
+            first.pdf_fwd = camera.pdf_xi(xo, first.h.p, ni, time);
+        }
+    }
 
-    walk(scene, r, root, gathered, pdf_fwd, Transport::Radiance)
+    path
 }
 
-/// Generates a ray path strating from a light
-pub fn light_path(scene: &Scene) -> Vec<Vertex> {
-    let light = scene.uniform_random_light();
+/// Generates a ray path strating from a light. The path carries the
+/// wavelengths `lambda` of the camera path in spectral mode, its working
+/// `space` and its `time`.
+pub fn light_path<'a>(
+    scene: &'a Scene,
+    lambda: Option<SampledWavelengths>,
+    space: ColorSpace,
+    time: Float,
+    sampler: &mut dyn Sampler,
+) -> Vec<Vertex<'a>> {
+    let light = scene.uniform_random_light(sampler.get_1d());
     let pdf_light = 1.0 / scene.num_lights() as Float;
-    let (ro, ho) = light.sample_leaving(
-        rand_utils::unit_square(),
-        rand_utils::unit_square()
+    let (mut ro, mut ho) = light.sample_leaving(
+        sampler.get_2d(),
+        sampler.get_2d(),
+        time,
     );
+    ro.lambda = lambda;
+    ho.lambda = lambda;
+    ro.space = space;
+    ho.space = space;
+    ro.time = time;
+    ho.time = time;
     let ng = ho.ng;
     let ns = ho.ns;
     let (pdf_origin, pdf_dir) = light.sample_leaving_pdf(&ro, ng);
@@ -27,7 +59,7 @@ pub fn light_path(scene: &Scene) -> Vec<Vertex> {
     let gathered = emit * ns.dot(ro.dir).abs()
         / (pdf_light * pdf_origin * pdf_dir);
 
-    walk(scene, ro, root, gathered, pdf_dir, Transport::Importance)
+    walk(scene, ro, root, gathered, pdf_dir, Transport::Importance, sampler)
 }
 
 /// Ray that randomly scatters around from the given root vertex
@@ -38,14 +70,15 @@ fn walk<'a>(
     mut gathered: Color,
     pdf_dir: Float,
     mode: Transport,
+    sampler: &mut dyn Sampler,
 ) -> Vec<Vertex<'a>> {
     let mut depth = 0;
     let mut vertices = vec![root];
     let mut pdf_fwd = pdf_dir;
 
-    while let Some(ho) = scene.hit(&ro) {
+    while let Some(ho) = scene.hit(&ro, sampler) {
         let material = ho.material;
-        gathered *= scene.transmittance(ho.t);
+        gathered *= scene.transmittance(ho.t, ho.lambda, ho.space);
 
         let prev = depth;
         let curr = depth + 1;
@@ -57,7 +90,7 @@ fn walk<'a>(
         ));
         let ho = &vertices[curr].h;
         let wo = ro.dir;
-        match material.bsdf_sample(wo, ho, rand_utils::unit_square()) {
+        match material.bsdf_sample(wo, ho, sampler.get_1d(), sampler.get_2d()) {
             None => {
                 // we hit a light. if tracing from a light, discard latest vertex
                 if matches!(mode, Transport::Importance) {
@@ -71,9 +104,12 @@ fn walk<'a>(
                 let ng = ho.ng;
 
                 let ns = ho.ns;
-                let ri = ho.generate_ray(wi);
+                let mut ri = ho.generate_ray(wi);
                 // normalized
                 let wi = ri.dir;
+                if material.disperses(wo, wi, ho) {
+                    ri.lambda = ri.lambda.map(|l| l.terminate_secondary());
+                }
 
                 pdf_fwd = material.bsdf_pdf(wo, wi, ho, false);
 
@@ -120,12 +156,11 @@ fn walk<'a>(
                 if depth > 3 {
                     let luminance = gathered.luminance();
                     let rr_prob = (1.0 - luminance).max(0.05);
-                    if rand_utils::rand_float() < rr_prob {
+                    if sampler.get_1d() < rr_prob {
+                        stats::count(Counter::RrTerminations, 1);
                         break;
                     }
-
-                    // TODO (9)
-                    //gathered /= 1.0 - rr_prob;
+                    gathered /= 1.0 - rr_prob;
                 }
 
                 depth += 1;
@@ -5,15 +5,17 @@ pub struct ObjectPdf<'a> {
     object: &'a dyn Sampleable,
     /// Point from where the object should be visible
     xo: Point,
+    /// Time at which the object gets sampled
+    time: Float,
 }
 
 impl<'a> ObjectPdf<'a> {
-    pub fn new(object: &'a dyn Sampleable, xo: Point) -> Self {
-        Self { object, xo }
+    pub fn new(object: &'a dyn Sampleable, xo: Point, time: Float) -> Self {
+        Self { object, xo, time }
     }
 
     pub fn sample_direction(&self, rand_sq: Vec2) -> Option<Direction> {
-        Some( self.object.sample_towards(self.xo, rand_sq) )
+        Some( self.object.sample_towards(self.xo, rand_sq, self.time) )
     }
 
     pub fn value_for(&self, ri: &Ray, _swap_dir: bool) -> Float {
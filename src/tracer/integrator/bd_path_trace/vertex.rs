@@ -145,7 +145,7 @@ impl<'a> Vertex<'a> {
             g *= ni.dot(wi).abs();
         }
 
-        g * scene.transmittance(xo.distance(xi))
+        g * scene.transmittance(xo.distance(xi), self.h.lambda, self.h.space)
     }
 
     /// PDF to sample direction to `next` from `curr` w.r.t. surface area measure
@@ -1,69 +1,98 @@
 use super::*;
 
-pub fn integrate(scene: &Scene, ro: Ray, raster_xy: Vec2) -> FilmSample {
-    let radiance = _integrate(scene, ro, 0);
-    FilmSample::new(radiance, raster_xy, false)
+pub fn integrate(
+    scene: &Scene,
+    camera: &Camera,
+    ro: Ray,
+    raster_xy: Vec2,
+    aovs: bool,
+    sampler: &mut dyn Sampler,
+) -> FilmSample {
+    let mut sample = FilmSample::new(Color::BLACK, raster_xy, false);
+
+    if let Some(ho) = scene.hit(&ro, sampler) {
+        add_first_hit_aovs(&mut sample, camera, &ho, aovs);
+        let radiance = _integrate_hit(scene, &ro, &ho, 0, sampler);
+        if aovs {
+            // specular surfaces get their radiance from the next hit
+            let aov = if ho.material.is_specular() && !ho.is_light() {
+                Aov::Indirect
+            } else {
+                Aov::Direct
+            };
+            sample.set_aov(aov, radiance);
+        }
+        sample.color = radiance;
+    }
+
+    sample
 }
 
 const MAX_RECURSION: usize = 50;
 
-fn _integrate(scene: &Scene, ro: Ray, depth: usize) -> Color {
-    match scene.hit(&ro) {
+fn _integrate(scene: &Scene, ro: Ray, depth: usize, sampler: &mut dyn Sampler) -> Color {
+    match scene.hit(&ro, sampler) {
         None => Color::BLACK,
-        Some(ho) => {
-            let material = ho.material;
-            let wo = ro.dir;
-            match material.bsdf_sample(wo, &ho, rand_utils::unit_square()) {
-                None => material.emit(&ho),
-                Some(wi) => {
-                    if !material.is_specular() {
-                        let radiance = shadow_ray(
-                            scene,
-                            &ro,
-                            &ho,
-                            rand_utils::unit_square(),
-                            rand_utils::unit_square(),
-                        );
+        Some(ho) => _integrate_hit(scene, &ro, &ho, depth, sampler),
+    }
+}
 
-                        scene.transmittance(ho.t) * radiance
-                    } else {
-                        if depth > MAX_RECURSION {
-                            return Color::BLACK;
-                        }
+fn _integrate_hit(
+    scene: &Scene,
+    ro: &Ray,
+    ho: &Hit,
+    depth: usize,
+    sampler: &mut dyn Sampler,
+) -> Color {
+    stats::count(Counter::PathVertices, 1);
+    let material = ho.material;
+    let wo = ro.dir;
+    match material.bsdf_sample(wo, ho, sampler.get_1d(), sampler.get_2d()) {
+        None => material.emit(ho),
+        Some(wi) => {
+            if !material.is_specular() {
+                let radiance = shadow_ray(scene, ro, ho, sampler);
 
-                        let ri = ho.generate_ray(wi);
-                        let wi = ri.dir;
-                        let wo = ro.dir;
+                scene.transmittance(ho.t, ho.lambda, ho.space) * radiance
+            } else {
+                if depth > MAX_RECURSION {
+                    return Color::BLACK;
+                }
 
-                        let p_scatter = material.bsdf_pdf(wo, wi, &ho, false);
-                        if p_scatter <= 0.0 {
-                            // return something better?
-                            return Color::BLACK;
-                        }
+                let mut ri = ho.generate_ray(wi);
+                let wi = ri.dir;
+                let wo = ro.dir;
+                if material.disperses(wo, wi, ho) {
+                    ri.lambda = ri.lambda.map(|l| l.terminate_secondary());
+                }
 
-                        let bsdf = material.bsdf_f(
-                            wo,
-                            wi,
-                            Transport::Radiance,
-                            &ho
-                        );
-                        let bsdf = if ho.is_medium() {
-                            // assume that mediums get sampled perfectly
-                            // according to the BSDF thus cancel out PDF
-                            bsdf * p_scatter
-                        } else {
-                            bsdf
-                        };
+                let p_scatter = material.bsdf_pdf(wo, wi, ho, false);
+                if p_scatter <= 0.0 {
+                    // return something better?
+                    return Color::BLACK;
+                }
 
-                        let ns = ho.ns;
+                let bsdf = material.bsdf_f(
+                    wo,
+                    wi,
+                    Transport::Radiance,
+                    ho
+                );
+                let bsdf = if ho.is_medium() {
+                    // assume that mediums get sampled perfectly
+                    // according to the BSDF thus cancel out PDF
+                    bsdf * p_scatter
+                } else {
+                    bsdf
+                };
 
-                        bsdf
-                            * scene.transmittance(ho.t)
-                            * material.shading_cosine(wi, ns)
-                            * _integrate(scene, ri, depth + 1)
-                            / p_scatter
-                    }
-                }
+                let ns = ho.ns;
+
+                bsdf
+                    * scene.transmittance(ho.t, ho.lambda, ho.space)
+                    * material.shading_cosine(wi, ns)
+                    * _integrate(scene, ri, depth + 1, sampler)
+                    / p_scatter
             }
         }
     }
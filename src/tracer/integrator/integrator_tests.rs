@@ -0,0 +1,184 @@
+use super::*;
+use crate::{Renderer, RenderSettings, Transform, samplers::SamplerType};
+use crate::tracer::{
+    Instanceable, Material, Sphere, Texture,
+    camera::{Projection, Shutter, ThinLens}
+};
+use glam::IVec2;
+
+const WIDTH: i32 = 8;
+const HEIGHT: i32 = 6;
+const NUM_SAMPLES: i32 = 1024;
+
+fn scene() -> Scene {
+    Scene::empty_box(
+        Color::WHITE,
+        Material::diffuse(Texture::Solid(Color::splat(0.7))),
+        Material::diffuse(Texture::Solid(Color::splat(0.7))),
+    )
+}
+
+/// Box with a mirror sphere, paths through it have delta vertices
+fn mirror_scene() -> Scene {
+    let mut scene = scene();
+    scene.add(Sphere::new(Point::new(0.3, -0.5, -1.3), 0.3, Material::mirror()));
+    scene
+}
+
+/// Box with a sphere moving to the right and rotating
+fn motion_scene() -> Scene {
+    let mut scene = scene();
+    scene.add(Sphere::new(Point::ZERO, 0.3, Material::diffuse(Texture::Solid(Color::WHITE)))
+              .translate(-0.3, -0.5, -1.2)
+              .keyframe(0.0, Transform::IDENTITY)
+              .keyframe(1.0, Transform::from_translation(Vec3::new(0.6, 0.0, 0.0))
+                        * Transform::from_rotation_y(1.0)));
+    scene
+}
+
+/// Mean color of each quadrant of the image rendered with `integrator`
+fn render(scene: Scene, camera: Camera, integrator: Integrator) -> Vec<Float> {
+    let settings = RenderSettings::default()
+        .samples(NUM_SAMPLES)
+        .integrator(integrator)
+        .seed(1);
+    let pixels = Renderer::with_settings(scene, camera, settings).render().resolve();
+
+    let (w, h) = (WIDTH as usize, HEIGHT as usize);
+    (0..4).map(|quadrant| {
+        let (x0, y0) = ((quadrant % 2) * w / 2, (quadrant / 2) * h / 2);
+        (y0..y0 + h / 2)
+            .flat_map(|y| (x0..x0 + w / 2).map(move |x| y * w + x))
+            .map(|idx| pixels[idx].luminance())
+            .sum::<Float>() / (w * h / 4) as Float
+    }).collect()
+}
+
+fn assert_integrators_agree(scene: fn() -> Scene, camera: fn() -> Camera) {
+    let pt = render(scene(), camera(), Integrator::PathTrace);
+    let bdpt = render(scene(), camera(), Integrator::BDPathTrace);
+    for (a, b) in pt.iter().zip(&bdpt) {
+        assert!(*a > 0.0);
+        assert!((a / b - 1.0).abs() < 0.1, "{:?} != {:?}", pt, bdpt);
+    }
+}
+
+#[test]
+fn bdpt_matches_path_trace_perspective() {
+    assert_integrators_agree(scene, || Camera::default(WIDTH, HEIGHT));
+}
+
+#[test]
+fn bdpt_matches_path_trace_mirror() {
+    assert_integrators_agree(mirror_scene, || Camera::default(WIDTH, HEIGHT));
+}
+
+#[test]
+fn bdpt_matches_path_trace_thin_lens() {
+    assert_integrators_agree(scene, || Camera::thin_lens(
+        Point::new(0.0, 0.0, -0.1),
+        Point::NEG_Z,
+        Direction::Y,
+        ThinLens::new(20.0, 2.0, 1.5),
+        WIDTH,
+        HEIGHT,
+    ));
+}
+
+#[test]
+fn bdpt_matches_path_trace_orthographic() {
+    assert_integrators_agree(scene, || Camera::orthographic(
+        Point::new(0.0, 0.0, -0.1),
+        Point::NEG_Z,
+        Direction::Y,
+        0.8,
+        0.0,
+        0.0,
+        WIDTH,
+        HEIGHT,
+    ));
+}
+
+#[test]
+fn bdpt_matches_path_trace_orthographic_dof() {
+    assert_integrators_agree(scene, || Camera::orthographic(
+        Point::new(0.0, 0.0, -0.1),
+        Point::NEG_Z,
+        Direction::Y,
+        0.8,
+        0.1,
+        1.5,
+        WIDTH,
+        HEIGHT,
+    ));
+}
+
+#[test]
+fn bdpt_matches_path_trace_panoramic() {
+    assert_integrators_agree(scene, || Camera::panoramic(
+        Point::new(0.0, 0.0, -1.0),
+        Point::new(0.0, 0.0, -2.0),
+        Direction::Y,
+        Projection::Equirectangular,
+        WIDTH,
+        HEIGHT,
+    ));
+}
+
+#[test]
+fn bdpt_matches_path_trace_motion_blur() {
+    assert_integrators_agree(motion_scene, || {
+        let mut camera = Camera::default(WIDTH, HEIGHT);
+        camera.set_shutter(Shutter::new(0.0, 1.0).rolling(0.5));
+        camera.add_keyframe(0.0, Transform::IDENTITY);
+        camera.add_keyframe(1.0, Transform::from_rotation_y(0.2));
+        camera
+    });
+}
+
+#[test]
+fn aovs_written_only_when_asked() {
+    let scene = scene();
+    let camera = Camera::default(WIDTH, HEIGHT);
+    let mut sampler = SamplerType::Independent.new_sampler(0, 1);
+    let raster_xy = Vec2::new(WIDTH as Float, HEIGHT as Float) / 2.0;
+
+    for integrator in [Integrator::PathTrace, Integrator::DirectLight, Integrator::BDPathTrace] {
+        for aovs in [false, true] {
+            sampler.start_sample(IVec2::ZERO, 0);
+            let (ro, _) = camera.generate_ray(raster_xy, Vec2::splat(0.5), 0.0).unwrap();
+            let samples = integrator.integrate(&scene, &camera, raster_xy, ro, aovs, sampler.as_mut());
+            let sample = samples.last().unwrap();
+            assert!(sample.alpha == 1.0);
+            assert!(sample.aovs.is_empty() != aovs);
+        }
+    }
+}
+
+#[test]
+fn bdpt_crop_matches_full_render() {
+    let render = |settings: RenderSettings| {
+        let settings = settings
+            .samples(NUM_SAMPLES)
+            .integrator(Integrator::BDPathTrace)
+            .seed(1);
+        Renderer::with_settings(scene(), Camera::default(WIDTH, HEIGHT), settings).render()
+    };
+    let (px_min, px_max) = ((2, 1), (6, 4));
+    let full = render(RenderSettings::default()).resolve();
+    let film = render(RenderSettings::default().crop(px_min, px_max));
+    let crop = film.resolve();
+
+    let sum = |pixels: &[Color]| {
+        (px_min.1..px_max.1)
+            .flat_map(|y| (px_min.0..px_max.0).map(move |x| (x + y * WIDTH) as usize))
+            .map(|idx| pixels[idx].luminance())
+            .sum::<Float>()
+    };
+    let (a, b) = (sum(&full), sum(&crop));
+    assert!(a > 0.0);
+    assert!((a / b - 1.0).abs() < 0.1, "{} != {}", a, b);
+
+    let cropped: Float = film.cropped().resolve().iter().map(|c| c.luminance()).sum();
+    assert!((cropped / b - 1.0).abs() < 1e-6);
+}
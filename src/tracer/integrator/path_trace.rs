@@ -1,38 +1,49 @@
 use super::*;
 
-pub fn integrate(scene: &Scene, mut ro: Ray, raster_xy: Vec2) -> FilmSample {
+pub fn integrate(
+    scene: &Scene,
+    camera: &Camera,
+    mut ro: Ray,
+    raster_xy: Vec2,
+    aovs: bool,
+    sampler: &mut dyn Sampler,
+) -> FilmSample {
+    let mut sample = FilmSample::new(Color::BLACK, raster_xy, false);
     let mut last_specular = true;
-    let mut radiance = Color::BLACK;
+    let mut direct = Color::BLACK;
+    let mut indirect = Color::BLACK;
     let mut gathered = Color::WHITE;
     let mut depth = 0;
 
-    while let Some(ho) = scene.hit(&ro) {
+    while let Some(ho) = scene.hit(&ro, sampler) {
+        if depth == 0 {
+            add_first_hit_aovs(&mut sample, camera, &ho, aovs);
+        }
+        stats::count(Counter::PathVertices, 1);
+        // light that has not scattered yet or scatters here for the first time
+        let radiance = if depth == 0 { &mut direct } else { &mut indirect };
         let material = ho.material;
-        gathered *= scene.transmittance(ho.t);
+        gathered *= scene.transmittance(ho.t, ho.lambda, ho.space);
         let wo = ro.dir;
 
-        match material.bsdf_sample(wo, &ho, rand_utils::unit_square()) {
+        match material.bsdf_sample(wo, &ho, sampler.get_1d(), sampler.get_2d()) {
             None => {
                 if last_specular {
-                    radiance += gathered * material.emit(&ho)
+                    *radiance += gathered * material.emit(&ho)
                 }
                 break;
             }
             Some(wi) => {
                 if !material.is_delta() {
-                    radiance += gathered
-                        * shadow_ray(
-                            scene,
-                            &ro,
-                            &ho,
-                            rand_utils::unit_square(),
-                            rand_utils::unit_square()
-                        );
+                    *radiance += gathered * shadow_ray(scene, &ro, &ho, sampler);
                 }
 
-                let ri = ho.generate_ray(wi);
+                let mut ri = ho.generate_ray(wi);
                 let wo = ro.dir;
                 let wi = ri.dir;
+                if material.disperses(wo, wi, &ho) {
+                    ri.lambda = ri.lambda.map(|l| l.terminate_secondary());
+                }
 
                 let p_scatter = material.bsdf_pdf(wo, wi, &ho, false);
                 // resample bad sample?
@@ -57,7 +68,8 @@ pub fn integrate(scene: &Scene, mut ro: Ray, raster_xy: Vec2) -> FilmSample {
                 if depth > 3 {
                     let luminance = gathered.luminance();
                     let rr_prob = (1.0 - luminance).max(0.05);
-                    if rand_utils::rand_float() < rr_prob {
+                    if sampler.get_1d() < rr_prob {
+                        stats::count(Counter::RrTerminations, 1);
                         break;
                     }
                     gathered /= 1.0 - rr_prob;
@@ -70,5 +82,10 @@ pub fn integrate(scene: &Scene, mut ro: Ray, raster_xy: Vec2) -> FilmSample {
         }
     }
 
-    FilmSample::new(radiance, raster_xy, false)
+    sample.color = direct + indirect;
+    if aovs {
+        sample.set_aov(Aov::Direct, direct);
+        sample.set_aov(Aov::Indirect, indirect);
+    }
+    sample
 }
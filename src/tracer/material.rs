@@ -1,6 +1,6 @@
-use crate::{ Normal, Direction, Transport, Float, Vec3, Vec2 };
+use crate::{ Normal, Direction, Transport, Float, Vec3, Vec2, error::{self, Error} };
 use crate::tracer::{
-    Color, hit::Hit, microfacet::MfDistribution,
+    Color, hit::Hit, microfacet::MfDistribution, Spectrum,
     texture::Texture, bsdf::BSDF, bxdf::BxDF, onb::Onb
 };
 
@@ -31,7 +31,60 @@ impl Material {
         is_transparent: bool,
         fresnel_enabled: bool
     ) -> Self {
-        let mfd = MfDistribution::new(roughness, eta, k, fresnel_enabled);
+        error::unwrap(Self::try_microfacet(
+            texture, roughness, eta, k, is_transparent, fresnel_enabled
+        ))
+    }
+
+    /// Fallible `microfacet`. Fails unless `roughness` is in `\[0,1\]`,
+    /// `eta` positive and `k` non-negative.
+    pub fn try_microfacet(
+        texture: Texture,
+        roughness: Float,
+        eta: Float,
+        k: Float,
+        is_transparent: bool,
+        fresnel_enabled: bool
+    ) -> Result<Self, Error> {
+        let mfd = MfDistribution::try_new(roughness, eta, k, fresnel_enabled)?;
+        Ok(Self::from_distribution(texture, mfd, is_transparent, fresnel_enabled))
+    }
+
+    /// General microfacet constructor with `eta` and `k` that vary with
+    /// wavelength in spectral mode
+    pub fn microfacet_spectral(
+        texture: Texture,
+        roughness: Float,
+        eta: Spectrum,
+        k: Spectrum,
+        is_transparent: bool,
+        fresnel_enabled: bool
+    ) -> Self {
+        error::unwrap(Self::try_microfacet_spectral(
+            texture, roughness, eta, k, is_transparent, fresnel_enabled
+        ))
+    }
+
+    /// Fallible `microfacet_spectral`. Fails like `try_microfacet` if `eta`
+    /// or `k` is out of range at any visible wavelength.
+    pub fn try_microfacet_spectral(
+        texture: Texture,
+        roughness: Float,
+        eta: Spectrum,
+        k: Spectrum,
+        is_transparent: bool,
+        fresnel_enabled: bool
+    ) -> Result<Self, Error> {
+        let mfd = MfDistribution::try_spectral(roughness, eta, k, fresnel_enabled)?;
+        Ok(Self::from_distribution(texture, mfd, is_transparent, fresnel_enabled))
+    }
+
+    fn from_distribution(
+        texture: Texture,
+        mfd: MfDistribution,
+        is_transparent: bool,
+        fresnel_enabled: bool
+    ) -> Self {
         // dirty dirty...
         let bsdf = if is_transparent {
             BSDF::new(BxDF::MfDielectric(mfd))
@@ -45,10 +98,41 @@ impl Material {
 
     /// Microfacet mirror with assignable roughness
     pub fn metal(texture: Texture, roughness: Float, eta: Float, k: Float) -> Self {
+        error::unwrap(Self::try_metal(texture, roughness, eta, k))
+    }
+
+    /// Fallible `metal`, see `try_microfacet`
+    pub fn try_metal(
+        texture: Texture,
+        roughness: Float,
+        eta: Float,
+        k: Float
+    ) -> Result<Self, Error> {
         let is_transparent = false;
         let fresnel_enabled = true;
 
-        Self::microfacet(
+        Self::try_microfacet(
+            texture,
+            roughness,
+            eta,
+            k,
+            is_transparent,
+            fresnel_enabled,
+        )
+    }
+
+    /// Microfacet metal with spectral `eta` and `k`, such as
+    /// `Spectrum::GOLD_ETA` and `Spectrum::GOLD_K`
+    pub fn metal_spectral(
+        texture: Texture,
+        roughness: Float,
+        eta: Spectrum,
+        k: Spectrum
+    ) -> Self {
+        let is_transparent = false;
+        let fresnel_enabled = true;
+
+        Self::microfacet_spectral(
             texture,
             roughness,
             eta,
@@ -78,11 +162,36 @@ impl Material {
 
     /// Transparent material
     pub fn transparent(texture: Texture, roughness: Float, eta: Float) -> Self {
+        error::unwrap(Self::try_transparent(texture, roughness, eta))
+    }
+
+    /// Fallible `transparent`, see `try_microfacet`
+    pub fn try_transparent(
+        texture: Texture,
+        roughness: Float,
+        eta: Float
+    ) -> Result<Self, Error> {
         let k = 0.0;
         let is_transparent = true;
         let fresnel_enabled = true;
 
-        Self::microfacet(
+        Self::try_microfacet(
+            texture,
+            roughness,
+            eta,
+            k,
+            is_transparent,
+            fresnel_enabled,
+        )
+    }
+
+    /// Transparent material with spectral `eta`, such as `Spectrum::BK7`
+    pub fn transparent_spectral(texture: Texture, roughness: Float, eta: Spectrum) -> Self {
+        let k = Spectrum::Constant(0.0);
+        let is_transparent = true;
+        let fresnel_enabled = true;
+
+        Self::microfacet_spectral(
             texture,
             roughness,
             eta,
@@ -113,13 +222,18 @@ impl Material {
 
     /// Perfect refraction
     pub fn glass(eta: Float) -> Self {
+        error::unwrap(Self::try_glass(eta))
+    }
+
+    /// Fallible `glass`. Fails unless `eta` is positive.
+    pub fn try_glass(eta: Float) -> Result<Self, Error> {
         let texture = Texture::Solid(Color::WHITE);
         let roughness = 0.0;
         let k = 0.0;
         let is_transparent = true;
         let fresnel_enabled = true;
 
-        Self::microfacet(
+        Self::try_microfacet(
             texture,
             roughness,
             eta,
@@ -129,6 +243,15 @@ impl Material {
         )
     }
 
+    /// Perfect refraction with spectral `eta`. Disperses light in
+    /// spectral mode.
+    pub fn glass_spectral(eta: Spectrum) -> Self {
+        let texture = Texture::Solid(Color::WHITE);
+        let roughness = 0.0;
+
+        Self::transparent_spectral(texture, roughness, eta)
+    }
+
     /// Is the material specular? I.e. reflects light
     pub fn is_specular(&self) -> bool {
         match self {
@@ -148,13 +271,44 @@ impl Material {
     }
 
 
+    /// Does scattering from `wo` to `wi` at `h` separate the wavelengths
+    /// of the path? Only the hero wavelength continues, the integrator
+    /// should terminate the secondary wavelengths of the scattered ray.
+    pub fn disperses(&self, wo: Direction, wi: Direction, h: &Hit) -> bool {
+        match self {
+            Self::Microfacet(bsdf, _, _) | Self::Standard(bsdf, _) => {
+                h.lambda.is_some()
+                    && bsdf.is_dispersive()
+                    && bsdf.is_delta()
+                    // transmission
+                    && wo.dot(h.ns) * wi.dot(h.ns) > 0.0
+            }
+            _ => false,
+        }
+    }
+
+    /// Albedo of the texture at `h` in the working space of the path.
+    /// Black for materials without a texture.
+    pub fn albedo_at(&self, h: &Hit) -> Color {
+        match self {
+            Self::Microfacet(_, t, _) | Self::Standard(_, t) | Self::Light(t) => {
+                Color::from(t.albedo_at(h).to_linear(h.space))
+            }
+            _ => Color::BLACK,
+        }
+    }
+
     /// How much light emitted at `h`?
     pub fn emit(&self, h: &Hit) -> Color {
         match self {
             Self::Light(t) => if h.backface {
                 Color::BLACK
             } else {
-                t.albedo_at(h)
+                let emission = t.albedo_at(h);
+                match &h.lambda {
+                    None => Color::from(emission.to_linear(h.space)),
+                    Some(lambda) => lambda.illuminant(emission),
+                }
             },
             _ => Color::BLACK
         }
@@ -172,31 +326,63 @@ impl Material {
         match self {
             Self::Standard(bsdf, texture) | Self::Microfacet(bsdf, texture, _) => {
                 let albedo = texture.albedo_at(h);
-                bsdf.f(wo, wi, h, albedo, mode)
+                match &h.lambda {
+                    None => bsdf.f(wo, wi, h, Color::from(albedo.to_linear(h.space)), mode),
+                    Some(lambda) if !bsdf.is_dispersive() => {
+                        bsdf.f(wo, wi, h, lambda.albedo(albedo), mode)
+                    }
+                    Some(lambda) => {
+                        let albedo = lambda.albedo(albedo);
+                        let l = lambda.lambda();
+                        if self.disperses(wo, wi, h) {
+                            // direction only valid for the hero wavelength
+                            let f = bsdf.at_wavelength(l.x).f(wo, wi, h, albedo, mode);
+                            lambda.hero_only(f)
+                        } else {
+                            Color::from(Vec3::new(
+                                bsdf.at_wavelength(l.x).f(wo, wi, h, albedo, mode).rgb.x,
+                                bsdf.at_wavelength(l.y).f(wo, wi, h, albedo, mode).rgb.y,
+                                bsdf.at_wavelength(l.z).f(wo, wi, h, albedo, mode).rgb.z,
+                            ))
+                        }
+                    }
+                }
             }
             // volumetric BSDF handled in integrator to cancel out PDF
             Self::Volumetric(_, sigma_t, sigma_s) => {
-                let transmittance = (-*sigma_t * h.t).exp();
+                let (sigma_t, sigma_s) = match &h.lambda {
+                    None => (
+                        Color::from(*sigma_t).to_linear(h.space),
+                        Color::from(sigma_s.to_linear(h.space)),
+                    ),
+                    Some(lambda) => (
+                        lambda.albedo(Color::from(*sigma_t)).rgb,
+                        lambda.albedo(*sigma_s),
+                    ),
+                };
+                let transmittance = (-sigma_t * h.t).exp();
                 // cancel out the transmittance pdf taken from scene transmitance
-                let pdf = (transmittance * *sigma_t).dot(Vec3::ONE)
+                let pdf = (transmittance * sigma_t).dot(Vec3::ONE)
                     / transmittance.dot(Vec3::ONE);
 
-                if pdf == 0.0 { Color::WHITE } else { *sigma_s / pdf }
+                if pdf == 0.0 { Color::WHITE } else { sigma_s / pdf }
             }
             _ => Color::BLACK,
         }
     }
 
     /// Samples leaving direction from `h` from incoming direction `wo`
+    /// using the random values `rand_u` and `rand_sq`
     pub fn bsdf_sample(
         &self,
         wo: Direction,
         h: &Hit,
+        rand_u: Float,
         rand_sq: Vec2
     ) -> Option<Direction> {
         match self {
             Self::Standard(bsdf, _) | Self::Microfacet(bsdf, _, _) => {
-                bsdf.sample(wo, h, rand_sq)
+                hero_bsdf(bsdf, h).sample(wo, h, rand_u, rand_sq)
             }
             /* Henyey-Greenstein (1941) */
             Self::Volumetric(g, _, _) => {
@@ -236,7 +422,7 @@ impl Material {
     ) -> Float {
         match self {
             Self::Standard(bsdf, _) | Self::Microfacet(bsdf, _, _) => {
-                bsdf.pdf(wo, wi, h, swap_dir)
+                hero_bsdf(bsdf, h).pdf(wo, wi, h, swap_dir)
             }
             Self::Volumetric(g, _, _) => {
                 let v = -wo;
@@ -259,3 +445,12 @@ impl Material {
         }
     }
 }
+
+/// BSDF at the hero wavelength of `h` in spectral mode. Decides the
+/// directions of dispersive scattering.
+fn hero_bsdf(bsdf: &BSDF, h: &Hit) -> BSDF {
+    match &h.lambda {
+        Some(lambda) if bsdf.is_dispersive() => bsdf.at_wavelength(lambda.hero()),
+        _ => *bsdf,
+    }
+}
@@ -154,7 +154,7 @@ fn furnace_sample(r: Ray, h: Hit) -> Color {
     let mut radiance = Color::BLACK;
 
     for _ in 0..NUM_SAMPLES {
-        match m.bsdf_sample(wo, &h, rand_utils::unit_square()) {
+        match m.bsdf_sample(wo, &h, rand_utils::rand_float(), rand_utils::unit_square()) {
             None => misses += 1,
             Some(wi) => radiance += m.bsdf_f(wo, wi, Transport::Radiance, &h)
                 * m.shading_cosine(wo, wi)
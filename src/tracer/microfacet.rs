@@ -1,4 +1,5 @@
-use crate::{ Normal, Direction, Float, Vec2, spherical_utils };
+use crate::{ Normal, Direction, Float, Vec2, spherical_utils, error::{self, Error} };
+use crate::tracer::{ Spectrum, spectrum::{LAMBDA_MIN, LAMBDA_MAX, LAMBDA_REFERENCE} };
 use num::complex::Complex;
 
 /// Configurable parameters for a microsurface
@@ -10,6 +11,10 @@ pub struct MicrofacetConfig {
     pub eta: Float,
     /// Absoprtion coefficient
     pub k: Float,
+    /// Refraction index as a function of wavelength used in spectral mode
+    pub eta_spectrum: Spectrum,
+    /// Absorption coefficient as a function of wavelength used in spectral mode
+    pub k_spectrum: Spectrum,
     /// Is fresnel term enabled
     pub fresnel_enabled: bool,
 }
@@ -21,16 +26,74 @@ impl MicrofacetConfig {
         k: Float,
         fresnel_enabled: bool,
     ) -> Self {
-        assert!((0.0..=1.0).contains(&roughness));
-        assert!(eta > 0.0);
-        assert!(k >= 0.0);
+        error::unwrap(Self::try_new(roughness, eta, k, fresnel_enabled))
+    }
 
-        Self {
+    /// Fallible `new`. Fails unless `roughness` is in `\[0,1\]`,
+    /// `eta` positive and `k` non-negative.
+    pub fn try_new(
+        roughness: Float,
+        eta: Float,
+        k: Float,
+        fresnel_enabled: bool,
+    ) -> Result<Self, Error> {
+        error::check((0.0..=1.0).contains(&roughness), || format!(
+            "roughness must be in [0,1], got {}", roughness
+        ))?;
+        error::check(eta > 0.0 && eta.is_finite(), || format!(
+            "refraction index must be positive, got {}", eta
+        ))?;
+        error::check(k >= 0.0 && k.is_finite(), || format!(
+            "absorption coefficient must be non-negative, got {}", k
+        ))?;
+
+        Ok(Self {
             roughness: Vec2::splat(roughness),
             eta,
             k,
+            eta_spectrum: Spectrum::Constant(eta),
+            k_spectrum: Spectrum::Constant(k),
+            fresnel_enabled,
+        })
+    }
+
+    /// Config with spectral `eta` and `k`. RGB rendering uses their
+    /// values at `LAMBDA_REFERENCE`.
+    pub fn spectral(
+        roughness: Float,
+        eta: Spectrum,
+        k: Spectrum,
+        fresnel_enabled: bool,
+    ) -> Self {
+        error::unwrap(Self::try_spectral(roughness, eta, k, fresnel_enabled))
+    }
+
+    /// Fallible `spectral`. Fails like `try_new` if `eta` or `k` is
+    /// out of range at any visible wavelength.
+    pub fn try_spectral(
+        roughness: Float,
+        eta: Spectrum,
+        k: Spectrum,
+        fresnel_enabled: bool,
+    ) -> Result<Self, Error> {
+        let mut cfg = Self::try_new(
+            roughness,
+            eta.eval(LAMBDA_REFERENCE),
+            k.eval(LAMBDA_REFERENCE),
             fresnel_enabled,
+        )?;
+        // spectra are smooth, checking every 10nm is enough
+        let steps = ((LAMBDA_MAX - LAMBDA_MIN) / 10.0) as usize;
+        for lambda in (0..=steps).map(|i| LAMBDA_MIN + 10.0 * i as Float) {
+            let (eta, k) = (eta.eval(lambda), k.eval(lambda));
+            error::check(eta > 0.0 && eta.is_finite() && k >= 0.0 && k.is_finite(), || format!(
+                "refraction index must be positive and absorption coefficient \
+                 non-negative, got {} and {} at {}nm", eta, k, lambda
+            ))?;
         }
+        cfg.eta_spectrum = eta;
+        cfg.k_spectrum = k;
+        Ok(cfg)
     }
 }
 
@@ -54,6 +117,54 @@ impl MfDistribution {
         Self::Ggx(MicrofacetConfig::new(roughness, eta, k, fresnel_enabled))
     }
 
+    /// Fallible `new`, see `MicrofacetConfig::try_new`
+    pub fn try_new(
+        roughness: Float,
+        eta: Float,
+        k: Float,
+        fresnel_enabled: bool
+    ) -> Result<Self, Error> {
+        MicrofacetConfig::try_new(roughness, eta, k, fresnel_enabled).map(Self::Ggx)
+    }
+
+    /// Distribution with `eta` and `k` that vary with wavelength
+    pub fn spectral(
+        roughness: Float,
+        eta: Spectrum,
+        k: Spectrum,
+        fresnel_enabled: bool
+    ) -> Self {
+        Self::Ggx(MicrofacetConfig::spectral(roughness, eta, k, fresnel_enabled))
+    }
+
+    /// Fallible `spectral`, see `MicrofacetConfig::try_spectral`
+    pub fn try_spectral(
+        roughness: Float,
+        eta: Spectrum,
+        k: Spectrum,
+        fresnel_enabled: bool
+    ) -> Result<Self, Error> {
+        MicrofacetConfig::try_spectral(roughness, eta, k, fresnel_enabled).map(Self::Ggx)
+    }
+
+    /// Do `eta` or `k` vary with wavelength?
+    pub fn is_dispersive(&self) -> bool {
+        let cfg = self.get_config();
+        !cfg.eta_spectrum.is_constant() || !cfg.k_spectrum.is_constant()
+    }
+
+    /// Copy of the distribution with `eta` and `k` evaluated at
+    /// the wavelength `lambda`
+    pub fn at_wavelength(&self, lambda: Float) -> Self {
+        let mut cfg = *self.get_config();
+        cfg.eta = cfg.eta_spectrum.eval(lambda);
+        cfg.k = cfg.k_spectrum.eval(lambda);
+        match self {
+            Self::Ggx(_) => Self::Ggx(cfg),
+            Self::Beckmann(_) => Self::Beckmann(cfg),
+        }
+    }
+
     /// might need tuning, send ratio that emittance is multiplied with?
     pub fn is_specular(&self) -> bool {
         let roughness = self.roughness();
@@ -1,7 +1,7 @@
 use crate::{
-    rand_utils, Axis, efloat::{self, EFloat},
+    rand_utils, Axis, efloat::{self, EFloat}, error::{self, Error},
     Point, Normal, Direction, Float,
-    Vec3, Vec2, Transform, Mat3
+    Vec3, Vec2, Transform, Mat3, animated_transform::AnimatedTransform
 };
 use crate::tracer::{
     hit::Hit, material::Material,
@@ -69,10 +69,11 @@ pub trait Sampleable: Object {
     /// Returns surface area of the object
     fn area(&self) -> Float;
 
-    /// Samples a ray leaving at random point on the surface of the object.
-    /// Direction cos weighed on the hemisphere. Returns also normal at ray origin
-    fn sample_leaving(&self, rand_sq0: Vec2, rand_sq1: Vec2) -> (Ray, Hit) {
-        let ho = self.sample_on(rand_sq0);
+    /// Samples a ray leaving at random point on the surface of the object
+    /// at `time`. Direction cos weighed on the hemisphere. Returns also
+    /// normal at ray origin
+    fn sample_leaving(&self, rand_sq0: Vec2, rand_sq1: Vec2, time: Float) -> (Ray, Hit) {
+        let ho = self.sample_on(rand_sq0, time);
         let ns = ho.ns;
         let xo = ho.p;
         let uvw = Onb::new(ns);
@@ -95,8 +96,9 @@ pub trait Sampleable: Object {
     }
 
     /// Returns randomly sampled point on the surface of the object
-    /// and the normal at the point.
-    fn sample_on(&self, rand_sq: Vec2) -> Hit;
+    /// and the normal at the point. Moving objects get sampled at their
+    /// position at `time`.
+    fn sample_on(&self, rand_sq: Vec2, time: Float) -> Hit;
 
     /// Sample random direction from `xo` towards area of object
     /// that is visible form `xo`
@@ -104,8 +106,9 @@ pub trait Sampleable: Object {
     /// # Arguments
     /// * `xo` - Point on the "from" object
     /// * `rand_sq` - Uniformly random point on unit square
-    fn sample_towards(&self, xo: Point, rand_sq: Vec2) -> Direction {
-        let xi = self.sample_on(rand_sq).p;
+    /// * `time` - Time of the path within the shutter interval
+    fn sample_towards(&self, xo: Point, rand_sq: Vec2, time: Float) -> Direction {
+        let xi = self.sample_on(rand_sq, time).p;
         xi - xo
     }
 
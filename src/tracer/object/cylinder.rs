@@ -16,14 +16,27 @@ pub struct Cylinder {
 impl Cylinder {
     /// Cylinder constructor
     pub fn new(height: Float, radius: Float, material: Material) -> Box<Self> {
-        assert!(height > 0.0);
-        assert!(radius > 0.0);
+        error::unwrap(Self::try_new(height, radius, material))
+    }
 
-        Box::new(Self {
+    /// Fallible `new`. Fails unless `height` and `radius` are positive.
+    pub fn try_new(
+        height: Float,
+        radius: Float,
+        material: Material,
+    ) -> Result<Box<Self>, Error> {
+        error::check(height > 0.0 && height.is_finite(), || format!(
+            "cylinder height must be positive, got {}", height
+        ))?;
+        error::check(radius > 0.0 && radius.is_finite(), || format!(
+            "cylinder radius must be positive, got {}", radius
+        ))?;
+
+        Ok(Box::new(Self {
             height,
             radius,
             material,
-        })
+        }))
     }
 }
 
@@ -27,7 +27,22 @@ impl Disk {
         radius: Float,
         material: Material
     ) -> Box<Self> {
-        assert!(normal_dir.dot(normal_dir) != 0.0);
+        error::unwrap(Self::try_new(origin, normal_dir, radius, material))
+    }
+
+    /// Fallible `new`. Fails if `normal_dir` is zero or `radius` not positive.
+    pub fn try_new(
+        origin: Point,
+        normal_dir: Direction,
+        radius: Float,
+        material: Material
+    ) -> Result<Box<Self>, Error> {
+        error::check(normal_dir.length_squared() != 0.0 && normal_dir.is_finite(), || format!(
+            "disk normal must be finite and non-zero, got {}", normal_dir
+        ))?;
+        error::check(radius > 0.0 && radius.is_finite(), || format!(
+            "disk radius must be positive, got {}", radius
+        ))?;
         let normal = normal_dir.normalize();
         let nx = EFloat::from(normal.x); let ny = EFloat::from(normal.y);
         let nz = EFloat::from(normal.z); let ox = EFloat::from(origin.x);
@@ -36,14 +51,14 @@ impl Disk {
         // origin.dot(-normal)
         let d = ox * (-nx) + oy * (-ny) + oz * (-nz);
 
-        Box::new(Self {
+        Ok(Box::new(Self {
             origin,
             material,
             radius,
             normal,
             d,
             uvw: Onb::new(normal),
-        })
+        }))
     }
 }
 
@@ -106,7 +121,7 @@ impl Sampleable for Disk {
         crate::PI * self.radius * self.radius
     }
 
-    fn sample_on(&self, rand_sq: Vec2) -> Hit {
+    fn sample_on(&self, rand_sq: Vec2, _time: Float) -> Hit {
         let rand_disk = rand_utils::square_to_disk(rand_sq);
 
         let xo = self.origin + self.uvw.to_world(Point::new(
@@ -127,8 +142,8 @@ impl Sampleable for Disk {
         ).unwrap()
     }
 
-    fn sample_towards(&self, xo: Point, rand_sq: Vec2) -> Direction {
-        let xi = self.sample_on(rand_sq).p;
+    fn sample_towards(&self, xo: Point, rand_sq: Vec2, time: Float) -> Direction {
+        let xi = self.sample_on(rand_sq, time).p;
         xi - xo
     }
 
@@ -29,7 +29,7 @@ fn sampled_rays_hit() {
     let xo = 5.0 * Point::ONE;
 
     for _ in 0..NUM_RAYS {
-        let wi = d.sample_towards(xo, rand_utils::unit_square());
+        let wi = d.sample_towards(xo, rand_utils::unit_square(), 0.0);
         let ri = Ray::new(xo, wi);
         let (p, _) = d.sample_towards_pdf(&ri);
 
@@ -15,6 +15,12 @@ pub struct Instance<T> {
     /// Transformation for normals from local to world.
     /// Transpose of `inv_transform` without translation.
     normal_transform: Mat3,
+    /// Keyframed motion applied after `transform`, empty if the
+    /// instance does not move
+    motion: AnimatedTransform,
+    /// Transformation applied after `motion`. Kept apart from the keyframes
+    /// as they can only hold a scale, rotation and translation.
+    post: Transform,
 }
 
 impl<T> Instance<T> {
@@ -29,8 +35,21 @@ impl<T> Instance<T> {
             transform,
             inv_transform,
             normal_transform,
+            motion: AnimatedTransform::default(),
+            post: Transform::IDENTITY,
         })
     }
+
+    /// Transformations from local to world, from world to local and
+    /// for normals from local to world at `time`
+    fn transforms_at(&self, time: Float) -> (Transform, Transform, Mat3) {
+        if self.motion.is_empty() {
+            return (self.transform, self.inv_transform, self.normal_transform);
+        }
+        let transform = self.post * self.motion.at(time) * self.transform;
+        let inv_transform = transform.inverse();
+        (transform, inv_transform, inv_transform.matrix3.transpose())
+    }
 }
 
 impl<T: Bounded> Instance<T> {
@@ -43,67 +62,94 @@ impl<T: Bounded> Instance<T> {
     }
 }
 
-impl<T: Bounded> Bounded for Instance<T> {
-    fn bounding_box(&self) -> AaBoundingBox {
+impl<T: Bounded> Instance<T> {
+    /// Bounding box of the object after applying `transform`
+    fn transformed_box(&self, transform: Transform) -> AaBoundingBox {
         /* Graphics Gems I, TRANSFORMING AXIS-ALIGNED BOUNDING BOXES */
         let mut ax_min = Point::ZERO;
         let mut ax_max = Point::ZERO;
         let aabb = self.object.bounding_box();
 
-        let a0 = self.transform.matrix3.row(0) * aabb.min(Axis::X);
-        let b0 = self.transform.matrix3.row(0) * aabb.max(Axis::X);
+        let a0 = transform.matrix3.row(0) * aabb.min(Axis::X);
+        let b0 = transform.matrix3.row(0) * aabb.max(Axis::X);
         let a0b0 = a0.min(b0);
         ax_min.x += a0b0.x + a0b0.y + a0b0.z;
         let a0b0 = a0.max(b0);
         ax_max.x += a0b0.x + a0b0.y + a0b0.z;
 
-        let a1 = self.transform.matrix3.row(1) * aabb.min(Axis::Y);
-        let b1 = self.transform.matrix3.row(1) * aabb.max(Axis::Y);
+        let a1 = transform.matrix3.row(1) * aabb.min(Axis::Y);
+        let b1 = transform.matrix3.row(1) * aabb.max(Axis::Y);
         let a1b1 = a1.min(b1);
         ax_min.y += a1b1.x + a1b1.y + a1b1.z;
         let a1b1 = a1.max(b1);
         ax_max.y += a1b1.x + a1b1.y + a1b1.z;
 
-        let a2 = self.transform.matrix3.row(2) * aabb.min(Axis::Z);
-        let b2 = self.transform.matrix3.row(2) * aabb.max(Axis::Z);
+        let a2 = transform.matrix3.row(2) * aabb.min(Axis::Z);
+        let b2 = transform.matrix3.row(2) * aabb.max(Axis::Z);
         let a2b2 = a2.min(b2);
         ax_min.z += a2b2.x + a2b2.y + a2b2.z;
         let a2b2 = a2.max(b2);
         ax_max.z += a2b2.x + a2b2.y + a2b2.z;
 
         // translate
-        ax_min.x += self.transform.translation.x;
-        ax_min.y += self.transform.translation.y;
-        ax_min.z += self.transform.translation.z;
-
-        ax_max.x += self.transform.translation.x;
-        ax_max.y += self.transform.translation.y;
-        ax_max.z += self.transform.translation.z;
+        ax_min += transform.translation;
+        ax_max += transform.translation;
 
         AaBoundingBox::new(ax_min, ax_max)
     }
 }
 
+impl<T: Bounded> Bounded for Instance<T> {
+    /// Bounding box of the instance over all of its motion
+    fn bounding_box(&self) -> AaBoundingBox {
+        if self.motion.is_empty() {
+            return self.transformed_box(self.transform);
+        }
+
+        // take the union of boxes along the motion. between the samples
+        // rotations move points away from the chord by at most the sagitta,
+        // doubled to leave margin for the interpolated scale
+        let max_angle = crate::PI / 16.0;
+        let aabb = self.transformed_box(self.transform);
+        let radius = aabb.ax_min.abs().max(aabb.ax_max.abs()).length();
+        self.motion.sample_times(max_angle)
+            .into_iter()
+            .map(|time| {
+                let transform = self.post * self.motion.at(time);
+                // Frobenius norm bounds the largest scale
+                let frobenius = |m: Mat3| (m.x_axis.length_squared() + m.y_axis.length_squared()
+                                           + m.z_axis.length_squared()).sqrt();
+                let scale = frobenius(self.motion.at(time).matrix3)
+                    * frobenius(self.post.matrix3);
+                let sagitta = 2.0 * scale * radius * (1.0 - (0.5 * max_angle).cos());
+                let aabb = self.transformed_box(transform * self.transform);
+                AaBoundingBox::new(aabb.ax_min - sagitta, aabb.ax_max + sagitta)
+            })
+            .fold(AaBoundingBox::default(), |b1, b2| b1.merge(&b2))
+    }
+}
+
 impl<T: Object> Object for Instance<T> {
     fn hit(&self, r: &Ray, t_min: Float, t_max: Float) -> Option<Hit> {
+        let (transform, inv_transform, normal_transform) = self.transforms_at(r.time);
         // inner object is in world coordinates. hence apply inverse
         // transformation to ray instead of transformation to object.
-        let ray_local = r.transform(self.inv_transform);
+        let ray_local = r.transform(inv_transform);
 
         self.object.hit(&ray_local, t_min, t_max).map(|mut h| {
-            h.ns = (self.normal_transform * h.ns).normalize();
-            h.ng = (self.normal_transform * h.ng).normalize();
+            h.ns = (normal_transform * h.ns).normalize();
+            h.ng = (normal_transform * h.ng).normalize();
 
             let err = efloat::gamma(3) * Vec3::new(
-                (Vec3::from(self.transform.matrix3.row(0)) * h.p)
-                    .abs().dot(Vec3::ONE) + self.transform.translation.x.abs(),
-                (Vec3::from(self.transform.matrix3.row(1)) * h.p)
-                    .abs().dot(Vec3::ONE) + self.transform.translation.y.abs(),
-                (Vec3::from(self.transform.matrix3.row(2)) * h.p)
-                    .abs().dot(Vec3::ONE) + self.transform.translation.z.abs(),
+                (Vec3::from(transform.matrix3.row(0)) * h.p)
+                    .abs().dot(Vec3::ONE) + transform.translation.x.abs(),
+                (Vec3::from(transform.matrix3.row(1)) * h.p)
+                    .abs().dot(Vec3::ONE) + transform.translation.y.abs(),
+                (Vec3::from(transform.matrix3.row(2)) * h.p)
+                    .abs().dot(Vec3::ONE) + transform.translation.z.abs(),
             );
 
-            h.p = self.transform.transform_point3(h.p);
+            h.p = transform.transform_point3(h.p);
             // TODO: just add them for now...
             h.fp_error += err;
             h
@@ -117,31 +163,34 @@ impl<T: Sampleable> Sampleable for Instance<T> {
         todo!()
     }
 
-    fn sample_on(&self, rand_sq: Vec2) -> Hit {
-        let mut ho = self.object.sample_on(rand_sq);
+    fn sample_on(&self, rand_sq: Vec2, time: Float) -> Hit {
+        let (transform, _, normal_transform) = self.transforms_at(time);
+        let mut ho = self.object.sample_on(rand_sq, time);
 
-        ho.ng = self.normal_transform * ho.ng;
-        ho.ns = self.normal_transform * ho.ns;
-        ho.p = self.transform.transform_point3(ho.p);
+        ho.ng = normal_transform * ho.ng;
+        ho.ns = normal_transform * ho.ns;
+        ho.p = transform.transform_point3(ho.p);
 
         ho
     }
 
-    fn sample_towards(&self, xo: Point, rand_sq: Vec2) -> Direction {
-        let xo_local = self.inv_transform.transform_point3(xo);
-        let dir_local = self.object.sample_towards(xo_local, rand_sq);
+    fn sample_towards(&self, xo: Point, rand_sq: Vec2, time: Float) -> Direction {
+        let (transform, inv_transform, _) = self.transforms_at(time);
+        let xo_local = inv_transform.transform_point3(xo);
+        let dir_local = self.object.sample_towards(xo_local, rand_sq, time);
 
-        self.transform.transform_vector3(dir_local)
+        transform.transform_vector3(dir_local)
     }
 
     fn sample_towards_pdf(&self, ri: &Ray) -> (Float, Option<Hit>) {
-        let ri_local = ri.transform(self.inv_transform);
+        let (transform, inv_transform, normal_transform) = self.transforms_at(ri.time);
+        let ri_local = ri.transform(inv_transform);
         let (pdf_local, hi_local) = self.object.sample_towards_pdf(&ri_local);
         if let Some(mut hi) = hi_local {
             let ng_local = hi.ng;
 
             let xi = ri.at(hi.t);
-            let ng = (self.normal_transform * ng_local).normalize();
+            let ng = (normal_transform * ng_local).normalize();
 
             // object pdf just needs these in world coordinates
             hi.p = xi;
@@ -153,8 +202,8 @@ impl<T: Sampleable> Sampleable for Instance<T> {
             // the base of the parallellepiped gives us the area scale at the
             // point of impact. think this is not exact with ansiotropic
             // scaling of solids. how to do for solid angle?
-            let height = ng.dot(self.transform.matrix3 * ng_local).abs();
-            let volume = self.transform.matrix3.determinant().abs();
+            let height = ng.dot(transform.matrix3 * ng_local).abs();
+            let volume = transform.matrix3.determinant().abs();
             let jacobian = volume / height;
 
             // p_y(y) = p_y(T(x)) = p_x(x) / |J_T(x)|
@@ -173,6 +222,9 @@ pub trait Instanceable<T> {
     /// Apply scale `xyz`
     fn scale(self, x: Float, y: Float, z: Float) -> Box<Instance<T>>;
 
+    /// Fallible `scale`. Fails if the scale of an axis is zero or not finite.
+    fn try_scale(self, x: Float, y: Float, z: Float) -> Result<Box<Instance<T>>, Error>;
+
     /// Rotate around x-axis by `r` radians
     fn rotate_x(self, r: Float) -> Box<Instance<T>>;
 
@@ -184,6 +236,9 @@ pub trait Instanceable<T> {
 
     /// Rotate around `axis` by `r` radisn
     fn rotate_axis(self, axis: Direction, r: Float) -> Box<Instance<T>>;
+
+    /// Move the object with `transform` at `time`, see `Instance::keyframe`
+    fn keyframe(self, time: Float, transform: Transform) -> Box<Instance<T>>;
 }
 
 /// To make applying transformations to objects easy
@@ -194,9 +249,12 @@ impl<T: Object> Instanceable<T> for T {
     }
 
     fn scale(self, x: Float, y: Float, z: Float) -> Box<Instance<T>> {
-        assert!(x * y * z != 0.0);
-        let s = Vec3::new(x, y, z);
-        Instance::new(self, Transform::from_scale(s))
+        error::unwrap(self.try_scale(x, y, z))
+    }
+
+    fn try_scale(self, x: Float, y: Float, z: Float) -> Result<Box<Instance<T>>, Error> {
+        let s = check_scale(x, y, z)?;
+        Ok(Instance::new(self, Transform::from_scale(s)))
     }
 
     fn rotate_x(self, r: Float) -> Box<Instance<T>> {
@@ -214,41 +272,87 @@ impl<T: Object> Instanceable<T> for T {
     fn rotate_axis(self, axis: Direction, r: Float) -> Box<Instance<T>> {
         Instance::new(self, Transform::from_axis_angle(axis, r))
     }
+
+    fn keyframe(self, time: Float, transform: Transform) -> Box<Instance<T>> {
+        Instance::new(self, Transform::IDENTITY).keyframe(time, transform)
+    }
 }
 
 /// Prevent nested Instance structs
 impl<T: Object> Instance<T> {
+    /// Apply `transform` AFTER current transformations. Moving instances
+    /// apply it after their motion.
+    fn then(mut self, transform: Transform) -> Box<Self> {
+        if self.motion.is_empty() {
+            Self::new(self.object, transform * self.transform)
+        } else {
+            self.post = transform * self.post;
+            Box::new(self)
+        }
+    }
+
     /// Apply translation AFTER curret transformations
     pub fn translate(self, x: Float, y: Float, z: Float) -> Box<Self> {
         let t = Vec3::new(x, y, z);
-        Self::new(self.object, Transform::from_translation(t) * self.transform)
+        self.then(Transform::from_translation(t))
     }
 
     /// Apply scale AFTER current transformations
     pub fn scale(self, x: Float, y: Float, z: Float) -> Box<Self> {
-        assert!(x * y * z != 0.0);
-        let s = Vec3::new(x, y, z);
-        Self::new(self.object, Transform::from_scale(s) * self.transform)
+        error::unwrap(self.try_scale(x, y, z))
+    }
+
+    /// Fallible `scale`. Fails if the scale of an axis is zero or not finite.
+    pub fn try_scale(self, x: Float, y: Float, z: Float) -> Result<Box<Self>, Error> {
+        let s = check_scale(x, y, z)?;
+        Ok(self.then(Transform::from_scale(s)))
     }
 
     /// Apply x-rotation AFTER current transformations.
     /// Looking at positive x, rotation in clockwise direction.
     pub fn rotate_x(self, r: Float) -> Box<Self> {
-        Self::new(self.object, Transform::from_rotation_x(r) * self.transform)
+        self.then(Transform::from_rotation_x(r))
     }
 
     /// Apply y-rotation AFTER current transformations
     pub fn rotate_y(self, r: Float) -> Box<Self> {
-        Self::new(self.object, Transform::from_rotation_y(r) * self.transform)
+        self.then(Transform::from_rotation_y(r))
     }
 
     /// Apply z-rotation AFTER current transformations
     pub fn rotate_z(self, r: Float) -> Box<Self> {
-        Self::new(self.object, Transform::from_rotation_z(r) * self.transform)
+        self.then(Transform::from_rotation_z(r))
     }
 
     /// Apply axis rotation AFTER current transformations
     pub fn rotate_axis(self, axis: Direction, r: Float) -> Box<Instance<T>> {
-        Self::new(self.object, Transform::from_axis_angle(axis, r) * self.transform)
+        self.then(Transform::from_axis_angle(axis, r))
+    }
+
+    /// Adds a keyframe to the motion of the instance. At `time` the
+    /// instance gets moved by `transform` AFTER the current transformations.
+    /// Between keyframes the scale, rotation and translation get
+    /// interpolated. Transformations applied to a moving instance get
+    /// applied after its motion. Rays are cast at times within the shutter
+    /// interval of the camera.
+    pub fn keyframe(self, time: Float, transform: Transform) -> Box<Self> {
+        error::unwrap(self.try_keyframe(time, transform))
     }
+
+    /// Fallible `keyframe`. Fails if `time` is not finite or `transform`
+    /// is not invertible.
+    pub fn try_keyframe(mut self, time: Float, transform: Transform) -> Result<Box<Self>, Error> {
+        self.motion.add(time, transform)?;
+        Ok(Box::new(self))
+    }
+}
+
+/// Scale `xyz` as a vector. Fails if the scale of an axis is zero or
+/// not finite.
+fn check_scale(x: Float, y: Float, z: Float) -> Result<Vec3, Error> {
+    let s = Vec3::new(x, y, z);
+    error::check(s.is_finite() && s.cmpne(Vec3::ZERO).all(), || format!(
+        "scale must be finite and non-zero on every axis, got {}", s
+    ))?;
+    Ok(s)
 }
@@ -15,7 +15,7 @@ fn sampling_equals_plain_object() {
     let xo = Point::NEG_Z;
 
     for _ in 0..NUM_SAMPLES {
-        let wi = sphere.sample_towards(xo, rand_utils::unit_square());
+        let wi = sphere.sample_towards(xo, rand_utils::unit_square(), 0.0);
         let ri = Ray::new(xo, wi);
         let (ref_p, _) = ref_sphere.sample_towards_pdf(&ri);
         let (p, _) = sphere.sample_towards_pdf(&ri);
@@ -34,10 +34,102 @@ fn sampled_direction_hits() {
     let xo = Point::NEG_Z;
 
     for _ in 0..NUM_SAMPLES {
-        let wi = sphere.sample_towards(xo, rand_utils::unit_square());
+        let wi = sphere.sample_towards(xo, rand_utils::unit_square(), 0.0);
         let ri = Ray::new(xo, wi);
         let (p, _) = sphere.sample_towards_pdf(&ri);
 
         assert!(p > 0.0);
     }
 }
+
+fn moving_cube() -> Box<Instance<Cube>> {
+    Cube::new(Material::Blank)
+        .translate(-0.5, -0.5, -0.5)
+        .keyframe(0.0, Transform::IDENTITY)
+        .keyframe(1.0, Transform::from_translation(Vec3::new(2.0, 0.0, 0.0))
+                  * Transform::from_rotation_z(crate::PI / 4.0))
+}
+
+#[test]
+fn hit_at_ray_time() {
+    let cube = moving_cube();
+    let mut r = Ray::new(Point::new(0.0, 0.0, -5.0), Direction::Z);
+    assert!(cube.hit(&r, 0.0, crate::INF).is_some());
+    r.time = 1.0;
+    assert!(cube.hit(&r, 0.0, crate::INF).is_none());
+    r.origin.x = 2.0;
+    let h = cube.hit(&r, 0.0, crate::INF).unwrap();
+    assert!((h.t - 5.0 + 0.5).abs() < 1e-6);
+}
+
+#[test]
+fn bounding_box_covers_motion() {
+    let cube = moving_cube();
+    let aabb = cube.bounding_box();
+    let inside = |p: Point| {
+        (p - aabb.ax_min).min_element() > -1e-6 && (aabb.ax_max - p).min_element() > -1e-6
+    };
+    for i in 0..=100 {
+        let (transform, _, _) = cube.transforms_at(i as Float / 100.0);
+        for corner in 0..8 {
+            let p = Point::new(
+                (corner & 1) as Float, ((corner >> 1) & 1) as Float, (corner >> 2) as Float
+            );
+            assert!(inside(transform.transform_point3(p)));
+        }
+    }
+}
+
+#[test]
+fn moving_emitter_sampled_at_time() {
+    let sphere = Sphere::new(Point::ZERO, 1.0, Material::Blank)
+        .translate(0.0, 0.0, 5.0)
+        .keyframe(0.0, Transform::IDENTITY)
+        .keyframe(1.0, Transform::from_translation(Vec3::new(10.0, 0.0, 0.0)));
+    let center = Point::new(10.0, 0.0, 5.0);
+    let xo = Point::ZERO;
+
+    for _ in 0..NUM_SAMPLES {
+        let ho = sphere.sample_on(rand_utils::unit_square(), 1.0);
+        assert!((ho.p.distance(center) - 1.0).abs() < 1e-6);
+        assert!(ho.ng.dot(ho.p - center) > 0.0);
+
+        let wi = sphere.sample_towards(xo, rand_utils::unit_square(), 1.0);
+        let mut ri = Ray::new(xo, wi);
+        ri.time = 1.0;
+        let (p, hi) = sphere.sample_towards_pdf(&ri);
+        assert!(p > 0.0);
+        assert!((hi.unwrap().p.distance(center) - 1.0).abs() < 1e-6);
+
+        ri.time = 0.0;
+        let (p, _) = sphere.sample_towards_pdf(&ri);
+        assert!(p == 0.0);
+    }
+}
+
+#[test]
+fn scale_after_keyframes_sheared() {
+    // scaling the rotated cube along x makes a sheared diamond at time 1
+    let cube = Cube::new(Material::Blank)
+        .translate(-0.5, -0.5, -0.5)
+        .keyframe(0.0, Transform::IDENTITY)
+        .keyframe(1.0, Transform::from_rotation_z(crate::PI / 4.0))
+        .scale(2.0, 1.0, 1.0);
+
+    let (transform, _, _) = cube.transforms_at(1.0);
+    let expected = Transform::from_scale(Vec3::new(2.0, 1.0, 1.0))
+        * Transform::from_rotation_z(crate::PI / 4.0)
+        * Transform::from_translation(Vec3::splat(-0.5));
+    assert!(transform.abs_diff_eq(expected, 1e-10));
+
+    let hits = |x: Float, y: Float, time: Float| {
+        let mut r = Ray::new(Point::new(x, y, -5.0), Direction::Z);
+        r.time = time;
+        cube.hit(&r, 0.0, crate::INF).is_some()
+    };
+    assert!(hits(1.3, 0.0, 1.0));
+    assert!(!hits(1.3, 0.0, 0.0));
+    assert!(hits(0.0, 0.6, 1.0));
+    assert!(!hits(0.75, 0.4, 1.0));
+    assert!(hits(0.75, 0.4, 0.0));
+}
@@ -1,5 +1,5 @@
 use super::*;
-use crate::cli::TracerCli;
+use crate::{stats::{self, Counter}, logging::log_info};
 use std::time::Instant;
 
 /// Triangle mesh constructed as a kD-tree
@@ -25,11 +25,12 @@ pub struct KdTree<T> {
 /// [Article by Amsallem](https://www.flomonster.fr/articles/kdtree.html)
 impl<T: Bounded> KdTree<T> {
     /// Constructs a kD-tree of the given objects with the given material.
-    /// Should each object have their own material instead?
+    /// Should each object have their own material instead? The tree gets
+    /// built in parallel on the current rayon thread pool.
     pub fn new(objects: Vec<T>, material: Material) -> Self {
         let start = Instant::now();
         if objects.len() > 10_000 {
-            println!("Creating kd-tree of {} triangles", objects.len());
+            log_info!("Creating kd-tree of {} triangles", objects.len());
         }
 
         let indices = (0..objects.len()).collect();
@@ -40,15 +41,10 @@ impl<T: Bounded> KdTree<T> {
             .iter()
             .fold(AaBoundingBox::default(), |b1, b2| b1.merge(b2));
 
-        let threads = argh::from_env::<TracerCli>().threads.unwrap_or(0);
-        let pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(threads)
-            .build()
-            .unwrap();
-        let root = pool.install(|| KdNode::construct(&bounds, &boundary, indices));
+        let root = KdNode::construct(&bounds, &boundary, indices);
 
         if objects.len() > 10_000 {
-            println!("Created kd-tree in {:#?}", start.elapsed());
+            log_info!("Created kd-tree in {:#?}", start.elapsed());
         }
 
         Self {
@@ -77,10 +73,12 @@ impl<T: Bounded> KdTree<T> {
         t_max: Float,
         aabb: &AaBoundingBox,
     ) -> Option<Hit> {
+        stats::count(Counter::KdNodesVisited, 1);
         // extract split info or check for hit at leaf node
         let (axis, point, mut node_first, mut node_second) = match node {
             KdNode::Split(axis, point, left, right) => (*axis, *point, left, right),
             KdNode::Leaf(indices) => {
+                stats::count(Counter::PrimitiveTests, indices.len() as u64);
                 let mut tt = t_max;
                 let mut h = None;
                 for idx in indices {
@@ -156,9 +154,12 @@ impl<T: Sampleable + Bounded> Sampleable for KdTree<T> {
         self.objects.iter().fold(0.0, |sum, obj| sum + obj.area())
     }
 
-    fn sample_on(&self, rand_sq: Vec2) -> Hit {
-        let n = rand_utils::rand_float() * self.objects.len() as Float;
-        let mut ho = self.objects[n.floor() as usize].sample_on(rand_sq);
+    fn sample_on(&self, rand_sq: Vec2, time: Float) -> Hit {
+        // choose the object with `rand_sq.x` and reuse its fraction
+        let n = rand_sq.x * self.objects.len() as Float;
+        let idx = (n.floor() as usize).min(self.objects.len() - 1);
+        let rand_sq = Vec2::new((n - idx as Float).min(1.0 - crate::EPSILON), rand_sq.y);
+        let mut ho = self.objects[idx].sample_on(rand_sq, time);
         ho.material = &self.material;
         ho
     }
@@ -1,5 +1,5 @@
 use super::*;
-use crate::tracer::Color;
+use crate::tracer::{Color, ColorSpace, SampledWavelengths};
 
 #[cfg(test)]
 mod medium_test;
@@ -17,7 +17,8 @@ pub struct Medium {
 }
 
 impl Medium {
-    /// Constructs a medium contained in an invisible solid.
+    /// Constructs a medium contained in an invisible solid. The coefficients
+    /// are linear sRGB values.
     ///
     /// # Arguments
     /// * `absorption` - How much of each RGB channel gets absorbed while
@@ -26,24 +27,46 @@ impl Medium {
     /// * `scatter_param` - Scattering parameter to Henyey-Greenstein in
     /// `(-1,1)`
     pub fn new(absorption: Vec3, scattering: Vec3, scatter_param: Float) -> Self {
-        assert!(-1.0 < scatter_param && scatter_param < 1.0);
-        assert!(scattering.min_element() >= 0.0);
-        assert!(absorption.max_element() <= 1.0
-                && absorption.min_element() >= 0.0);
+        error::unwrap(Self::try_new(absorption, scattering, scatter_param))
+    }
+
+    /// Fallible `new`. Fails if any of the arguments is outside its range.
+    pub fn try_new(
+        absorption: Vec3,
+        scattering: Vec3,
+        scatter_param: Float,
+    ) -> Result<Self, Error> {
+        error::check(-1.0 < scatter_param && scatter_param < 1.0, || format!(
+            "medium scattering parameter must be in (-1,1), got {}", scatter_param
+        ))?;
+        error::check(scattering.min_element() >= 0.0 && scattering.is_finite(), || format!(
+            "medium scattering must be finite and non-negative, got {}", scattering
+        ))?;
+        let valid = absorption.cmpge(Vec3::ZERO).all() && absorption.cmple(Vec3::ONE).all();
+        error::check(valid, || format!(
+            "medium absorption must be in [0,1], got {}", absorption
+        ))?;
 
         let sigma_s = Color::from(scattering);
         let sigma_t = scattering + absorption;
 
-        Self {
+        Ok(Self {
             sigma_t,
             material: Material::Volumetric(scatter_param, sigma_t, sigma_s),
-        }
+        })
     }
 
-    /// Computes the transmittance for the distance `t`.
-    pub fn transmittance(&self, t_delta: Float) -> Color {
+    /// Computes the transmittance for the distance `t`. Values at the
+    /// wavelengths `lambda` in spectral mode and in the working `space`
+    /// otherwise.
+    pub fn transmittance(
+        &self,
+        t_delta: Float,
+        lambda: Option<SampledWavelengths>,
+        space: ColorSpace,
+    ) -> Color {
         // need to move some of the stuff to bsdf?
-        let transmittance = (-self.sigma_t * t_delta).exp();
+        let transmittance = (-self.sigma_t(lambda, space) * t_delta).exp();
 
         let pdf = transmittance.dot(Vec3::ONE) / 3.0;
 
@@ -54,15 +77,26 @@ impl Medium {
             Color::from(transmittance / pdf)
         }
     }
-}
 
-impl Object for Medium {
-    fn hit(&self, ro: &Ray, t_min: Float, t_max: Float) -> Option<Hit> {
+    /// `sigma_t` upsampled to the wavelengths `lambda` in spectral mode and
+    /// converted to the working `space` otherwise
+    fn sigma_t(&self, lambda: Option<SampledWavelengths>, space: ColorSpace) -> Vec3 {
+        match lambda {
+            None => Color::from(self.sigma_t).to_linear(space),
+            Some(lambda) => lambda.albedo(Color::from(self.sigma_t)).rgb,
+        }
+    }
+
+    /// Samples a scattering point of `ro` inside the medium between `t_min`
+    /// and `t_max`. `rand_sq.x` chooses the color channel and `rand_sq.y`
+    /// the distance.
+    pub fn hit(&self, ro: &Ray, t_min: Float, t_max: Float, rand_sq: Vec2) -> Option<Hit> {
         // choose a random color channel from density
-        let density = match 3.0 * rand_utils::rand_float() {
-            f if f < 1.0 => self.sigma_t.x,
-            f if f < 2.0 => self.sigma_t.y,
-            _ => self.sigma_t.z,
+        let sigma_t = self.sigma_t(ro.lambda, ro.space);
+        let density = match 3.0 * rand_sq.x {
+            f if f < 1.0 => sigma_t.x,
+            f if f < 2.0 => sigma_t.y,
+            _ => sigma_t.z,
         };
 
         // this channel never gets hit
@@ -73,7 +107,7 @@ impl Object for Medium {
         let ray_length = ro.dir.length();
         let inside_dist = (t_max - t_min) * ray_length;
 
-        let hit_dist = -(1.0 - rand_utils::rand_float()).ln() / density;
+        let hit_dist = -(1.0 - rand_sq.y).ln() / density;
         // this way, the scale of the world matters.
         // doubt there are alternative ways?
         if hit_dist > inside_dist {
@@ -5,7 +5,7 @@ fn zero_medium_has_no_effect() {
     let m = Medium::new(Vec3::ZERO, Vec3::ZERO, 0.0);
 
     let r = Ray::new(Point::ZERO, Direction::Z);
-    assert!(m.hit(&r, 0.0, crate::INF).is_none());
+    assert!(m.hit(&r, 0.0, crate::INF, Vec2::splat(0.5)).is_none());
 
     let h = Hit::new(
         100.0,
@@ -17,7 +17,7 @@ fn zero_medium_has_no_effect() {
         Normal::X,
         Vec2::X,
     ).unwrap();
-    assert!(m.transmittance(h.t).mean() == 1.0);
+    assert!(m.transmittance(h.t, None, ColorSpace::Srgb).mean() == 1.0);
 }
 
 #[test]
@@ -25,5 +25,5 @@ fn medium_gets_hit() {
     let m = Medium::new(Vec3::splat(crate::EPSILON), Vec3::splat(crate::EPSILON), 0.0);
 
     let r = Ray::new(Point::ZERO, Direction::Z);
-    assert!(m.hit(&r, 0.0, crate::INF).is_some());
+    assert!(m.hit(&r, 0.0, crate::INF, Vec2::splat(0.5)).is_some());
 }
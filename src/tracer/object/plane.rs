@@ -16,7 +16,14 @@ pub struct Plane {
 impl Plane {
     /// Constructs an infinite plane given a point and a normal
     pub fn new(p: Point, n: Normal, material: Material) -> Box<Self> {
-        assert!(n.dot(n) != 0.0);
+        error::unwrap(Self::try_new(p, n, material))
+    }
+
+    /// Fallible `new`. Fails if the normal `n` is zero.
+    pub fn try_new(p: Point, n: Normal, material: Material) -> Result<Box<Self>, Error> {
+        error::check(n.length_squared() != 0.0 && n.is_finite(), || format!(
+            "plane normal must be finite and non-zero, got {}", n
+        ))?;
         let normal = n.normalize();
         let nx = EFloat::from(normal.x); let ny = EFloat::from(normal.y);
         let nz = EFloat::from(normal.z); let px = EFloat::from(p.x);
@@ -25,11 +32,11 @@ impl Plane {
         // p.dot(-normal)
         let d = px * (-nx) + py * (-ny) + pz * (-nz);
 
-        Box::new(Self {
+        Ok(Box::new(Self {
             normal,
             material,
             d,
-        })
+        }))
     }
 }
 
@@ -50,7 +50,7 @@ impl Sampleable for Rectangle {
         self.mesh.area()
     }
 
-    fn sample_on(&self, rand_sq: Vec2) -> Hit {
-        self.mesh.sample_on(rand_sq)
+    fn sample_on(&self, rand_sq: Vec2, time: Float) -> Hit {
+        self.mesh.sample_on(rand_sq, time)
     }
 }
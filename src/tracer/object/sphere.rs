@@ -19,13 +19,27 @@ impl Sphere {
     /// * `radius` - Radius of the sphere
     /// * `material` - Material of the sphere
     pub fn new(origin: Point, radius: Float, material: Material) -> Box<Self> {
-        assert!(radius != 0.0);
+        error::unwrap(Self::try_new(origin, radius, material))
+    }
 
-        Box::new(Self {
+    /// Fallible `new`. Fails if `radius` is zero or not finite.
+    pub fn try_new(
+        origin: Point,
+        radius: Float,
+        material: Material,
+    ) -> Result<Box<Self>, Error> {
+        error::check(radius != 0.0 && radius.is_finite(), || format!(
+            "sphere radius must be finite and non-zero, got {}", radius
+        ))?;
+        error::check(origin.is_finite(), || format!(
+            "sphere origin must be finite, got {}", origin
+        ))?;
+
+        Ok(Box::new(Self {
             origin,
             radius,
             material,
-        })
+        }))
     }
 }
 
@@ -94,7 +108,7 @@ impl Sampleable for Sphere {
     }
 
     /// Sample on unit sphere and scale
-    fn sample_on(&self, rand_sq: Vec2) -> Hit {
+    fn sample_on(&self, rand_sq: Vec2, _time: Float) -> Hit {
         let rand_sph = rand_utils::square_to_sphere(rand_sq);
 
         let xo = self.origin + self.radius * rand_sph;
@@ -115,13 +129,13 @@ impl Sampleable for Sphere {
     /// Visible area from `xo` forms a cone. Sample a random point on the
     /// spherical cap that the visible area forms. Return a ray with direction
     /// towards the sampled point.
-    fn sample_towards(&self, xo: Point, rand_sq: Vec2) -> Direction {
+    fn sample_towards(&self, xo: Point, rand_sq: Vec2, time: Float) -> Direction {
         let dist_origin2 = xo.distance_squared(self.origin);
         let radius2 = self.radius * self.radius;
 
         let xi = if dist_origin2 < radius2 {
             // if inside sphere, just sample on the surface
-            let xi = self.sample_on(rand_sq).p;
+            let xi = self.sample_on(rand_sq, time).p;
             xi
         } else {
             /* uvw-orthonormal basis,
@@ -13,7 +13,7 @@ fn sampled_rays_hit() {
     let xo = 5.0 * Point::Z;
 
     for _ in 0..NUM_RAYS {
-        let wi = s.sample_towards(xo, rand_utils::unit_square());
+        let wi = s.sample_towards(xo, rand_utils::unit_square(), 0.0);
         let ri = Ray::new(xo, wi);
         let (p, _) = s.sample_towards_pdf(&ri);
 
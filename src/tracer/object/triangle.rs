@@ -195,7 +195,7 @@ impl Sampleable for Triangle {
     }
 
     /// Random point with barycentrics.
-    fn sample_on(&self, rand_sq: Vec2) -> Hit {
+    fn sample_on(&self, rand_sq: Vec2, _time: Float) -> Hit {
         let gamma = 1.0 - (1.0 - rand_sq.x).sqrt();
         let beta = rand_sq.y * (1.0 - gamma);
         let alpha = 1.0 - gamma - beta;
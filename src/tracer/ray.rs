@@ -1,4 +1,5 @@
 use crate::{ Axis, Direction, Point, Float, Transform };
+use crate::tracer::{ColorSpace, SampledWavelengths};
 
 /// Ray abstraction
 pub struct Ray {
@@ -6,6 +7,13 @@ pub struct Ray {
     pub origin: Point,
     /// Direction of the ray. Normalized.
     pub dir: Direction,
+    /// Wavelengths carried by the ray in spectral mode
+    pub lambda: Option<SampledWavelengths>,
+    /// Working space of the path in RGB mode
+    pub space: ColorSpace,
+    /// Time at which the ray is cast within the shutter interval
+    /// of the camera
+    pub time: Float,
 }
 
 impl Ray {
@@ -14,6 +22,9 @@ impl Ray {
         Self {
             origin,
             dir: dir.normalize(),
+            lambda: None,
+            space: ColorSpace::Srgb,
+            time: 0.0,
         }
     }
 
@@ -23,6 +34,9 @@ impl Ray {
         Self {
             origin: transformation.transform_point3(self.origin),
             dir: transformation.transform_vector3(self.dir),
+            lambda: self.lambda,
+            space: self.space,
+            time: self.time,
         }
     }
 
@@ -1,7 +1,8 @@
-use crate::{ Float, rand_utils };
+use crate::Float;
+use crate::samplers::Sampler;
 use crate::tracer::{
-    hit::Hit, ray::Ray, Material, Texture, Color,
-    Medium, Object, Rectangle, Sampleable
+    hit::Hit, ray::Ray, Material, Texture, Color, ColorSpace,
+    Medium, Object, Rectangle, Sampleable, SampledWavelengths
 };
 
 #[cfg(test)]
@@ -44,56 +45,73 @@ impl Scene {
         self.lights.len()
     }
 
-    /// Choose one of the lights uniformly at random. Crash if no lights.
-    pub fn uniform_random_light(&self) -> &dyn Sampleable {
-        let rnd = rand_utils::rand_float();
-        let idx = (rnd * self.lights.len() as Float).floor() as usize;
+    /// Choose one of the lights uniformly at random using `rand_u` in
+    /// `\[0,1)`. Crash if no lights.
+    pub fn uniform_random_light(&self, rand_u: Float) -> &dyn Sampleable {
+        let idx = (rand_u * self.lights.len() as Float).floor() as usize;
+        let idx = idx.min(self.lights.len() - 1);
         self.lights[idx].as_ref()
     }
 
-    /// Returns the transmittance due to volumetric medium
-    pub fn transmittance(&self, t: Float) -> Color {
+    /// Returns the transmittance due to volumetric medium at the
+    /// wavelengths `lambda` of the path, or in its working `space`
+    pub fn transmittance(
+        &self,
+        t: Float,
+        lambda: Option<SampledWavelengths>,
+        space: ColorSpace,
+    ) -> Color {
         match &self.medium {
             None => Color::WHITE,
-            Some(medium) => medium.transmittance(t),
+            Some(medium) => medium.transmittance(t, lambda, space),
         }
     }
 
-    /// Returns the closest object `r` hits and `None` if no hits
-    pub fn hit(&self, r: &Ray) -> Option<Hit> {
+    /// Returns the closest object `r` hits and `None` if no hits.
+    /// Scattering in the medium gets sampled with `sampler`.
+    pub fn hit(&self, r: &Ray, sampler: &mut dyn Sampler) -> Option<Hit> {
         let mut t_max = crate::INF;
         let mut h = None;
 
         if let Some(medium) = &self.medium {
             // if we hit an object, it must be closer than what we have
-            h = medium.hit(r, 0.0, t_max).or(h);
+            h = medium.hit(r, 0.0, t_max, sampler.get_2d()).or(h);
             // update distance to closest found so far
             t_max = h.as_ref().map_or(t_max, |hit| hit.t);
         }
 
-        for object in &self.objects {
+        for (i, object) in self.objects.iter().enumerate() {
             // if we hit an object, it must be closer than what we have
-            h = object.hit(r, 0.0, t_max).or(h);
+            h = object.hit(r, 0.0, t_max).map(|mut hit| {
+                hit.object_id = Some(i);
+                hit
+            }).or(h);
             // update distance to closest found so far
             t_max = h.as_ref().map_or(t_max, |hit| hit.t);
         }
 
         // lazy, something better should be done.
         // use enum wrapper? have issues with instances..
-        for light in &self.lights {
+        for (i, light) in self.lights.iter().enumerate() {
             h = light.hit(r, 0.0, t_max).map(|mut hit| {
                 t_max = hit.t;
                 hit.light = Some(light.as_ref());
+                hit.object_id = Some(self.objects.len() + i);
                 hit
             }).or(h);
         }
 
-        h
+        h.map(|mut hit| {
+            hit.lambda = r.lambda;
+            hit.space = r.space;
+            hit.time = r.time;
+            hit
+        })
     }
 
     /// Does ray `r` reach the light object `light`?
     pub fn hit_light<'a>(&'a self, r: &Ray, light: &'a dyn Sampleable) -> Option<Hit> {
-        let light_hit = match light.hit(r, 0.0, crate::INF) {
+        let mut light_hit = match light.hit(r, 0.0, crate::INF) {
             None => return None,
             Some(hi) => hi,
         };
@@ -112,6 +130,9 @@ impl Scene {
             }
         }
 
+        light_hit.lambda = r.lambda;
+        light_hit.space = r.space;
+        light_hit.time = r.time;
         Some( light_hit )
     }
 }
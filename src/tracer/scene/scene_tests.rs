@@ -1,6 +1,6 @@
 use super::*;
 use crate::tracer::{Plane, Sphere};
-use crate::{Point, Direction};
+use crate::{Point, Direction, samplers::SamplerType};
 
 /* light at y = 2, plane at y = 1 perp to z */
 fn scene(m: Material) -> Scene {
@@ -53,5 +53,6 @@ fn hits_closest() {
 
     let r = Ray::new(Point::ZERO, Point::Y);
     let is_blank = |h: &Hit| -> bool { matches!(h.material, Material::Blank) };
-    assert!(s.hit(&r).filter(is_blank).is_some());
+    let mut sampler = SamplerType::Independent.new_sampler(0, 1);
+    assert!(s.hit(&r, sampler.as_mut()).filter(is_blank).is_some());
 }
@@ -0,0 +1,385 @@
+use crate::{Float, Vec3};
+use crate::tracer::{Color, ColorSpace};
+
+#[cfg(test)]
+mod spectrum_tests;
+
+/// Shortest wavelength in nanometers sampled in spectral mode
+pub const LAMBDA_MIN: Float = 360.0;
+/// Longest wavelength in nanometers sampled in spectral mode
+pub const LAMBDA_MAX: Float = 830.0;
+/// Wavelength in nanometers at which spectral curves get evaluated
+/// when rendering in RGB
+pub const LAMBDA_REFERENCE: Float = 550.0;
+/// Number of wavelengths carried by each path
+const NUM_WAVELENGTHS: usize = 3;
+/// Integral of the `y` matching function over `\[LAMBDA_MIN, LAMBDA_MAX\]`
+const CIE_Y_INTEGRAL: Float = 106.922084;
+/// Luminance of the tabulated D65 spectrum, normalizes white emitters
+/// to unit luminance
+const D65_LUMINANCE: Float = 98.851007;
+
+/// Relative spectral power of the CIE D65 illuminant in steps of 10nm
+/// starting from `LAMBDA_MIN`
+const D65: [Float; 48] = [
+    46.6383, 52.0891, 49.9755, 54.6482, 82.7549, 91.4860, 93.4318, 86.6823,
+    104.865, 117.008, 117.812, 114.861, 115.923, 108.811, 109.354, 107.802,
+    104.790, 107.689, 104.405, 104.046, 100.000, 96.3342, 95.7880, 88.6856,
+    90.0062, 89.5991, 87.6987, 83.2886, 83.6992, 80.0268, 80.2146, 82.2778,
+    78.2842, 69.7213, 71.6091, 74.3490, 61.6040, 69.8856, 75.0870, 63.5927,
+    46.4182, 66.8054, 63.3828, 64.3040, 59.4519, 51.9590, 57.4406, 60.3125,
+];
+
+/// First and last wavelength of the basis spectra of Smits (1999)
+const SMITS_RANGE: (Float, Float) = (380.0, 720.0);
+/// Basis spectra of Smits (1999) sampled at 10 evenly spaced wavelengths
+const SMITS_WHITE: [Float; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [Float; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [Float; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [Float; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [Float; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [Float; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [Float; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// Wavelengths carried by a path in spectral mode. Each channel of the
+/// colors transported along the path holds the value at one wavelength.
+/// The first wavelength is the hero wavelength that decides directions
+/// of dispersive scattering.
+#[derive(Clone, Copy, Debug)]
+pub struct SampledWavelengths {
+    /// Wavelengths in nanometers
+    lambda: Vec3,
+    /// PDFs of the wavelengths
+    pdf: Vec3,
+}
+
+impl SampledWavelengths {
+    /// Samples the hero wavelength from the visible range using `u` and
+    /// places the rest at equal offsets within the range. Sampling follows
+    /// the sensitivity of the eye as in pbrt.
+    pub fn sample(u: Float) -> Self {
+        let mut lambda = Vec3::ZERO;
+        let mut pdf = Vec3::ZERO;
+        for i in 0..NUM_WAVELENGTHS {
+            let ui = (u + i as Float / NUM_WAVELENGTHS as Float).fract();
+            lambda[i] = sample_visible(ui);
+            pdf[i] = visible_pdf(lambda[i]);
+        }
+
+        Self { lambda, pdf }
+    }
+
+    /// The hero wavelength
+    pub fn hero(&self) -> Float {
+        self.lambda.x
+    }
+
+    /// All of the wavelengths in nanometers
+    pub fn lambda(&self) -> Vec3 {
+        self.lambda
+    }
+
+    /// PDFs of the wavelengths
+    pub fn pdf(&self) -> Vec3 {
+        self.pdf
+    }
+
+    /// Terminates the secondary wavelengths after scattering that only
+    /// the hero wavelength can take
+    pub fn terminate_secondary(mut self) -> Self {
+        self.pdf.y = 0.0;
+        self.pdf.z = 0.0;
+        self
+    }
+
+    /// Have the secondary wavelengths been terminated?
+    pub fn is_secondary_terminated(&self) -> bool {
+        self.pdf.y == 0.0 && self.pdf.z == 0.0
+    }
+
+    /// Drops the secondary wavelengths from the spectral `values` of
+    /// scattering that terminates them. The hero carries the estimate of
+    /// the whole path from the first termination on.
+    pub fn hero_only(&self, values: Color) -> Color {
+        let scale = if self.is_secondary_terminated() {
+            1.0
+        } else {
+            NUM_WAVELENGTHS as Float
+        };
+
+        Color::from(Vec3::new(scale * values.rgb.x, 0.0, 0.0))
+    }
+
+    /// Values of a path joined from two subpaths with the wavelengths
+    /// `self` and `other`. If both terminated the secondary wavelengths,
+    /// the hero got scaled twice.
+    pub fn join(&self, other: &Self, values: Color) -> Color {
+        if self.is_secondary_terminated() && other.is_secondary_terminated() {
+            values / NUM_WAVELENGTHS as Float
+        } else {
+            values
+        }
+    }
+
+    /// Evaluates `spectrum` at the wavelengths
+    pub fn eval(&self, spectrum: &Spectrum) -> Vec3 {
+        Vec3::new(
+            spectrum.eval(self.lambda.x),
+            spectrum.eval(self.lambda.y),
+            spectrum.eval(self.lambda.z),
+        )
+    }
+
+    /// Upsamples the linear sRGB reflectance or coefficient `rgb` to values
+    /// at the wavelengths
+    pub fn albedo(&self, rgb: Color) -> Color {
+        let value = |lambda: Float| rgb_to_spectrum(rgb.rgb, lambda).max(0.0);
+
+        Color::from(Vec3::new(
+            value(self.lambda.x),
+            value(self.lambda.y),
+            value(self.lambda.z),
+        ))
+    }
+
+    /// Upsamples the linear sRGB emission `rgb`. White
+    /// emission becomes the D65 illuminant with unit luminance.
+    pub fn illuminant(&self, rgb: Color) -> Color {
+        let d65 = Vec3::new(
+            d65(self.lambda.x),
+            d65(self.lambda.y),
+            d65(self.lambda.z),
+        );
+
+        self.albedo(rgb) * Color::from(d65 / D65_LUMINANCE)
+    }
+
+    /// Estimates CIE XYZ of the spectral `values` at the wavelengths
+    pub fn to_xyz(&self, values: Color) -> Vec3 {
+        let mut xyz = Vec3::ZERO;
+        for i in 0..NUM_WAVELENGTHS {
+            if self.pdf[i] == 0.0 {
+                continue;
+            }
+            xyz += values.rgb[i] * cie_xyz(self.lambda[i]) / self.pdf[i];
+        }
+
+        xyz / (NUM_WAVELENGTHS as Float * CIE_Y_INTEGRAL)
+    }
+
+    /// Converts the spectral `values` to linear RGB in `space`
+    pub fn to_rgb(&self, values: Color, space: ColorSpace) -> Color {
+        let srgb = ColorSpace::Srgb.rgb_to_xyz().inverse() * self.to_xyz(values);
+
+        Color::from(ColorSpace::conversion(ColorSpace::Srgb, space) * srgb)
+    }
+}
+
+/// Spectral curves of optical constants, such as the index of refraction
+/// `eta` and the absorption coefficient `k`. Wavelengths in nanometers.
+#[derive(Clone, Copy, Debug)]
+pub enum Spectrum {
+    /// Same value at every wavelength
+    Constant(Float),
+    /// Linearly interpolated `(wavelength, value)` pairs sorted by
+    /// wavelength. Clamped to the end values outside the table.
+    Tabulated(&'static [(Float, Float)]),
+    /// Sellmeier equation of the index of refraction of glasses with the
+    /// `B` and `C` coefficients. `C` in squared micrometers.
+    Sellmeier([Float; 3], [Float; 3]),
+}
+
+impl Spectrum {
+    /// Schott N-BK7 borosilicate crown glass
+    pub const BK7: Self = Self::Sellmeier(
+        [1.03961212, 0.231792344, 1.01046945],
+        [0.00600069867, 0.0200179144, 103.560653],
+    );
+    /// Fused silica (Malitson 1965)
+    pub const FUSED_SILICA: Self = Self::Sellmeier(
+        [0.6961663, 0.4079426, 0.8974794],
+        [0.00467914826, 0.0135120631, 97.9340025],
+    );
+    /// Schott SF11 dense flint glass with strong dispersion
+    pub const SF11: Self = Self::Sellmeier(
+        [1.73759695, 0.313747346, 1.89878101],
+        [0.013188707, 0.0623068142, 155.23629],
+    );
+    /// Diamond (Peter 1923)
+    pub const DIAMOND: Self = Self::Sellmeier(
+        [0.3306, 4.3356, 0.0],
+        [0.030625, 0.011236, 0.0],
+    );
+    /// Index of refraction of gold (Johnson and Christy 1972)
+    pub const GOLD_ETA: Self = Self::Tabulated(&[
+        (400.0, 1.66), (450.0, 1.47), (500.0, 0.97), (550.0, 0.35),
+        (600.0, 0.22), (650.0, 0.17), (700.0, 0.16), (750.0, 0.16),
+        (800.0, 0.15),
+    ]);
+    /// Absorption coefficient of gold (Johnson and Christy 1972)
+    pub const GOLD_K: Self = Self::Tabulated(&[
+        (400.0, 1.96), (450.0, 1.95), (500.0, 1.87), (550.0, 2.72),
+        (600.0, 3.10), (650.0, 3.56), (700.0, 4.00), (750.0, 4.42),
+        (800.0, 4.78),
+    ]);
+    /// Index of refraction of silver (Johnson and Christy 1972)
+    pub const SILVER_ETA: Self = Self::Tabulated(&[
+        (400.0, 0.05), (450.0, 0.04), (500.0, 0.05), (550.0, 0.06),
+        (600.0, 0.06), (650.0, 0.05), (700.0, 0.04), (750.0, 0.03),
+        (800.0, 0.04),
+    ]);
+    /// Absorption coefficient of silver (Johnson and Christy 1972)
+    pub const SILVER_K: Self = Self::Tabulated(&[
+        (400.0, 2.07), (450.0, 2.65), (500.0, 3.09), (550.0, 3.59),
+        (600.0, 4.15), (650.0, 4.48), (700.0, 4.84), (750.0, 5.24),
+        (800.0, 5.52),
+    ]);
+    /// Index of refraction of copper (Johnson and Christy 1972)
+    pub const COPPER_ETA: Self = Self::Tabulated(&[
+        (400.0, 1.18), (450.0, 1.16), (500.0, 1.12), (550.0, 1.02),
+        (600.0, 0.27), (650.0, 0.21), (700.0, 0.21), (750.0, 0.24),
+        (800.0, 0.26),
+    ]);
+    /// Absorption coefficient of copper (Johnson and Christy 1972)
+    pub const COPPER_K: Self = Self::Tabulated(&[
+        (400.0, 2.21), (450.0, 2.50), (500.0, 2.60), (550.0, 2.58),
+        (600.0, 3.41), (650.0, 3.67), (700.0, 4.21), (750.0, 4.60),
+        (800.0, 5.00),
+    ]);
+    /// Index of refraction of aluminium (Rakić 1995)
+    pub const ALUMINIUM_ETA: Self = Self::Tabulated(&[
+        (400.0, 0.49), (450.0, 0.62), (500.0, 0.77), (550.0, 0.96),
+        (600.0, 1.20), (650.0, 1.47), (700.0, 1.83), (750.0, 2.34),
+        (800.0, 2.80),
+    ]);
+    /// Absorption coefficient of aluminium (Rakić 1995)
+    pub const ALUMINIUM_K: Self = Self::Tabulated(&[
+        (400.0, 4.86), (450.0, 5.47), (500.0, 6.08), (550.0, 6.69),
+        (600.0, 7.26), (650.0, 7.79), (700.0, 8.31), (750.0, 8.62),
+        (800.0, 8.45),
+    ]);
+
+    /// Value of the curve at wavelength `lambda`
+    pub fn eval(&self, lambda: Float) -> Float {
+        match self {
+            Self::Constant(v) => *v,
+            Self::Tabulated(table) => {
+                let idx = table.partition_point(|(l, _)| *l <= lambda);
+                if idx == 0 {
+                    table[0].1
+                } else if idx == table.len() {
+                    table[idx - 1].1
+                } else {
+                    let (l0, v0) = table[idx - 1];
+                    let (l1, v1) = table[idx];
+                    let t = (lambda - l0) / (l1 - l0);
+                    (1.0 - t) * v0 + t * v1
+                }
+            }
+            Self::Sellmeier(b, c) => {
+                let l2 = (lambda * 1e-3).powi(2);
+                let n2 = 1.0 + (0..3)
+                    .map(|i| b[i] * l2 / (l2 - c[i]))
+                    .sum::<Float>();
+                n2.max(1.0).sqrt()
+            }
+        }
+    }
+
+    /// Does the value change with the wavelength?
+    pub fn is_constant(&self) -> bool {
+        matches!(self, Self::Constant(_))
+    }
+}
+
+/// Samples a wavelength in `\[LAMBDA_MIN, LAMBDA_MAX\]` proportional to
+/// the sensitivity of the eye
+fn sample_visible(u: Float) -> Float {
+    538.0 - 138.888889 * (0.85691062 - 1.82750197 * u).atanh()
+}
+
+/// PDF of `sample_visible`
+fn visible_pdf(lambda: Float) -> Float {
+    if !(LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda) {
+        return 0.0;
+    }
+
+    0.0039398042 / (0.0072 * (lambda - 538.0)).cosh().powi(2)
+}
+
+/// CIE 1931 color matching functions at `lambda` using the multi-lobe
+/// Gaussian fit of Wyman et al. 2013
+fn cie_xyz(lambda: Float) -> Vec3 {
+    let g = |mu: Float, sigma_lo: Float, sigma_hi: Float| {
+        let sigma = if lambda < mu { sigma_lo } else { sigma_hi };
+        (-0.5 * ((lambda - mu) / sigma).powi(2)).exp()
+    };
+
+    Vec3::new(
+        1.056 * g(599.8, 37.9, 31.0)
+            + 0.362 * g(442.0, 16.0, 26.7)
+            - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+/// Relative power of the D65 illuminant at `lambda`
+fn d65(lambda: Float) -> Float {
+    let t = ((lambda - LAMBDA_MIN) / 10.0).clamp(0.0, (D65.len() - 1) as Float);
+    let idx = (t as usize).min(D65.len() - 2);
+    let t = t - idx as Float;
+
+    (1.0 - t) * D65[idx] + t * D65[idx + 1]
+}
+
+/// Linear interpolation of a basis spectrum of Smits at `lambda`
+fn smits_basis(basis: &[Float; 10], lambda: Float) -> Float {
+    let (lo, hi) = SMITS_RANGE;
+    let t = ((lambda - lo) / (hi - lo) * 9.0).clamp(0.0, 9.0);
+    let idx = (t as usize).min(8);
+    let t = t - idx as Float;
+
+    (1.0 - t) * basis[idx] + t * basis[idx + 1]
+}
+
+/// Value at `lambda` of a smooth spectrum with the linear sRGB color `rgb`
+/// as in Smits (1999)
+fn rgb_to_spectrum(rgb: Vec3, lambda: Float) -> Float {
+    let (r, g, b) = (rgb.x, rgb.y, rgb.z);
+    let basis = |spectrum: &[Float; 10]| smits_basis(spectrum, lambda);
+
+    if r <= g && r <= b {
+        r * basis(&SMITS_WHITE) + if g <= b {
+            (g - r) * basis(&SMITS_CYAN) + (b - g) * basis(&SMITS_BLUE)
+        } else {
+            (b - r) * basis(&SMITS_CYAN) + (g - b) * basis(&SMITS_GREEN)
+        }
+    } else if g <= r && g <= b {
+        g * basis(&SMITS_WHITE) + if r <= b {
+            (r - g) * basis(&SMITS_MAGENTA) + (b - r) * basis(&SMITS_BLUE)
+        } else {
+            (b - g) * basis(&SMITS_MAGENTA) + (r - b) * basis(&SMITS_RED)
+        }
+    } else {
+        b * basis(&SMITS_WHITE) + if r <= g {
+            (r - b) * basis(&SMITS_YELLOW) + (g - r) * basis(&SMITS_GREEN)
+        } else {
+            (g - b) * basis(&SMITS_YELLOW) + (r - g) * basis(&SMITS_RED)
+        }
+    }
+}
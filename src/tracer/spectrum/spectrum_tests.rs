@@ -0,0 +1,80 @@
+use super::*;
+
+const NUM_SAMPLES: usize = 10_000;
+
+fn assert_close(a: Vec3, b: Vec3, eps: Float) {
+    assert!((a - b).abs().max_element() < eps, "{} != {}", a, b);
+}
+
+/// Mean RGB of `values` upsampled at stratified wavelengths
+fn mean_rgb(values: impl Fn(&SampledWavelengths) -> Color) -> Vec3 {
+    (0..NUM_SAMPLES)
+        .map(|i| {
+            let lambda = SampledWavelengths::sample((i as Float + 0.5) / NUM_SAMPLES as Float);
+            lambda.to_rgb(values(&lambda), ColorSpace::Srgb).rgb
+        })
+        .sum::<Vec3>() / NUM_SAMPLES as Float
+}
+
+#[test]
+fn wavelengths_in_visible_range() {
+    for i in 0..100 {
+        let lambda = SampledWavelengths::sample(i as Float / 100.0);
+        let l = lambda.lambda();
+        assert!(l.min_element() >= LAMBDA_MIN && l.max_element() <= LAMBDA_MAX);
+        assert!(lambda.pdf().min_element() > 0.0);
+    }
+}
+
+#[test]
+fn visible_pdf_integrates_to_one() {
+    let steps = 10_000;
+    let dl = (LAMBDA_MAX - LAMBDA_MIN) / steps as Float;
+    let integral = (0..steps)
+        .map(|i| visible_pdf(LAMBDA_MIN + (i as Float + 0.5) * dl))
+        .sum::<Float>() * dl;
+
+    assert!((integral - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn white_emitter_resolves_to_white() {
+    let rgb = mean_rgb(|lambda| lambda.illuminant(Color::WHITE));
+    assert_close(rgb, Vec3::ONE, 0.02);
+}
+
+#[test]
+fn upsampled_colors_roundtrip() {
+    for rgb in [Vec3::new(0.5, 0.2, 0.1), Vec3::new(0.2, 0.8, 0.4), Vec3::new(0.1, 0.1, 0.6)] {
+        let resolved = mean_rgb(|lambda| lambda.illuminant(Color::from(rgb)));
+        assert_close(resolved, rgb, 0.05);
+    }
+}
+
+#[test]
+fn hero_only_keeps_estimate() {
+    let lambda = SampledWavelengths::sample(0.3);
+    let values = Color::from(Vec3::new(0.7, 0.0, 0.0));
+    let xyz = lambda.to_xyz(lambda.hero_only(values));
+    let expected = values.rgb.x * cie_xyz(lambda.hero()) / lambda.pdf().x / CIE_Y_INTEGRAL;
+
+    assert_close(xyz, expected, 1e-10);
+}
+
+#[test]
+fn glasses_disperse() {
+    for glass in [Spectrum::BK7, Spectrum::FUSED_SILICA, Spectrum::SF11, Spectrum::DIAMOND] {
+        let blue = glass.eval(450.0);
+        let red = glass.eval(650.0);
+        assert!(blue > red && red > 1.0);
+    }
+    assert!((Spectrum::BK7.eval(587.6) - 1.5168).abs() < 1e-3);
+}
+
+#[test]
+fn tabulated_interpolates_and_clamps() {
+    let spectrum = Spectrum::Tabulated(&[(400.0, 1.0), (500.0, 3.0)]);
+    assert!((spectrum.eval(450.0) - 2.0).abs() < 1e-12);
+    assert!(spectrum.eval(300.0) == 1.0);
+    assert!(spectrum.eval(900.0) == 3.0);
+}